@@ -0,0 +1,112 @@
+//! Latence des listes de tâches selon le nombre de tâches de l'utilisateur.
+//!
+//! Lancer avec une base de test : `DATABASE_URL=postgres://... cargo bench --bench task_listing`.
+//! Chaque utilisateur créé a des tâches prévues tous les jours, avec trois sous-tâches chacune ;
+//! il est supprimé à la fin.
+
+use axum::{Extension, Router, body::Body, http::Request};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use sqlx::PgPool;
+use task_manager::{db, routes};
+use tokio::runtime::Runtime;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const SIZES: [i64; 3] = [10, 100, 1000];
+
+/// Crée un utilisateur avec `count` tâches de trois sous-tâches chacune
+async fn seed_user(pool: &PgPool, count: i64) -> Uuid {
+    let user_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO users (id, username, password_hash) VALUES ($1, $2, 'bench')")
+        .bind(user_id)
+        .bind(format!("bench-{}", user_id))
+        .execute(pool)
+        .await
+        .unwrap();
+
+    sqlx::query(
+        r#"
+        WITH new_tasks AS (
+            INSERT INTO tasks (user_id, title, has_subtasks)
+            SELECT $1, 'Tâche ' || n, true FROM generate_series(1, $2) AS n
+            RETURNING id
+        ),
+        days AS (
+            INSERT INTO task_days (task_id, day_of_week)
+            SELECT id, d FROM new_tasks, generate_series(1, 7) AS d
+        )
+        INSERT INTO subtasks (task_id, title, priority)
+        SELECT id, 'Étape ' || p, p FROM new_tasks, generate_series(1, 3) AS p
+        "#,
+    )
+    .bind(user_id)
+    .bind(count)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    // Statistiques à jour, comme sur une base en service
+    sqlx::query("ANALYZE tasks, task_days, subtasks")
+        .execute(pool)
+        .await
+        .unwrap();
+
+    user_id
+}
+
+/// Envoie une requête GET au routeur des tâches, sans passer par l'authentification
+async fn get(app: &Router, uri: &str) {
+    let response = app
+        .clone()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert!(
+        response.status().is_success(),
+        "{} -> {}",
+        uri,
+        response.status()
+    );
+    axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+}
+
+fn task_listing(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let pool = rt.block_on(async {
+        let pool = db::init_pool().await;
+        db::run_migrations(&pool).await;
+        pool
+    });
+
+    let mut group = c.benchmark_group("task_listing");
+    let mut users = Vec::new();
+
+    for size in SIZES {
+        let user_id = rt.block_on(seed_user(&pool, size));
+        users.push(user_id);
+        let app = routes::routes(pool.clone()).layer(Extension(user_id));
+
+        group.bench_with_input(BenchmarkId::new("today", size), &app, |b, app| {
+            b.to_async(&rt).iter(|| get(app, "/tasks"));
+        });
+        group.bench_with_input(BenchmarkId::new("all", size), &app, |b, app| {
+            b.to_async(&rt).iter(|| get(app, "/tasks/all?limit=200"));
+        });
+    }
+    group.finish();
+
+    rt.block_on(async {
+        for user_id in users {
+            sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(user_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    });
+}
+
+criterion_group!(benches, task_listing);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+-- =========================
+-- TEAMS
+-- =========================
+CREATE TABLE teams (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL CHECK (length(trim(name)) > 0),
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+-- =========================
+-- TEAM MEMBERS
+-- =========================
+CREATE TABLE team_members (
+    team_id INTEGER NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL DEFAULT 'member' CHECK (role IN ('admin', 'member')),
+    joined_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (team_id, user_id)
+);
+
+-- =========================
+-- TEAM TEMPLATES
+-- =========================
+CREATE TABLE team_templates (
+    id SERIAL PRIMARY KEY,
+    team_id INTEGER NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    title TEXT NOT NULL CHECK (length(trim(title)) > 0),
+    days INTEGER[] NOT NULL DEFAULT '{}',
+    subtasks TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+-- Lien entre la copie d'un membre et le modèle d'équipe d'origine
+ALTER TABLE tasks
+    ADD COLUMN template_id INTEGER REFERENCES team_templates(id) ON DELETE SET NULL;
+
+-- =========================
+-- INDEXES
+-- =========================
+CREATE INDEX idx_team_members_user_id ON team_members(user_id);
+CREATE INDEX idx_team_templates_team_id ON team_templates(team_id);
+CREATE INDEX idx_tasks_template_id ON tasks(template_id);
@@ -0,0 +1,26 @@
+-- =========================
+-- TAGS
+-- =========================
+CREATE TABLE tags (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL CHECK (length(trim(name)) > 0),
+    color TEXT NOT NULL DEFAULT '#6366f1' CHECK (color ~ '^#[0-9a-fA-F]{6}$'),
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (user_id, name)
+);
+
+-- =========================
+-- TASK TAGS
+-- =========================
+CREATE TABLE task_tags (
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (task_id, tag_id)
+);
+
+-- =========================
+-- INDEXES
+-- =========================
+CREATE INDEX idx_tags_user_id ON tags(user_id);
+CREATE INDEX idx_task_tags_tag_id ON task_tags(tag_id);
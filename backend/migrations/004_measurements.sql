@@ -0,0 +1,16 @@
+-- =========================
+-- QUANTITATIVE HABITS
+-- =========================
+-- 'boolean' : fait / pas fait (comportement historique)
+-- 'count', 'duration', 'value' : objectif chiffré atteint quand value >= target
+ALTER TABLE tasks
+    ADD COLUMN measurement_type TEXT NOT NULL DEFAULT 'boolean'
+        CHECK (measurement_type IN ('boolean', 'count', 'duration', 'value')),
+    ADD COLUMN target DOUBLE PRECISION CHECK (target > 0),
+    ADD COLUMN unit TEXT,
+    ADD CONSTRAINT tasks_target_matches_measurement
+        CHECK ((measurement_type = 'boolean') = (target IS NULL));
+
+-- Valeur mesurée pour la journée (NULL pour les tâches booléennes)
+ALTER TABLE task_completions
+    ADD COLUMN value DOUBLE PRECISION CHECK (value >= 0);
@@ -0,0 +1,10 @@
+-- =========================
+-- COMPLETION NOTES
+-- =========================
+ALTER TABLE task_completions
+    ADD COLUMN note TEXT,
+    ADD COLUMN rating SMALLINT CHECK (rating BETWEEN 1 AND 5);
+
+-- Recherche plein texte du journal
+CREATE INDEX idx_task_completions_note_search
+    ON task_completions USING GIN (to_tsvector('simple', COALESCE(note, '')));
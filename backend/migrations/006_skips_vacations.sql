@@ -0,0 +1,61 @@
+-- =========================
+-- SKIPPED OCCURRENCES
+-- =========================
+ALTER TABLE task_completions
+    ADD COLUMN skipped BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN skip_reason TEXT;
+
+-- =========================
+-- VACATIONS
+-- =========================
+CREATE TABLE vacations (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL CHECK (end_date >= start_date),
+    reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+-- Tâches mises en pause (aucune ligne = toutes les tâches de l'utilisateur)
+CREATE TABLE vacation_tasks (
+    vacation_id INTEGER NOT NULL REFERENCES vacations(id) ON DELETE CASCADE,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    PRIMARY KEY (vacation_id, task_id)
+);
+
+CREATE INDEX idx_vacations_user_dates ON vacations(user_id, start_date, end_date);
+
+-- =========================
+-- EXCUSED OCCURRENCES
+-- =========================
+-- Une occurrence non complétée est excusée si elle a été sautée
+-- ou si elle tombe pendant des vacances qui couvrent la tâche.
+-- Les statistiques la retirent alors des occurrences prévues.
+CREATE FUNCTION is_excused(p_task_id INTEGER, p_date DATE) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT NOT EXISTS (
+        SELECT 1 FROM task_completions tc
+        WHERE tc.task_id = p_task_id AND tc.date = p_date AND tc.completed
+    )
+    AND (
+        EXISTS (
+            SELECT 1 FROM task_completions tc
+            WHERE tc.task_id = p_task_id AND tc.date = p_date AND tc.skipped
+        )
+        OR EXISTS (
+            SELECT 1
+            FROM vacations v
+            JOIN tasks t ON t.user_id = v.user_id
+            WHERE t.id = p_task_id
+              AND p_date BETWEEN v.start_date AND v.end_date
+              AND (
+                  NOT EXISTS (SELECT 1 FROM vacation_tasks vt WHERE vt.vacation_id = v.id)
+                  OR EXISTS (
+                      SELECT 1 FROM vacation_tasks vt
+                      WHERE vt.vacation_id = v.id AND vt.task_id = p_task_id
+                  )
+              )
+        )
+    )
+$$;
@@ -0,0 +1,9 @@
+-- =========================
+-- TRASH
+-- =========================
+-- Date de mise à la corbeille, pour la purge définitive après N jours
+ALTER TABLE tasks ADD COLUMN deleted_at TIMESTAMP;
+
+UPDATE tasks SET deleted_at = now() WHERE deleted = true;
+
+CREATE INDEX idx_tasks_deleted_at ON tasks(deleted_at) WHERE deleted = true;
@@ -0,0 +1,61 @@
+-- =========================
+-- AUDIT LOG
+-- =========================
+-- Historique en ajout seul des modifications de tâches, sous-tâches et complétions.
+-- Pas de clé étrangère : l'historique survit à la purge des tâches et des comptes.
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor_id UUID,                  -- NULL pour les tâches de fond
+    owner_id UUID NOT NULL,         -- Propriétaire de la tâche concernée
+    task_id INTEGER NOT NULL,
+    entity TEXT NOT NULL CHECK (entity IN ('task', 'subtask', 'completion')),
+    entity_id TEXT NOT NULL,        -- id de la tâche / sous-tâche, "task_id@date" pour une complétion
+    action TEXT NOT NULL,
+    before JSONB,
+    after JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_audit_log_task_id ON audit_log(task_id, id DESC);
+CREATE INDEX idx_audit_log_owner_id ON audit_log(owner_id);
+
+-- État complet d'une tâche (jours, étiquettes, sous-tâches) tel qu'enregistré dans le journal
+CREATE FUNCTION audit_task_snapshot(p_task_id INTEGER) RETURNS JSONB
+LANGUAGE sql STABLE AS $$
+    SELECT jsonb_build_object(
+        'id', t.id,
+        'title', t.title,
+        'active', t.active,
+        'deleted', t.deleted,
+        'measurement_type', t.measurement_type,
+        'target', t.target,
+        'unit', t.unit,
+        'days', COALESCE((
+            SELECT jsonb_agg(td.day_of_week ORDER BY td.day_of_week)
+            FROM task_days td WHERE td.task_id = t.id
+        ), '[]'),
+        'tags', COALESCE((
+            SELECT jsonb_agg(tt.tag_id ORDER BY tt.tag_id)
+            FROM task_tags tt WHERE tt.task_id = t.id
+        ), '[]'),
+        'subtasks', COALESCE((
+            SELECT jsonb_agg(jsonb_build_object(
+                'id', s.id, 'title', s.title, 'completed', s.completed, 'priority', s.priority
+            ) ORDER BY s.priority, s.id)
+            FROM subtasks s WHERE s.task_id = t.id
+        ), '[]')
+    )
+    FROM tasks t
+    WHERE t.id = p_task_id
+$$;
+
+CREATE FUNCTION audit_log_append_only() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END
+$$;
+
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_append_only();
@@ -0,0 +1,20 @@
+-- =========================
+-- UNDO TOKENS
+-- =========================
+-- Chaque entrée du journal garde l'identifiant de la transaction qui l'a écrite,
+-- pour pouvoir annuler d'un bloc toutes les modifications d'une même requête.
+ALTER TABLE audit_log
+    ADD COLUMN tx_id BIGINT NOT NULL DEFAULT txid_current();
+
+CREATE INDEX idx_audit_log_tx_id ON audit_log(tx_id);
+
+-- Jeton d'annulation renvoyé par une modification, valable pendant UNDO_WINDOW_MINUTES
+CREATE TABLE undo_tokens (
+    token UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tx_id BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    used_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_undo_tokens_created_at ON undo_tokens(created_at);
@@ -0,0 +1,21 @@
+-- =========================
+-- SUBTASK VERSIONS
+-- =========================
+-- Numéro de version incrémenté à chaque modification effective d'une sous-tâche,
+-- pour détecter les modifications concurrentes
+ALTER TABLE subtasks
+    ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+CREATE FUNCTION bump_version() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+    NEW.version := OLD.version + 1;
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER subtasks_bump_version
+    BEFORE UPDATE ON subtasks
+    FOR EACH ROW
+    WHEN (OLD.* IS DISTINCT FROM NEW.*)
+    EXECUTE FUNCTION bump_version();
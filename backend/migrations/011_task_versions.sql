@@ -0,0 +1,37 @@
+-- =========================
+-- TASK VERSIONS
+-- =========================
+-- Version d'une tâche, exposée comme ETag : elle change avec la tâche elle-même
+-- mais aussi avec ses jours, ses étiquettes et ses sous-tâches
+ALTER TABLE tasks
+    ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+CREATE TRIGGER tasks_bump_version
+    BEFORE UPDATE ON tasks
+    FOR EACH ROW
+    WHEN (OLD.* IS DISTINCT FROM NEW.*)
+    EXECUTE FUNCTION bump_version();
+
+CREATE FUNCTION bump_parent_task_version() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        UPDATE tasks SET version = version + 1 WHERE id = OLD.task_id;
+    ELSE
+        UPDATE tasks SET version = version + 1 WHERE id = NEW.task_id;
+    END IF;
+    RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER subtasks_bump_task_version
+    AFTER INSERT OR UPDATE OR DELETE ON subtasks
+    FOR EACH ROW EXECUTE FUNCTION bump_parent_task_version();
+
+CREATE TRIGGER task_days_bump_task_version
+    AFTER INSERT OR UPDATE OR DELETE ON task_days
+    FOR EACH ROW EXECUTE FUNCTION bump_parent_task_version();
+
+CREATE TRIGGER task_tags_bump_task_version
+    AFTER INSERT OR UPDATE OR DELETE ON task_tags
+    FOR EACH ROW EXECUTE FUNCTION bump_parent_task_version();
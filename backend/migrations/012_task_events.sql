@@ -0,0 +1,23 @@
+-- =========================
+-- TASK EVENTS
+-- =========================
+-- Chaque entrée du journal d'audit est aussi publiée sur le canal `task_events`.
+-- La notification n'est délivrée qu'au commit de la transaction, et atteint
+-- toutes les instances du serveur qui écoutent le canal.
+CREATE FUNCTION notify_task_event() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+    PERFORM pg_notify('task_events', json_build_object(
+        'user_id', NEW.owner_id,
+        'entity', NEW.entity,
+        'action', NEW.action,
+        'task_id', NEW.task_id,
+        'entity_id', NEW.entity_id
+    )::text);
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER audit_log_notify
+    AFTER INSERT ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION notify_task_event();
@@ -0,0 +1,22 @@
+-- =========================
+-- OFFLINE SYNC
+-- =========================
+-- Date de la modification côté client pour les mutations reçues par `POST /api/sync`
+-- (NULL : la modification date de son écriture, `created_at`)
+ALTER TABLE audit_log
+    ADD COLUMN changed_at TIMESTAMPTZ;
+
+-- Dernière modification d'un élément, pour résoudre les conflits
+CREATE INDEX idx_audit_log_entity ON audit_log(owner_id, entity, entity_id);
+
+-- Modifications déjà reçues d'un client, pour que les renvois après une coupure
+-- ne soient pas rejoués (le résultat d'origine est renvoyé)
+CREATE TABLE sync_mutations (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    mutation_id UUID NOT NULL,
+    result JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, mutation_id)
+);
+
+CREATE INDEX idx_sync_mutations_created_at ON sync_mutations(created_at);
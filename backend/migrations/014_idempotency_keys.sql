@@ -0,0 +1,19 @@
+-- =========================
+-- IDEMPOTENCY KEYS
+-- =========================
+-- Réponse mémorisée d'une requête POST envoyée avec un en-tête `Idempotency-Key`,
+-- renvoyée telle quelle si le client rejoue la requête (status NULL : en cours)
+CREATE TABLE idempotency_keys (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    request_hash TEXT NOT NULL,
+    status SMALLINT,
+    headers JSONB,
+    body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, key)
+);
+
+CREATE INDEX idx_idempotency_keys_created_at ON idempotency_keys(created_at);
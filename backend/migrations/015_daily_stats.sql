@@ -0,0 +1,216 @@
+-- =========================
+-- DAILY STATS
+-- =========================
+-- Bilan de chaque tâche pour chaque jour passé (ou aujourd'hui), tel qu'il était ce jour-là :
+-- une ligne par jour où la tâche était prévue ou a une complétion.
+-- `scheduled` : jour prévu, tâche active et non supprimée, occurrence non excusée.
+-- Les sous-tâches sont l'état de la liste lors du dernier calcul de la journée.
+CREATE TABLE task_daily_stats (
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    scheduled BOOLEAN NOT NULL,
+    completed BOOLEAN NOT NULL,
+    value DOUBLE PRECISION NOT NULL DEFAULT 0,
+    subtasks_total INTEGER NOT NULL DEFAULT 0,
+    subtasks_completed INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (task_id, date)
+);
+
+CREATE INDEX idx_task_daily_stats_user_date ON task_daily_stats(user_id, date);
+
+-- Somme des bilans des tâches de l'utilisateur pour chaque jour.
+-- Une ligne existe dès que la journée a été calculée, même sans tâche prévue :
+-- toutes les tâches de l'utilisateur ont alors leur bilan pour ce jour.
+-- Les unités (`*_units`) sont celles de la heatmap : une tâche prévue compte avec
+-- ses sous-tâches restantes, une tâche complétée avec ses sous-tâches complétées.
+CREATE TABLE user_daily_stats (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    scheduled_tasks INTEGER NOT NULL,
+    -- Tâches prévues et complétées
+    done_tasks INTEGER NOT NULL,
+    -- Toutes les complétions du jour, prévues ou non
+    completed_tasks INTEGER NOT NULL,
+    scheduled_units INTEGER NOT NULL,
+    completed_units INTEGER NOT NULL,
+    PRIMARY KEY (user_id, date)
+);
+
+-- Bilans recalculés depuis les données brutes (toutes les tâches si `p_task_id` est NULL)
+CREATE FUNCTION compute_task_daily_stats(p_user_id UUID, p_task_id INTEGER, p_from DATE, p_to DATE)
+RETURNS SETOF task_daily_stats
+LANGUAGE sql STABLE AS $$
+    SELECT t.id, t.user_id, d.day::date,
+           td.task_id IS NOT NULL AND t.active AND NOT t.deleted AND NOT is_excused(t.id, d.day::date),
+           COALESCE(tc.completed, false),
+           COALESCE(tc.value, 0),
+           st.total::int,
+           st.done::int
+    FROM tasks t
+    CROSS JOIN generate_series(p_from, p_to, interval '1 day') AS d(day)
+    LEFT JOIN task_days td ON td.task_id = t.id AND td.day_of_week = extract(isodow from d.day)
+    LEFT JOIN task_completions tc ON tc.task_id = t.id AND tc.date = d.day::date
+    CROSS JOIN LATERAL (
+        SELECT COUNT(*) AS total, COUNT(*) FILTER (WHERE s.completed) AS done
+        FROM subtasks s WHERE s.task_id = t.id
+    ) st
+    WHERE t.user_id = p_user_id
+      AND (p_task_id IS NULL OR t.id = p_task_id)
+      AND (td.task_id IS NOT NULL OR tc.task_id IS NOT NULL)
+$$;
+
+-- Recalcule les bilans d'une tâche (ou de toutes) entre deux dates, puis les totaux
+-- de l'utilisateur pour ces jours. Les jours futurs sont ignorés.
+CREATE FUNCTION refresh_daily_stats(p_user_id UUID, p_task_id INTEGER, p_from DATE, p_to DATE)
+RETURNS void
+LANGUAGE plpgsql AS $$
+BEGIN
+    p_to := LEAST(p_to, current_date);
+    -- L'utilisateur a pu être supprimé avant un déclencheur différé
+    IF p_from > p_to OR NOT EXISTS (SELECT 1 FROM users WHERE id = p_user_id) THEN
+        RETURN;
+    END IF;
+
+    DELETE FROM task_daily_stats
+    WHERE user_id = p_user_id
+      AND (p_task_id IS NULL OR task_id = p_task_id)
+      AND date BETWEEN p_from AND p_to;
+
+    INSERT INTO task_daily_stats
+    SELECT * FROM compute_task_daily_stats(p_user_id, p_task_id, p_from, p_to)
+    ON CONFLICT (task_id, date) DO UPDATE SET
+        scheduled = EXCLUDED.scheduled,
+        completed = EXCLUDED.completed,
+        value = EXCLUDED.value,
+        subtasks_total = EXCLUDED.subtasks_total,
+        subtasks_completed = EXCLUDED.subtasks_completed;
+
+    INSERT INTO user_daily_stats
+        (user_id, date, scheduled_tasks, done_tasks, completed_tasks, scheduled_units, completed_units)
+    SELECT p_user_id, d.day::date,
+           COUNT(s.task_id) FILTER (WHERE s.scheduled),
+           COUNT(s.task_id) FILTER (WHERE s.scheduled AND s.completed),
+           COUNT(s.task_id) FILTER (WHERE s.completed),
+           COALESCE(SUM(1 + s.subtasks_total - s.subtasks_completed) FILTER (WHERE s.scheduled), 0),
+           COALESCE(SUM(1 + s.subtasks_completed) FILTER (WHERE s.completed), 0)
+    FROM generate_series(p_from, p_to, interval '1 day') AS d(day)
+    LEFT JOIN task_daily_stats s ON s.user_id = p_user_id AND s.date = d.day::date
+    GROUP BY d.day
+    ON CONFLICT (user_id, date) DO UPDATE SET
+        scheduled_tasks = EXCLUDED.scheduled_tasks,
+        done_tasks = EXCLUDED.done_tasks,
+        completed_tasks = EXCLUDED.completed_tasks,
+        scheduled_units = EXCLUDED.scheduled_units,
+        completed_units = EXCLUDED.completed_units;
+END
+$$;
+
+-- Met à jour le bilan d'une tâche pour un jour. Si la journée de l'utilisateur
+-- n'a pas encore été calculée, elle l'est entièrement.
+CREATE FUNCTION refresh_task_daily_stats(p_task_id INTEGER, p_date DATE)
+RETURNS void
+LANGUAGE plpgsql AS $$
+DECLARE
+    v_user_id UUID;
+BEGIN
+    -- Tâche supprimée définitivement : ses bilans partent avec elle
+    SELECT user_id INTO v_user_id FROM tasks WHERE id = p_task_id;
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM user_daily_stats WHERE user_id = v_user_id AND date = p_date) THEN
+        PERFORM refresh_daily_stats(v_user_id, p_task_id, p_date, p_date);
+    ELSE
+        PERFORM refresh_daily_stats(v_user_id, NULL, p_date, p_date);
+    END IF;
+END
+$$;
+
+-- Calcule les journées manquantes d'un utilisateur entre deux dates
+CREATE FUNCTION ensure_daily_stats(p_user_id UUID, p_from DATE, p_to DATE)
+RETURNS void
+LANGUAGE plpgsql AS $$
+DECLARE
+    v_day DATE;
+BEGIN
+    FOR v_day IN
+        SELECT d.day::date
+        FROM generate_series(p_from, LEAST(p_to, current_date), interval '1 day') AS d(day)
+        WHERE NOT EXISTS (
+            SELECT 1 FROM user_daily_stats u WHERE u.user_id = p_user_id AND u.date = d.day::date
+        )
+    LOOP
+        PERFORM refresh_daily_stats(p_user_id, NULL, v_day, v_day);
+    END LOOP;
+END
+$$;
+
+-- =========================
+-- INCREMENTAL UPDATES
+-- =========================
+-- Complétions, valeurs et occurrences sautées : le jour concerné
+CREATE FUNCTION task_completions_refresh_stats() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM refresh_task_daily_stats(OLD.task_id, OLD.date);
+    END IF;
+    IF TG_OP = 'INSERT' OR (TG_OP = 'UPDATE' AND (NEW.task_id, NEW.date) <> (OLD.task_id, OLD.date)) THEN
+        PERFORM refresh_task_daily_stats(NEW.task_id, NEW.date);
+    END IF;
+    RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER task_completions_stats
+    AFTER INSERT OR DELETE OR UPDATE OF task_id, date, completed, value, skipped ON task_completions
+    FOR EACH ROW EXECUTE FUNCTION task_completions_refresh_stats();
+
+-- Sous-tâches, jours prévus, archivage et suppression : la journée en cours
+-- (les jours passés restent tels qu'ils étaient)
+CREATE FUNCTION refresh_today_stats() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+    IF TG_TABLE_NAME = 'tasks' THEN
+        PERFORM refresh_task_daily_stats(NEW.id, current_date);
+    ELSIF TG_OP = 'DELETE' THEN
+        PERFORM refresh_task_daily_stats(OLD.task_id, current_date);
+    ELSE
+        PERFORM refresh_task_daily_stats(NEW.task_id, current_date);
+    END IF;
+    RETURN NULL;
+END
+$$;
+
+CREATE TRIGGER subtasks_stats
+    AFTER INSERT OR DELETE OR UPDATE OF completed ON subtasks
+    FOR EACH ROW EXECUTE FUNCTION refresh_today_stats();
+
+CREATE TRIGGER task_days_stats
+    AFTER INSERT OR DELETE ON task_days
+    FOR EACH ROW EXECUTE FUNCTION refresh_today_stats();
+
+CREATE TRIGGER tasks_stats
+    AFTER UPDATE OF active, deleted ON tasks
+    FOR EACH ROW EXECUTE FUNCTION refresh_today_stats();
+
+-- Vacances : tous les jours couverts, au commit, une fois les tâches concernées enregistrées
+CREATE FUNCTION vacations_refresh_stats() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM refresh_daily_stats(OLD.user_id, NULL, OLD.start_date, OLD.end_date);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM refresh_daily_stats(NEW.user_id, NULL, NEW.start_date, NEW.end_date);
+    END IF;
+    RETURN NULL;
+END
+$$;
+
+CREATE CONSTRAINT TRIGGER vacations_stats
+    AFTER INSERT OR UPDATE OR DELETE ON vacations
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION vacations_refresh_stats();
@@ -0,0 +1,94 @@
+-- =========================
+-- SCHEDULE HISTORY
+-- =========================
+-- Jours prévus de chaque tâche avec leur période de validité : `task_days` reste le
+-- planning actuel, cette table garde aussi les plannings précédents.
+-- `effective_from` est inclus, `effective_to` exclu (NULL : toujours en vigueur).
+CREATE TABLE task_schedules (
+    id SERIAL PRIMARY KEY,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    day_of_week INTEGER NOT NULL CHECK (day_of_week BETWEEN 1 AND 7),
+    effective_from DATE NOT NULL,
+    effective_to DATE CHECK (effective_to > effective_from)
+);
+
+CREATE INDEX idx_task_schedules_task_id ON task_schedules(task_id);
+CREATE UNIQUE INDEX idx_task_schedules_current ON task_schedules(task_id, day_of_week)
+    WHERE effective_to IS NULL;
+
+-- Les plannings existants sont considérés en vigueur depuis la création de la tâche
+INSERT INTO task_schedules (task_id, day_of_week, effective_from)
+SELECT td.task_id, td.day_of_week, t.created_at::date
+FROM task_days td
+JOIN tasks t ON t.id = td.task_id;
+
+-- Un changement de `task_days` prend effet le jour même. Un jour ajouté puis retiré
+-- le même jour ne laisse pas de trace ; retiré puis remis, il reprend sa période.
+CREATE FUNCTION task_days_track_schedule() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        DELETE FROM task_schedules
+        WHERE task_id = OLD.task_id AND day_of_week = OLD.day_of_week
+          AND effective_to IS NULL AND effective_from >= current_date;
+
+        UPDATE task_schedules SET effective_to = current_date
+        WHERE task_id = OLD.task_id AND day_of_week = OLD.day_of_week
+          AND effective_to IS NULL;
+        RETURN OLD;
+    END IF;
+
+    UPDATE task_schedules SET effective_to = NULL
+    WHERE task_id = NEW.task_id AND day_of_week = NEW.day_of_week
+      AND effective_to = current_date;
+
+    IF NOT FOUND THEN
+        INSERT INTO task_schedules (task_id, day_of_week, effective_from)
+        VALUES (NEW.task_id, NEW.day_of_week, current_date);
+    END IF;
+    RETURN NEW;
+END
+$$;
+
+-- Nommé pour passer avant `task_days_stats`, qui lit le planning
+CREATE TRIGGER task_days_history
+    AFTER INSERT OR DELETE ON task_days
+    FOR EACH ROW EXECUTE FUNCTION task_days_track_schedule();
+
+-- =========================
+-- DAILY STATS
+-- =========================
+-- Les bilans jugent chaque jour avec le planning de ce jour-là
+CREATE OR REPLACE FUNCTION compute_task_daily_stats(p_user_id UUID, p_task_id INTEGER, p_from DATE, p_to DATE)
+RETURNS SETOF task_daily_stats
+LANGUAGE sql STABLE AS $$
+    SELECT t.id, t.user_id, d.day::date,
+           sch.task_id IS NOT NULL AND t.active AND NOT t.deleted AND NOT is_excused(t.id, d.day::date),
+           COALESCE(tc.completed, false),
+           COALESCE(tc.value, 0),
+           st.total::int,
+           st.done::int
+    FROM tasks t
+    CROSS JOIN generate_series(p_from, p_to, interval '1 day') AS d(day)
+    LEFT JOIN task_schedules sch ON sch.task_id = t.id
+        AND sch.day_of_week = extract(isodow from d.day)
+        AND d.day::date >= sch.effective_from
+        AND (sch.effective_to IS NULL OR d.day::date < sch.effective_to)
+        AND d.day::date >= t.created_at::date
+    LEFT JOIN task_completions tc ON tc.task_id = t.id AND tc.date = d.day::date
+    CROSS JOIN LATERAL (
+        SELECT COUNT(*) AS total, COUNT(*) FILTER (WHERE s.completed) AS done
+        FROM subtasks s WHERE s.task_id = t.id
+    ) st
+    WHERE t.user_id = p_user_id
+      AND (p_task_id IS NULL OR t.id = p_task_id)
+      AND (sch.task_id IS NOT NULL OR tc.task_id IS NOT NULL)
+$$;
+
+-- Les bilans déjà calculés pour des jours antérieurs à la création d'une tâche
+-- la comptaient comme prévue : ces journées sont recalculées
+SELECT refresh_daily_stats(s.user_id, NULL, MIN(s.date), MAX(s.date))
+FROM task_daily_stats s
+JOIN tasks t ON t.id = s.task_id
+WHERE s.scheduled AND s.date < t.created_at::date
+GROUP BY s.user_id;
@@ -0,0 +1,71 @@
+-- =========================
+-- REMINDERS
+-- =========================
+-- Heures de rappel d'une tâche, dans la journée de l'utilisateur (celle des complétions)
+CREATE TABLE task_reminders (
+    id SERIAL PRIMARY KEY,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    remind_at TIME NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (task_id, remind_at)
+);
+
+-- =========================
+-- NOTIFICATION SETTINGS
+-- =========================
+-- Canaux (webhook, e-mail), heures calmes et heure du récapitulatif quotidien.
+-- Les heures calmes peuvent passer minuit (22:00 - 07:00).
+CREATE TABLE notification_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    webhook_url TEXT,
+    email TEXT,
+    quiet_start TIME,
+    quiet_end TIME,
+    digest_at TIME,
+    CHECK ((quiet_start IS NULL) = (quiet_end IS NULL))
+);
+
+-- Abonnements Web Push des navigateurs de l'utilisateur
+CREATE TABLE push_subscriptions (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    endpoint TEXT NOT NULL UNIQUE,
+    p256dh TEXT NOT NULL,
+    auth TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_push_subscriptions_user_id ON push_subscriptions(user_id);
+
+-- =========================
+-- NOTIFICATIONS
+-- =========================
+-- Notifications envoyées : un rappel par occurrence, un récapitulatif par jour.
+-- Les index uniques empêchent un second envoi, même avec plusieurs instances.
+CREATE TABLE notifications (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('reminder', 'digest')),
+    task_id INTEGER REFERENCES tasks(id) ON DELETE CASCADE,
+    reminder_id INTEGER REFERENCES task_reminders(id) ON DELETE SET NULL,
+    date DATE NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_notifications_user_created ON notifications(user_id, created_at DESC);
+CREATE UNIQUE INDEX idx_notifications_reminder_date ON notifications(reminder_id, date)
+    WHERE kind = 'reminder';
+CREATE UNIQUE INDEX idx_notifications_digest_date ON notifications(user_id, date)
+    WHERE kind = 'digest';
+
+-- L'heure est-elle dans les heures calmes (aucune si elles ne sont pas définies) ?
+CREATE FUNCTION in_quiet_hours(p_time TIME, p_start TIME, p_end TIME) RETURNS BOOLEAN
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT CASE
+        WHEN p_start IS NULL OR p_end IS NULL THEN false
+        WHEN p_start <= p_end THEN p_time >= p_start AND p_time < p_end
+        ELSE p_time >= p_start OR p_time < p_end
+    END
+$$;
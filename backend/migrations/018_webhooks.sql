@@ -0,0 +1,107 @@
+-- =========================
+-- WEBHOOKS
+-- =========================
+-- Webhooks sortants de l'utilisateur. `events` vide = tous les événements.
+-- Le secret signe chaque envoi (HMAC-SHA256).
+CREATE TABLE webhooks (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    events TEXT[] NOT NULL DEFAULT '{}',
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_webhooks_user_id ON webhooks(user_id);
+
+-- File d'envoi persistante : une ligne par événement et par webhook.
+-- `next_attempt_at` porte à la fois l'attente entre deux essais et la réservation
+-- d'un envoi en cours par une instance.
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    -- Journée résumée, pour n'envoyer qu'un `daily.summary` par jour
+    summary_date DATE,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    delivered_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_webhook_deliveries_pending ON webhook_deliveries(next_attempt_at)
+    WHERE status = 'pending';
+CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, id DESC);
+CREATE UNIQUE INDEX idx_webhook_deliveries_summary ON webhook_deliveries(webhook_id, summary_date)
+    WHERE event = 'daily.summary';
+
+-- Journal des essais d'envoi
+CREATE TABLE webhook_delivery_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    delivery_id BIGINT NOT NULL REFERENCES webhook_deliveries(id) ON DELETE CASCADE,
+    attempted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    status_code INTEGER,
+    error TEXT,
+    duration_ms INTEGER NOT NULL
+);
+
+CREATE INDEX idx_webhook_delivery_attempts_delivery ON webhook_delivery_attempts(delivery_id);
+
+-- =========================
+-- EVENTS
+-- =========================
+-- Événement webhook d'une entrée du journal d'audit (NULL : pas d'événement) :
+-- tâche créée, modifiée ou supprimée, complétion cochée ou décochée
+CREATE FUNCTION webhook_event(p_entry audit_log) RETURNS TEXT
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT CASE
+        WHEN p_entry.entity = 'task' AND p_entry.before IS NULL THEN 'task.created'
+        WHEN p_entry.entity = 'task'
+             AND COALESCE((p_entry.after->>'deleted')::boolean, true)
+             AND NOT COALESCE((p_entry.before->>'deleted')::boolean, false) THEN 'task.deleted'
+        -- Purge d'une tâche déjà dans la corbeille : déjà signalée
+        WHEN p_entry.entity = 'task' AND p_entry.after IS NULL THEN NULL
+        WHEN p_entry.entity = 'task' THEN 'task.updated'
+        WHEN p_entry.entity = 'completion'
+             AND COALESCE((p_entry.before->>'completed')::boolean, false)
+                 IS DISTINCT FROM COALESCE((p_entry.after->>'completed')::boolean, false)
+            THEN 'completion.toggled'
+    END
+$$;
+
+-- Met en file l'événement pour chaque webhook actif de l'utilisateur qui l'écoute,
+-- dans la transaction de la modification
+CREATE FUNCTION enqueue_webhook_deliveries() RETURNS trigger
+LANGUAGE plpgsql AS $$
+DECLARE
+    v_event TEXT := webhook_event(NEW);
+BEGIN
+    IF v_event IS NULL THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO webhook_deliveries (webhook_id, event, payload)
+    SELECT w.id, v_event, jsonb_build_object(
+        'event', v_event,
+        'occurred_at', NEW.created_at,
+        'task_id', NEW.task_id,
+        'actor_id', NEW.actor_id,
+        'action', NEW.action,
+        'before', NEW.before,
+        'after', NEW.after
+    )
+    FROM webhooks w
+    WHERE w.user_id = NEW.owner_id
+      AND w.active
+      AND (cardinality(w.events) = 0 OR v_event = ANY(w.events));
+
+    RETURN NEW;
+END
+$$;
+
+CREATE TRIGGER audit_log_webhooks
+    AFTER INSERT ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION enqueue_webhook_deliveries();
@@ -0,0 +1,23 @@
+-- =========================
+-- TASK TRIGGERS
+-- =========================
+-- URL secrètes qui agissent sur la complétion du jour d'une tâche sans connexion
+-- (raccourcis téléphone, tags NFC, boutons connectés). Seul le hash du jeton est
+-- conservé : l'URL n'est affichée qu'à la création.
+CREATE TABLE task_triggers (
+    id SERIAL PRIMARY KEY,
+    task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    token_hash BYTEA NOT NULL UNIQUE,
+    name TEXT,
+    action TEXT NOT NULL CHECK (action IN ('toggle', 'complete', 'uncomplete', 'increment')),
+    -- Valeur ajoutée par `increment` (tâches chiffrées)
+    amount DOUBLE PRECISION CHECK ((action = 'increment') = (amount IS NOT NULL)),
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMPTZ,
+    use_count INTEGER NOT NULL DEFAULT 0,
+    -- Fenêtre de limitation de débit en cours et appels reçus dans cette fenêtre
+    window_start TIMESTAMPTZ NOT NULL DEFAULT now(),
+    window_calls INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_task_triggers_task_id ON task_triggers(task_id);
@@ -0,0 +1,9 @@
+-- =========================
+-- USER ADMINISTRATION
+-- =========================
+-- Compte désactivé par un administrateur : connexion et jetons refusés
+ALTER TABLE users ADD COLUMN disabled_at TIMESTAMPTZ;
+
+-- Les jetons émis avant cette date sont refusés (mot de passe réinitialisé,
+-- compte désactivé)
+ALTER TABLE users ADD COLUMN tokens_valid_after TIMESTAMPTZ;
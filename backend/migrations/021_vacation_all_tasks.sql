@@ -0,0 +1,40 @@
+-- =========================
+-- VACATIONS : TOUTES LES TÂCHES
+-- =========================
+-- La pause de toutes les tâches est explicite. Avant, l'absence de lignes dans
+-- vacation_tasks en tenait lieu : la purge de la seule tâche d'une pause ciblée
+-- (ON DELETE CASCADE) l'étendait à toutes les tâches de l'utilisateur.
+-- Les pauses existantes avec une sélection ne couvrent qu'elle. Une pause déjà vidée
+-- par une purge ne se distingue plus d'une pause sans sélection et garde toutes les tâches.
+ALTER TABLE vacations ADD COLUMN all_tasks BOOLEAN NOT NULL DEFAULT true;
+
+UPDATE vacations v SET all_tasks = false
+WHERE EXISTS (SELECT 1 FROM vacation_tasks vt WHERE vt.vacation_id = v.id);
+
+CREATE OR REPLACE FUNCTION is_excused(p_task_id INTEGER, p_date DATE) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT NOT EXISTS (
+        SELECT 1 FROM task_completions tc
+        WHERE tc.task_id = p_task_id AND tc.date = p_date AND tc.completed
+    )
+    AND (
+        EXISTS (
+            SELECT 1 FROM task_completions tc
+            WHERE tc.task_id = p_task_id AND tc.date = p_date AND tc.skipped
+        )
+        OR EXISTS (
+            SELECT 1
+            FROM vacations v
+            JOIN tasks t ON t.user_id = v.user_id
+            WHERE t.id = p_task_id
+              AND p_date BETWEEN v.start_date AND v.end_date
+              AND (
+                  v.all_tasks
+                  OR EXISTS (
+                      SELECT 1 FROM vacation_tasks vt
+                      WHERE vt.vacation_id = v.id AND vt.task_id = p_task_id
+                  )
+              )
+        )
+    )
+$$;
@@ -0,0 +1,6 @@
+-- =========================
+-- IDEMPOTENCY CLAIMS
+-- =========================
+-- Début de la réservation d'une clé par la requête en cours (status NULL). Une réservation
+-- restée en suspens (processus arrêté avant la fin) est reprise passé un délai.
+ALTER TABLE idempotency_keys ADD COLUMN claimed_at TIMESTAMPTZ NOT NULL DEFAULT now();
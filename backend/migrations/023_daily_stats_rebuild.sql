@@ -0,0 +1,121 @@
+-- =========================
+-- DAILY STATS REBUILD
+-- =========================
+-- Les bilans peuvent être recalculés à tout moment depuis les données brutes
+-- (reconstruction périodique des derniers jours, historique manquant). Ni l'archivage
+-- ni les sous-tâches n'ont d'historique : un jour passé déjà calculé garde l'état
+-- enregistré ce jour-là, le reste (planning, complétions, excuses) est recalculé.
+-- `active` : tâche active et non supprimée ce jour-là. Les jours prévus d'une tâche
+-- archivée ont aussi leur ligne, pour s'en souvenir.
+ALTER TABLE task_daily_stats ADD COLUMN active BOOLEAN NOT NULL DEFAULT true;
+
+-- Jour au planning, non excusé, mais pas compté : la tâche était archivée ou supprimée
+UPDATE task_daily_stats s SET active = false
+FROM tasks t
+WHERE t.id = s.task_id
+  AND NOT s.scheduled
+  AND s.date >= t.created_at::date
+  AND EXISTS (
+      SELECT 1 FROM task_schedules sch
+      WHERE sch.task_id = s.task_id
+        AND sch.day_of_week = extract(isodow from s.date)
+        AND s.date >= sch.effective_from
+        AND (sch.effective_to IS NULL OR s.date < sch.effective_to)
+  )
+  AND NOT is_excused(s.task_id, s.date);
+
+CREATE OR REPLACE FUNCTION compute_task_daily_stats(p_user_id UUID, p_task_id INTEGER, p_from DATE, p_to DATE)
+RETURNS SETOF task_daily_stats
+LANGUAGE sql STABLE AS $$
+    SELECT t.id, t.user_id, d.day::date,
+           sch.task_id IS NOT NULL
+               AND COALESCE(old.active, t.active AND NOT t.deleted)
+               AND NOT is_excused(t.id, d.day::date),
+           COALESCE(tc.completed, false),
+           COALESCE(tc.value, 0),
+           COALESCE(old.subtasks_total, st.total::int),
+           COALESCE(old.subtasks_completed, st.done::int),
+           COALESCE(old.active, t.active AND NOT t.deleted)
+    FROM tasks t
+    CROSS JOIN generate_series(p_from, p_to, interval '1 day') AS d(day)
+    LEFT JOIN task_schedules sch ON sch.task_id = t.id
+        AND sch.day_of_week = extract(isodow from d.day)
+        AND d.day::date >= sch.effective_from
+        AND (sch.effective_to IS NULL OR d.day::date < sch.effective_to)
+        AND d.day::date >= t.created_at::date
+    LEFT JOIN task_completions tc ON tc.task_id = t.id AND tc.date = d.day::date
+    -- État enregistré d'un jour passé
+    LEFT JOIN task_daily_stats old ON old.task_id = t.id AND old.date = d.day::date
+        AND d.day::date < current_date
+    CROSS JOIN LATERAL (
+        SELECT COUNT(*) AS total, COUNT(*) FILTER (WHERE s.completed) AS done
+        FROM subtasks s WHERE s.task_id = t.id
+    ) st
+    WHERE t.user_id = p_user_id
+      AND (p_task_id IS NULL OR t.id = p_task_id)
+      AND (sch.task_id IS NOT NULL OR tc.task_id IS NOT NULL)
+$$;
+
+-- Remplace les bilans de la période par le recalcul, qui lit ceux qu'il remplace
+CREATE OR REPLACE FUNCTION refresh_daily_stats(p_user_id UUID, p_task_id INTEGER, p_from DATE, p_to DATE)
+RETURNS void
+LANGUAGE plpgsql AS $$
+BEGIN
+    p_to := LEAST(p_to, current_date);
+    -- L'utilisateur a pu être supprimé avant un déclencheur différé
+    IF p_from > p_to OR NOT EXISTS (SELECT 1 FROM users WHERE id = p_user_id) THEN
+        RETURN;
+    END IF;
+
+    WITH computed AS MATERIALIZED (
+        SELECT * FROM compute_task_daily_stats(p_user_id, p_task_id, p_from, p_to)
+    ), removed AS (
+        DELETE FROM task_daily_stats s
+        WHERE s.user_id = p_user_id
+          AND (p_task_id IS NULL OR s.task_id = p_task_id)
+          AND s.date BETWEEN p_from AND p_to
+          AND NOT EXISTS (SELECT 1 FROM computed c WHERE c.task_id = s.task_id AND c.date = s.date)
+    )
+    INSERT INTO task_daily_stats
+    SELECT * FROM computed
+    ON CONFLICT (task_id, date) DO UPDATE SET
+        scheduled = EXCLUDED.scheduled,
+        completed = EXCLUDED.completed,
+        value = EXCLUDED.value,
+        subtasks_total = EXCLUDED.subtasks_total,
+        subtasks_completed = EXCLUDED.subtasks_completed,
+        active = EXCLUDED.active;
+
+    INSERT INTO user_daily_stats
+        (user_id, date, scheduled_tasks, done_tasks, completed_tasks, scheduled_units, completed_units)
+    SELECT p_user_id, d.day::date,
+           COUNT(s.task_id) FILTER (WHERE s.scheduled),
+           COUNT(s.task_id) FILTER (WHERE s.scheduled AND s.completed),
+           COUNT(s.task_id) FILTER (WHERE s.completed),
+           COALESCE(SUM(1 + s.subtasks_total - s.subtasks_completed) FILTER (WHERE s.scheduled), 0),
+           COALESCE(SUM(1 + s.subtasks_completed) FILTER (WHERE s.completed), 0)
+    FROM generate_series(p_from, p_to, interval '1 day') AS d(day)
+    LEFT JOIN task_daily_stats s ON s.user_id = p_user_id AND s.date = d.day::date
+    GROUP BY d.day
+    ON CONFLICT (user_id, date) DO UPDATE SET
+        scheduled_tasks = EXCLUDED.scheduled_tasks,
+        done_tasks = EXCLUDED.done_tasks,
+        completed_tasks = EXCLUDED.completed_tasks,
+        scheduled_units = EXCLUDED.scheduled_units,
+        completed_units = EXCLUDED.completed_units;
+END
+$$;
+
+-- Premier jour de l'historique d'un utilisateur : création de sa première tâche,
+-- ou complétion antérieure (NULL sans tâche)
+CREATE FUNCTION stats_start_date(p_user_id UUID) RETURNS DATE
+LANGUAGE sql STABLE AS $$
+    SELECT LEAST(MIN(t.created_at)::date, MIN(tc.date))
+    FROM tasks t
+    LEFT JOIN task_completions tc ON tc.task_id = t.id
+    WHERE t.user_id = p_user_id
+$$;
+
+-- Les journées jamais calculées (avant l'introduction des bilans, serveur arrêté)
+-- manquaient aux totaux des statistiques
+SELECT ensure_daily_stats(id, stats_start_date(id), current_date) FROM users;
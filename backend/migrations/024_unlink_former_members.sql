@@ -0,0 +1,12 @@
+-- =========================
+-- UNLINK FORMER MEMBERS
+-- =========================
+-- Les copies de modèles d'un membre qui a quitté l'équipe deviennent des tâches
+-- personnelles : la modification d'un modèle ne se répercute plus sur elles.
+UPDATE tasks t SET template_id = NULL
+FROM team_templates tt
+WHERE tt.id = t.template_id
+  AND NOT EXISTS (
+      SELECT 1 FROM team_members m
+      WHERE m.team_id = tt.team_id AND m.user_id = t.user_id
+  );
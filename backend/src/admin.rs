@@ -0,0 +1,148 @@
+//! Opérations d'administration lancées par les sous-commandes du binaire `task-manager`.
+
+use sqlx::PgPool;
+use sqlx::types::JsonValue;
+use uuid::Uuid;
+
+use crate::auth::hash_password;
+
+/// Utilisateur désigné par son identifiant ou son nom
+pub async fn find_user(pool: &PgPool, user: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    let id = user.parse::<Uuid>().ok();
+    sqlx::query_scalar!(
+        "SELECT id FROM users WHERE id = $1 OR ($1 IS NULL AND username = $2)",
+        id,
+        user
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Crée un compte, `None` si le nom est déjà pris
+pub async fn create_user(
+    pool: &PgPool,
+    username: &str,
+    password: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO users (id, username, password_hash) VALUES ($1, $2, $3)
+        ON CONFLICT (username) DO NOTHING
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        username,
+        hash_password(password)
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Remplace le mot de passe et révoque les jetons déjà émis. `iat` étant à la seconde
+/// près, la révocation couvre toute la seconde en cours (y compris un jeton émis juste après)
+pub async fn reset_password(
+    pool: &PgPool,
+    user_id: Uuid,
+    password: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET password_hash = $2,
+            tokens_valid_after = date_trunc('second', now()) + interval '1 second'
+        WHERE id = $1
+        "#,
+        user_id,
+        hash_password(password)
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Désactive un compte (connexion, jetons, URL de déclenchement et notifications
+/// refusés) ou le réactive
+pub async fn set_disabled(pool: &PgPool, user_id: Uuid, disabled: bool) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE users SET
+            disabled_at = CASE WHEN $2 THEN COALESCE(disabled_at, now()) END,
+            tokens_valid_after = CASE WHEN $2 THEN date_trunc('second', now()) + interval '1 second'
+                                      ELSE tokens_valid_after END
+        WHERE id = $1
+        "#,
+        user_id,
+        disabled
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Toutes les données d'un utilisateur en JSON : compte, tâches (avec jours, sous-tâches
+/// et étiquettes), complétions, étiquettes, vacances, rappels et réglages de notification,
+/// webhooks et URL de déclenchement (sans leurs secrets)
+pub async fn export_user(pool: &PgPool, user_id: Uuid) -> Result<JsonValue, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT jsonb_build_object(
+            'exported_at', now(),
+            'user', jsonb_build_object(
+                'id', u.id,
+                'username', u.username,
+                'created_at', u.created_at,
+                'disabled_at', u.disabled_at
+            ),
+            'tasks', COALESCE((
+                SELECT jsonb_agg(audit_task_snapshot(t.id) || jsonb_build_object(
+                           'created_at', t.created_at,
+                           'deleted_at', t.deleted_at
+                       ) ORDER BY t.id)
+                FROM tasks t WHERE t.user_id = u.id
+            ), '[]'),
+            'completions', COALESCE((
+                SELECT jsonb_agg(to_jsonb(tc) ORDER BY tc.task_id, tc.date)
+                FROM task_completions tc JOIN tasks t ON t.id = tc.task_id
+                WHERE t.user_id = u.id
+            ), '[]'),
+            'tags', COALESCE((
+                SELECT jsonb_agg(to_jsonb(g) ORDER BY g.id) FROM tags g WHERE g.user_id = u.id
+            ), '[]'),
+            'vacations', COALESCE((
+                SELECT jsonb_agg(to_jsonb(v) || jsonb_build_object(
+                           'task_ids', (SELECT jsonb_agg(vt.task_id ORDER BY vt.task_id)
+                                        FROM vacation_tasks vt WHERE vt.vacation_id = v.id)
+                       ) ORDER BY v.start_date)
+                FROM vacations v WHERE v.user_id = u.id
+            ), '[]'),
+            'reminders', COALESCE((
+                SELECT jsonb_agg(to_jsonb(r) ORDER BY r.task_id, r.remind_at)
+                FROM task_reminders r JOIN tasks t ON t.id = r.task_id
+                WHERE t.user_id = u.id
+            ), '[]'),
+            'notification_settings', (
+                SELECT to_jsonb(s) - 'user_id' FROM notification_settings s WHERE s.user_id = u.id
+            ),
+            'webhooks', COALESCE((
+                SELECT jsonb_agg(to_jsonb(w) - 'secret' - 'user_id' ORDER BY w.id)
+                FROM webhooks w WHERE w.user_id = u.id
+            ), '[]'),
+            'triggers', COALESCE((
+                SELECT jsonb_agg(jsonb_build_object(
+                           'id', tr.id, 'task_id', tr.task_id, 'name', tr.name,
+                           'action', tr.action, 'amount', tr.amount, 'created_at', tr.created_at
+                       ) ORDER BY tr.id)
+                FROM task_triggers tr JOIN tasks t ON t.id = tr.task_id
+                WHERE t.user_id = u.id
+            ), '[]')
+        ) AS "export!"
+        FROM users u
+        WHERE u.id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+}
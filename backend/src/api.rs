@@ -0,0 +1,66 @@
+//! Versions de l'API. Chaque version est un routeur complet (routes publiques et
+//! authentifiées) monté sous `/api/vN` ; `/api` reste un alias déprécié de la v1.
+
+use axum::Router;
+use axum::middleware::{from_fn, from_fn_with_state};
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+
+use crate::events::TaskEvent;
+use crate::routes_audit::audit_routes;
+use crate::routes_auth::auth_routes;
+use crate::routes_batch::batch_routes;
+use crate::routes_events::event_routes;
+use crate::routes_journal::journal_routes;
+use crate::routes_notifications::notification_routes;
+use crate::routes_sync::sync_routes;
+use crate::routes_tags::tag_routes;
+use crate::routes_teams::team_routes;
+use crate::routes_triggers::{public_trigger_routes, trigger_routes};
+use crate::routes_undo::undo_routes;
+use crate::routes_vacations::vacation_routes;
+use crate::routes_webhooks::webhook_routes;
+use crate::{idempotency, middleware, openapi, routes};
+
+/// Toutes les versions de l'API et leur documentation. Une nouvelle version se monte
+/// à côté de la v1 (`.nest("/api/v2", v2(...))`) sans toucher aux précédentes.
+pub fn router(pool: PgPool, events: broadcast::Sender<TaskEvent>) -> Router {
+    let v1 = v1(pool, events);
+
+    Router::new()
+        .nest("/api/v1", v1.clone())
+        .nest("/api", v1.layer(from_fn(middleware::deprecated_api)))
+        .merge(openapi::docs_routes())
+        .merge(openapi::legacy_docs_routes().layer(from_fn(middleware::deprecated_api)))
+}
+
+/// Version 1 : inscription, connexion et URL de déclenchement publiques,
+/// le reste authentifié par le jeton de session
+pub fn v1(pool: PgPool, events: broadcast::Sender<TaskEvent>) -> Router {
+    Router::new()
+        .nest("/auth", auth_routes(pool.clone()))
+        .nest(
+            "/triggers",
+            public_trigger_routes(pool.clone()).route_layer(from_fn_with_state(
+                pool.clone(),
+                idempotency::trigger_idempotency,
+            )),
+        )
+        .merge(
+            routes::routes(pool.clone())
+                .merge(team_routes(pool.clone()))
+                .merge(tag_routes(pool.clone()))
+                .merge(journal_routes(pool.clone()))
+                .merge(vacation_routes(pool.clone()))
+                .merge(notification_routes(pool.clone()))
+                .merge(trigger_routes(pool.clone()))
+                .merge(webhook_routes(pool.clone()))
+                .merge(audit_routes(pool.clone()))
+                .merge(undo_routes(pool.clone()))
+                .merge(sync_routes(pool.clone()))
+                .merge(batch_routes(pool.clone()))
+                .merge(event_routes(events))
+                .layer(from_fn_with_state(pool.clone(), idempotency::idempotency))
+                .layer(from_fn_with_state(pool, middleware::auth)),
+        )
+}
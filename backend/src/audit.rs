@@ -0,0 +1,211 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{PgConnection, types::JsonValue};
+use uuid::Uuid;
+
+/// Une ligne du journal d'audit
+pub struct AuditEntry<'a> {
+    pub actor_id: Option<Uuid>,
+    pub task_id: i32,
+    pub entity: &'a str,
+    pub entity_id: String,
+    pub action: &'a str,
+    pub before: Option<JsonValue>,
+    pub after: Option<JsonValue>,
+    pub changed_at: Option<DateTime<Utc>>,
+}
+
+impl<'a> AuditEntry<'a> {
+    /// Modification d'une tâche (acteur `None` pour les tâches de fond)
+    pub fn task(actor_id: impl Into<Option<Uuid>>, task_id: i32, action: &'a str) -> Self {
+        Self {
+            actor_id: actor_id.into(),
+            task_id,
+            entity: "task",
+            entity_id: task_id.to_string(),
+            action,
+            before: None,
+            after: None,
+            changed_at: None,
+        }
+    }
+
+    /// Modification d'une sous-tâche
+    pub fn subtask(actor_id: impl Into<Option<Uuid>>, task_id: i32, subtask_id: i32, action: &'a str) -> Self {
+        Self {
+            entity: "subtask",
+            entity_id: subtask_id.to_string(),
+            ..Self::task(actor_id, task_id, action)
+        }
+    }
+
+    /// Modification de la complétion d'une tâche pour une date
+    pub fn completion(actor_id: impl Into<Option<Uuid>>, task_id: i32, date: NaiveDate, action: &'a str) -> Self {
+        Self {
+            entity: "completion",
+            entity_id: format!("{}@{}", task_id, date),
+            ..Self::task(actor_id, task_id, action)
+        }
+    }
+
+    pub fn before(mut self, before: Option<JsonValue>) -> Self {
+        self.before = before;
+        self
+    }
+
+    pub fn after(mut self, after: Option<JsonValue>) -> Self {
+        self.after = after;
+        self
+    }
+
+    /// Date de la modification côté client (mutations de synchronisation hors ligne)
+    pub fn changed_at(mut self, changed_at: DateTime<Utc>) -> Self {
+        self.changed_at = Some(changed_at);
+        self
+    }
+}
+
+/// Date d'une occurrence : celle fournie, ou la date du jour côté base
+/// (les complétions utilisent `current_date`)
+pub async fn resolve_date(
+    conn: &mut PgConnection,
+    date: Option<NaiveDate>,
+) -> Result<NaiveDate, StatusCode> {
+    sqlx::query_scalar!(r#"SELECT COALESCE($1, current_date) as "date!""#, date)
+        .fetch_one(conn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Comme `resolve_date`, mais refuse une date future (400). Le jour courant est celui
+/// de la base, comme pour les déclencheurs et les statistiques.
+pub async fn resolve_past_date(
+    conn: &mut PgConnection,
+    date: Option<NaiveDate>,
+) -> Result<NaiveDate, StatusCode> {
+    sqlx::query_scalar!(
+        r#"SELECT COALESCE($1, current_date) as "date!" WHERE $1 IS NULL OR $1 <= current_date"#,
+        date
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::BAD_REQUEST)
+}
+
+/// Ajoute une entrée au journal, dans la transaction de la modification
+pub async fn record(conn: &mut PgConnection, entry: AuditEntry<'_>) -> Result<(), StatusCode> {
+    // Rien à tracer si la modification n'a rien changé
+    if entry.before.is_some() && entry.before == entry.after {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (actor_id, owner_id, task_id, entity, entity_id, action, before, after, changed_at)
+        SELECT $1, user_id, id, $3, $4, $5, $6, $7, $8 FROM tasks WHERE id = $2
+        "#,
+        entry.actor_id,
+        entry.task_id,
+        entry.entity,
+        entry.entity_id,
+        entry.action,
+        entry.before,
+        entry.after,
+        entry.changed_at
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur journal d'audit: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
+
+/// État complet d'une tâche (jours, étiquettes, sous-tâches) pour le journal,
+/// voir la fonction SQL `audit_task_snapshot`
+pub async fn task_snapshot(
+    conn: &mut PgConnection,
+    task_id: i32,
+) -> Result<Option<JsonValue>, StatusCode> {
+    sqlx::query_scalar!(
+        r#"SELECT audit_task_snapshot(id) as "snapshot!" FROM tasks WHERE id = $1"#,
+        task_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur capture tâche: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Trace la suppression définitive de tâches, juste avant le `DELETE`
+/// (acteur `None` pour la purge automatique de la corbeille)
+pub async fn record_purge(
+    conn: &mut PgConnection,
+    actor_id: Option<Uuid>,
+    task_ids: &[i32],
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (actor_id, owner_id, task_id, entity, entity_id, action, before)
+        SELECT $1, user_id, id, 'task', id::text, 'purge', audit_task_snapshot(id)
+        FROM tasks WHERE id = ANY($2)
+        "#,
+        actor_id,
+        task_ids
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// État d'une sous-tâche pour le journal
+pub async fn subtask_snapshot(
+    conn: &mut PgConnection,
+    subtask_id: i32,
+) -> Result<Option<JsonValue>, StatusCode> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT jsonb_build_object(
+            'id', id, 'title', title, 'completed', completed, 'priority', priority
+        ) as "snapshot!"
+        FROM subtasks
+        WHERE id = $1
+        "#,
+        subtask_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur capture sous-tâche: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// État de la complétion d'une tâche à une date pour le journal
+pub async fn completion_snapshot(
+    conn: &mut PgConnection,
+    task_id: i32,
+    date: NaiveDate,
+) -> Result<Option<JsonValue>, StatusCode> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT to_jsonb(tc) - 'task_id' as "snapshot!"
+        FROM task_completions tc
+        WHERE tc.task_id = $1 AND tc.date = $2
+        "#,
+        task_id,
+        date
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur capture complétion: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
@@ -5,10 +5,15 @@ use serde::{Deserialize, Serialize};
 use std::env;
 use uuid::Uuid;
 
+/// Longueur minimale d'un mot de passe
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
 #[derive(Serialize, Deserialize)]
 pub struct Claims {
     pub sub: Uuid,
     pub exp: usize,
+    #[serde(default)] // Date d'émission, absente des jetons plus anciens
+    pub iat: usize,
 }
 
 pub fn hash_password(password: &str) -> String {
@@ -28,9 +33,11 @@ pub fn verify_password(password: &str, hash: &str) -> bool {
 
 pub fn create_jwt(user_id: Uuid) -> String {
     let secret = env::var("JWT_SECRET").expect("JWT_SECRET not set");
+    let now = chrono::Utc::now().timestamp() as usize;
     let claims = Claims {
         sub: user_id,
-        exp: chrono::Utc::now().timestamp() as usize + 60 * 60 * 24,
+        exp: now + 60 * 60 * 24,
+        iat: now,
     };
 
     encode(
@@ -0,0 +1,70 @@
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::CliResult;
+
+/// Client de l'API REST, authentifié par le jeton de la dernière connexion
+pub struct Client {
+    http: reqwest::Client,
+    base: String,
+    token: Option<String>,
+}
+
+impl Client {
+    pub fn new(server: &str, token: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base: format!("{}/api/v1", server.trim_end_matches('/')),
+            token,
+        }
+    }
+
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Envoie la requête ; un code d'erreur devient un message lisible
+    pub async fn send(&self, request: RequestBuilder) -> CliResult<Response> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        if status == StatusCode::UNAUTHORIZED && self.token.is_some() {
+            return Err("session expirée : relancez `taskctl login`".into());
+        }
+        if status == StatusCode::UNAUTHORIZED {
+            return Err("non connecté : lancez `taskctl login`".into());
+        }
+        let body = response.text().await.unwrap_or_default();
+        let detail = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["error"].as_str().map(str::to_string))
+            .unwrap_or(body);
+        Err(format!("le serveur a répondu {} {}", status, detail.trim()).into())
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> CliResult<T> {
+        Ok(self
+            .send(self.request(Method::GET, path))
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// Envoie un corps JSON, la réponse éventuelle est ignorée
+    pub async fn send_json<B: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &B,
+    ) -> CliResult<Response> {
+        self.send(self.request(method, path).json(body)).await
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::CliResult;
+
+/// Serveur utilisé sans `--server` ni connexion préalable
+pub const DEFAULT_SERVER: &str = "http://localhost:3000";
+
+/// Fichier de configuration : serveur et jeton de la dernière connexion
+#[derive(Serialize, Deserialize, Default)]
+pub struct Config {
+    pub server: Option<String>,
+    pub username: Option<String>,
+    pub token: Option<String>,
+}
+
+impl Config {
+    /// `$TASKCTL_CONFIG`, sinon `taskctl/config.json` dans le dossier de configuration
+    /// de l'utilisateur (`~/.config` sous Linux)
+    pub fn path() -> CliResult<PathBuf> {
+        if let Some(path) = std::env::var_os("TASKCTL_CONFIG") {
+            return Ok(PathBuf::from(path));
+        }
+        let dir = dirs::config_dir().ok_or("dossier de configuration introuvable")?;
+        Ok(dir.join("taskctl").join("config.json"))
+    }
+
+    /// Configuration enregistrée, vide si le fichier n'existe pas encore
+    pub fn load() -> CliResult<Self> {
+        let path = Self::path()?;
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("{} illisible : {}", path.display(), e).into()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Enregistre la configuration, lisible par l'utilisateur seul (elle contient le jeton)
+    pub fn save(&self) -> CliResult<PathBuf> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,70 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Deserialize;
+
+/// Taux de complétion d'une journée, tel que renvoyé par `GET /stats`
+#[derive(Deserialize)]
+pub struct DayPercent {
+    pub date: NaiveDate,
+    pub percent: i32,
+}
+
+/// Symbole d'une journée selon son taux de complétion
+fn cell(percent: i32) -> char {
+    match percent {
+        p if p <= 0 => '.',
+        p if p < 34 => '-',
+        p if p < 67 => '+',
+        p if p < 100 => '*',
+        _ => '#',
+    }
+}
+
+pub const LEGEND: &str = "  . 0 %   - < 34 %   + < 67 %   * < 100 %   # 100 %";
+
+/// Heatmap en ASCII : une ligne par jour de la semaine, une colonne par semaine,
+/// les mois en en-tête
+pub fn render(history: &[DayPercent]) -> String {
+    let (Some(first), Some(last)) = (history.first(), history.last()) else {
+        return String::new();
+    };
+    // Colonnes alignées sur les lundis
+    let start = first.date - Duration::days(first.date.weekday().num_days_from_monday() as i64);
+    let weeks = ((last.date - start).num_days() / 7 + 1) as usize;
+
+    let mut grid = vec![vec![' '; weeks]; 7];
+    for day in history {
+        let offset = (day.date - start).num_days() as usize;
+        grid[offset % 7][offset / 7] = cell(day.percent);
+    }
+
+    // Nom du mois au-dessus de la première semaine qui le contient
+    let mut header = vec![' '; weeks * 2 + 2];
+    let mut previous_month = None;
+    let mut free_from = 0;
+    for week in 0..weeks {
+        let monday = start + Duration::days(week as i64 * 7);
+        let month = monday.max(first.date).month0();
+        if previous_month != Some(month) && week * 2 >= free_from {
+            header.splice(week * 2..week * 2 + 3, MONTHS[month as usize].chars());
+            free_from = week * 2 + 4;
+        }
+        previous_month = Some(month);
+    }
+
+    let mut output = format!("     {}\n", header.iter().collect::<String>().trim_end());
+    for (weekday, row) in grid.iter().enumerate() {
+        let cells: Vec<String> = row.iter().map(char::to_string).collect();
+        output.push_str(&format!(
+            "{}  {}\n",
+            WEEKDAYS[weekday],
+            cells.join(" ").trim_end()
+        ));
+    }
+    output
+}
+
+const WEEKDAYS: [&str; 7] = ["Lun", "Mar", "Mer", "Jeu", "Ven", "Sam", "Dim"];
+
+const MONTHS: [&str; 12] = [
+    "jan", "fév", "mar", "avr", "mai", "jun", "jul", "aoû", "sep", "oct", "nov", "déc",
+];
@@ -0,0 +1,639 @@
+//! `taskctl` : client en ligne de commande de l'API du gestionnaire de tâches.
+//! Les corps de requête reprennent les types de `task_manager::models`.
+
+mod client;
+mod config;
+mod heatmap;
+
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use task_manager::models::{
+    AuthResponse, BatchOperation, BatchRequest, CreateTaskRequest, LogValueRequest, LoginRequest,
+    SetArchivedRequest, SetCompletionRequest, SubtaskUpdate, Task, UpdateTaskRequest,
+};
+
+use client::Client;
+use config::{Config, DEFAULT_SERVER};
+use heatmap::DayPercent;
+
+pub type CliResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// Opérations par requête `POST /batch` (limite du serveur)
+const BATCH_SIZE: usize = 200;
+
+#[derive(Parser)]
+#[command(
+    name = "taskctl",
+    version,
+    about = "Client en ligne de commande du gestionnaire de tâches"
+)]
+struct Cli {
+    /// URL du serveur (par défaut celle de la dernière connexion, sinon http://localhost:3000)
+    #[arg(long, global = true, env = "TASKCTL_SERVER")]
+    server: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Se connecter et enregistrer le jeton dans le fichier de configuration
+    /// (mot de passe demandé, ou lu dans `TASKCTL_PASSWORD`)
+    Login {
+        #[arg(short, long)]
+        username: Option<String>,
+    },
+    /// Oublier le jeton enregistré
+    Logout,
+    /// Afficher les tâches du jour
+    Today,
+    /// Marquer une tâche du jour comme faite (par id ou par titre)
+    Done {
+        task: String,
+        /// Décocher la tâche
+        #[arg(long)]
+        undo: bool,
+        /// Ajouter cette valeur à la mesure du jour (tâches chiffrées)
+        #[arg(long, conflicts_with = "undo")]
+        value: Option<f64>,
+    },
+    /// Créer une tâche
+    Add {
+        title: String,
+        /// Jours : `daily`, `weekdays`, `weekend` ou une liste (`mon,wed,fri`, `1,3,5`)
+        #[arg(short, long, default_value = "daily")]
+        days: String,
+        /// Sous-tâche (option répétable)
+        #[arg(short, long = "subtask")]
+        subtasks: Vec<String>,
+        /// Type de mesure : `count`, `duration` ou `value` (case à cocher par défaut)
+        #[arg(long = "type", requires = "target")]
+        measurement_type: Option<String>,
+        /// Objectif journalier d'une tâche chiffrée
+        #[arg(long)]
+        target: Option<f64>,
+        #[arg(long)]
+        unit: Option<String>,
+    },
+    /// Modifier le titre, les jours, les sous-tâches ou l'objectif d'une tâche
+    Edit {
+        task: String,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(short, long)]
+        days: Option<String>,
+        /// Sous-tâche à ajouter (option répétable)
+        #[arg(long = "add-subtask")]
+        add_subtasks: Vec<String>,
+        #[arg(long)]
+        target: Option<f64>,
+        #[arg(long)]
+        unit: Option<String>,
+    },
+    /// Archiver une tâche, ou la réactiver avec `--undo`
+    Archive {
+        task: String,
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Statistiques et heatmap des derniers jours
+    Stats {
+        #[arg(long, default_value_t = 91, value_parser = clap::value_parser!(i32).range(1..=366))]
+        days: i32,
+    },
+    /// Exporter les tâches en JSON (même format que la page de gestion)
+    Export {
+        /// Fichier de sortie (sortie standard par défaut)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Importer les tâches d'un fichier JSON exporté
+    Import { file: PathBuf },
+}
+
+/// Tâche du jour (`GET /tasks`)
+#[derive(Deserialize)]
+struct TodayTask {
+    #[serde(flatten)]
+    task: Task,
+    #[serde(default)]
+    skipped: bool,
+    #[serde(default)]
+    paused: bool,
+    value: Option<f64>,
+    target: Option<f64>,
+    unit: Option<String>,
+}
+
+/// Tâche avec sa version et sa mesure (`GET /tasks/:id`, `GET /tasks/all`)
+#[derive(Deserialize)]
+struct VersionedTask {
+    #[serde(flatten)]
+    task: Task,
+    version: i32,
+    measurement_type: String,
+    target: Option<f64>,
+    unit: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TaskPage {
+    tasks: Vec<VersionedTask>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StatsResponse {
+    history: Vec<DayPercent>,
+    summary: StatsSummary,
+}
+
+#[derive(Deserialize)]
+struct StatsSummary {
+    total_created: i64,
+    total_completed_ever: i64,
+    success_rate: i32,
+    today_percent: i32,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    committed: bool,
+}
+
+/// Fichier d'export, compatible avec l'import de la page de gestion
+#[derive(Serialize, Deserialize)]
+struct Export {
+    version: String,
+    #[serde(rename = "exportDate")]
+    export_date: String,
+    tasks: Vec<ExportedTask>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedTask {
+    title: String,
+    days: Vec<i32>,
+    #[serde(default)]
+    subtasks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    measurement_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unit: Option<String>,
+}
+
+/// Un export complet, ou simplement une liste de tâches
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ImportFile {
+    Export(Export),
+    Tasks(Vec<ExportedTask>),
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("Erreur : {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> CliResult<()> {
+    let mut config = Config::load()?;
+    let server = cli
+        .server
+        .clone()
+        .or_else(|| config.server.clone())
+        .unwrap_or_else(|| DEFAULT_SERVER.to_string());
+    let client = Client::new(&server, config.token.clone());
+
+    match cli.command {
+        Command::Login { username } => login(&client, &mut config, server, username).await,
+        Command::Logout => {
+            config.token = None;
+            config.save()?;
+            println!("Déconnecté");
+            Ok(())
+        }
+        Command::Today => today(&client).await,
+        Command::Done { task, undo, value } => done(&client, &task, undo, value).await,
+        Command::Add {
+            title,
+            days,
+            subtasks,
+            measurement_type,
+            target,
+            unit,
+        } => {
+            let request = CreateTaskRequest {
+                title,
+                days: parse_days(&days)?,
+                subtasks: (!subtasks.is_empty()).then_some(subtasks),
+                tags: None,
+                measurement_type,
+                target,
+                unit,
+            };
+            client.send_json(Method::POST, "/tasks", &request).await?;
+            println!("Tâche « {} » créée", request.title);
+            Ok(())
+        }
+        Command::Edit {
+            task,
+            title,
+            days,
+            add_subtasks,
+            target,
+            unit,
+        } => edit(&client, &task, title, days, add_subtasks, target, unit).await,
+        Command::Archive { task, undo } => {
+            let tasks = all_tasks(&client).await?;
+            let task = find_task(tasks.iter().map(|t| &t.task), &task)?;
+            let request = SetArchivedRequest { archived: !undo };
+            client
+                .send_json(Method::PUT, &format!("/tasks/{}/archived", task.id), &request)
+                .await?;
+            let state = if undo { "réactivée" } else { "archivée" };
+            println!("Tâche « {} » {}", task.title, state);
+            Ok(())
+        }
+        Command::Stats { days } => stats(&client, days).await,
+        Command::Export { output } => export(&client, output).await,
+        Command::Import { file } => import(&client, &file).await,
+    }
+}
+
+// --- COMMANDES ---
+
+async fn login(
+    client: &Client,
+    config: &mut Config,
+    server: String,
+    username: Option<String>,
+) -> CliResult<()> {
+    let username = match username.or_else(|| config.username.clone()) {
+        Some(username) => username,
+        None => {
+            print!("Nom d'utilisateur : ");
+            std::io::stdout().flush()?;
+            let mut line = String::new();
+            std::io::stdin().lock().read_line(&mut line)?;
+            line.trim().to_string()
+        }
+    };
+    let password = match std::env::var("TASKCTL_PASSWORD") {
+        Ok(password) => password,
+        Err(_) => rpassword::prompt_password("Mot de passe : ")?,
+    };
+
+    let request = LoginRequest {
+        username: username.clone(),
+        password,
+    };
+    let auth: AuthResponse = client
+        .send_json(Method::POST, "/auth/login", &request)
+        .await?
+        .json()
+        .await?;
+
+    config.server = Some(server);
+    config.username = Some(username.clone());
+    config.token = Some(auth.token);
+    let path = config.save()?;
+    println!("Connecté en tant que {} (jeton enregistré dans {})", username, path.display());
+    Ok(())
+}
+
+async fn today(client: &Client) -> CliResult<()> {
+    let tasks: Vec<TodayTask> = client.get("/tasks").await?;
+    if tasks.is_empty() {
+        println!("Aucune tâche prévue aujourd'hui");
+        return Ok(());
+    }
+
+    let done = tasks.iter().filter(|t| t.task.completed).count();
+    println!("Aujourd'hui : {}/{} faite(s)", done, tasks.len());
+    for today in &tasks {
+        let task = &today.task;
+        let mark = match () {
+            _ if task.completed => "[x]",
+            _ if today.skipped || today.paused => "[~]",
+            _ => "[ ]",
+        };
+        let mut line = format!("{} {:>4}  {}", mark, task.id, task.title);
+        if let Some(target) = today.target {
+            let unit = today.unit.as_deref().unwrap_or("");
+            line.push_str(&format!(
+                "  ({}/{} {})",
+                today.value.unwrap_or(0.0),
+                target,
+                unit
+            ));
+        }
+        if today.skipped {
+            line.push_str("  (sautée)");
+        } else if today.paused {
+            line.push_str("  (en pause)");
+        }
+        println!("{}", line.trim_end());
+        for subtask in &task.subtasks {
+            let mark = if subtask.completed { "[x]" } else { "[ ]" };
+            println!("          {} {}", mark, subtask.title);
+        }
+    }
+    Ok(())
+}
+
+async fn done(client: &Client, query: &str, undo: bool, value: Option<f64>) -> CliResult<()> {
+    let tasks: Vec<TodayTask> = client.get("/tasks").await?;
+    let task = find_task(tasks.iter().map(|t| &t.task), query)?;
+    // Seule une tâche chiffrée a un objectif
+    let measured = tasks.iter().any(|t| t.task.id == task.id && t.target.is_some());
+
+    if let Some(value) = value {
+        let request = LogValueRequest {
+            value,
+            date: None,
+            increment: Some(true),
+        };
+        let logged: serde_json::Value = client
+            .send_json(Method::POST, &format!("/tasks/{}/log", task.id), &request)
+            .await?
+            .json()
+            .await?;
+        println!(
+            "« {} » : {} / {}{}",
+            task.title,
+            logged["value"],
+            logged["target"],
+            if logged["completed"] == true { ", faite" } else { "" }
+        );
+        return Ok(());
+    }
+    if measured {
+        return Err("tâche chiffrée : indiquer la valeur avec --value".into());
+    }
+
+    let request = SetCompletionRequest {
+        date: None,
+        completed: !undo,
+    };
+    client
+        .send_json(Method::PUT, &format!("/tasks/{}/completion", task.id), &request)
+        .await?;
+    let state = if undo { "décochée" } else { "faite" };
+    println!("« {} » {}", task.title, state);
+    Ok(())
+}
+
+async fn edit(
+    client: &Client,
+    query: &str,
+    title: Option<String>,
+    days: Option<String>,
+    add_subtasks: Vec<String>,
+    target: Option<f64>,
+    unit: Option<String>,
+) -> CliResult<()> {
+    let tasks = all_tasks(client).await?;
+    let id = find_task(tasks.iter().map(|t| &t.task), query)?.id;
+    // Version à jour, pour l'en-tête `If-Match`
+    let current: VersionedTask = client.get(&format!("/tasks/{}", id)).await?;
+
+    let mut changes = UpdateTaskRequest {
+        title,
+        days: days.as_deref().map(parse_days).transpose()?,
+        ..Default::default()
+    };
+    if !add_subtasks.is_empty() {
+        let mut subtasks: Vec<SubtaskUpdate> = current
+            .task
+            .subtasks
+            .iter()
+            .map(|s| SubtaskUpdate {
+                id: Some(s.id),
+                title: s.title.clone(),
+                completed: None,
+                version: Some(s.version),
+            })
+            .collect();
+        subtasks.extend(add_subtasks.into_iter().map(|title| SubtaskUpdate {
+            id: None,
+            title,
+            completed: None,
+            version: None,
+        }));
+        changes.subtasks = Some(subtasks);
+    }
+    // L'objectif et l'unité s'envoient avec le type de mesure
+    if target.is_some() || unit.is_some() {
+        if current.measurement_type == "boolean" {
+            return Err("seule une tâche chiffrée a un objectif".into());
+        }
+        changes.measurement_type = Some(current.measurement_type);
+        changes.target = target.or(current.target);
+        changes.unit = unit.or(current.unit);
+    }
+
+    let request = client
+        .request(Method::POST, &format!("/tasks/{}", id))
+        .header("If-Match", format!("\"{}\"", current.version))
+        .json(&changes);
+    client.send(request).await?;
+    println!("Tâche « {} » modifiée", changes.title.unwrap_or(current.task.title));
+    Ok(())
+}
+
+async fn stats(client: &Client, days: i32) -> CliResult<()> {
+    let stats: StatsResponse = client.get(&format!("/stats?days={}", days)).await?;
+
+    print!("{}", heatmap::render(&stats.history));
+    println!("{}", heatmap::LEGEND);
+    println!();
+    println!("Aujourd'hui     : {} %", stats.summary.today_percent);
+    println!("Taux de réussite : {} %", stats.summary.success_rate);
+    println!("Tâches créées   : {}", stats.summary.total_created);
+    println!("Complétions     : {}", stats.summary.total_completed_ever);
+    Ok(())
+}
+
+async fn export(client: &Client, output: Option<PathBuf>) -> CliResult<()> {
+    let tasks = all_tasks(client).await?;
+    let export = Export {
+        version: "1.0".to_string(),
+        export_date: Utc::now().to_rfc3339(),
+        tasks: tasks
+            .into_iter()
+            .map(|t| {
+                let measured = t.measurement_type != "boolean";
+                ExportedTask {
+                    title: t.task.title,
+                    days: t.task.days,
+                    subtasks: t.task.subtasks.into_iter().map(|s| s.title).collect(),
+                    measurement_type: measured.then_some(t.measurement_type),
+                    target: t.target.filter(|_| measured),
+                    unit: t.unit.filter(|_| measured),
+                }
+            })
+            .collect(),
+    };
+
+    let json = serde_json::to_string_pretty(&export)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, json)?;
+            eprintln!("{} tâche(s) exportée(s) dans {}", export.tasks.len(), path.display());
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+async fn import(client: &Client, file: &PathBuf) -> CliResult<()> {
+    let content = std::fs::read_to_string(file)?;
+    let tasks = match serde_json::from_str::<ImportFile>(&content)
+        .map_err(|_| format!("{} : fichier d'export invalide", file.display()))?
+    {
+        ImportFile::Export(export) => export.tasks,
+        ImportFile::Tasks(tasks) => tasks,
+    };
+
+    let mut operations: Vec<BatchOperation> = tasks
+        .into_iter()
+        .map(|t| {
+            BatchOperation::Create(CreateTaskRequest {
+                title: t.title,
+                days: t.days,
+                subtasks: (!t.subtasks.is_empty()).then_some(t.subtasks),
+                tags: None,
+                measurement_type: t.measurement_type,
+                target: t.target,
+                unit: t.unit,
+            })
+        })
+        .collect();
+
+    let mut imported = 0;
+    while !operations.is_empty() {
+        let chunk: Vec<BatchOperation> = operations
+            .drain(..operations.len().min(BATCH_SIZE))
+            .collect();
+        let count = chunk.len();
+        let request = BatchRequest {
+            operations: chunk,
+            atomic: Some(true),
+        };
+        let response: BatchResponse = client
+            .send_json(Method::POST, "/batch", &request)
+            .await?
+            .json()
+            .await?;
+        if !response.committed {
+            return Err(format!(
+                "import interrompu après {} tâche(s) : une tâche du lot suivant est invalide",
+                imported
+            )
+            .into());
+        }
+        imported += count;
+    }
+
+    println!("{} tâche(s) importée(s)", imported);
+    Ok(())
+}
+
+// --- OUTILS ---
+
+/// Toutes les tâches non supprimées, en suivant les pages de `GET /tasks/all`
+async fn all_tasks(client: &Client) -> CliResult<Vec<VersionedTask>> {
+    let mut tasks = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let path = match &cursor {
+            Some(cursor) => format!("/tasks/all?limit=200&cursor={}", cursor),
+            None => "/tasks/all?limit=200".to_string(),
+        };
+        let page: TaskPage = client.get(&path).await?;
+        tasks.extend(page.tasks);
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(tasks),
+        }
+    }
+}
+
+/// Tâche désignée par son id, son titre exact ou une partie unique de son titre
+/// (sans tenir compte de la casse)
+fn find_task<'a>(tasks: impl IntoIterator<Item = &'a Task>, query: &str) -> CliResult<&'a Task> {
+    let tasks: Vec<&Task> = tasks.into_iter().collect();
+    if let Ok(id) = query.parse::<i32>()
+        && let Some(task) = tasks.iter().find(|t| t.id == id)
+    {
+        return Ok(task);
+    }
+
+    let needle = query.to_lowercase();
+    if let Some(task) = tasks.iter().find(|t| t.title.to_lowercase() == needle) {
+        return Ok(task);
+    }
+    let matches: Vec<&&Task> = tasks
+        .iter()
+        .filter(|t| t.title.to_lowercase().contains(&needle))
+        .collect();
+    match matches.as_slice() {
+        [task] => Ok(task),
+        [] => Err(format!("aucune tâche ne correspond à « {} »", query).into()),
+        _ => {
+            let names: Vec<String> = matches
+                .iter()
+                .map(|t| format!("{} ({})", t.title, t.id))
+                .collect();
+            Err(format!("« {} » est ambigu : {}", query, names.join(", ")).into())
+        }
+    }
+}
+
+/// Jours de la semaine (1 = lundi … 7 = dimanche) : `daily`, `weekdays`, `weekend`
+/// ou une liste de numéros et de noms (anglais ou français, abrégés)
+fn parse_days(value: &str) -> CliResult<Vec<i32>> {
+    let mut days = match value.trim().to_lowercase().as_str() {
+        "daily" | "tous" => (1..=7).collect(),
+        "weekdays" | "semaine" => (1..=5).collect(),
+        "weekend" => vec![6, 7],
+        list => list
+            .split(',')
+            .map(|day| parse_day(day.trim()).ok_or_else(|| format!("jour inconnu : « {} »", day)))
+            .collect::<Result<Vec<i32>, String>>()?,
+    };
+    days.sort();
+    days.dedup();
+    Ok(days)
+}
+
+fn parse_day(day: &str) -> Option<i32> {
+    if let Ok(number) = day.parse::<i32>() {
+        return (1..=7).contains(&number).then_some(number);
+    }
+    const NAMES: [[&str; 2]; 7] = [
+        ["mon", "lun"],
+        ["tue", "mar"],
+        ["wed", "mer"],
+        ["thu", "jeu"],
+        ["fri", "ven"],
+        ["sat", "sam"],
+        ["sun", "dim"],
+    ];
+    let prefix = day.get(..3)?;
+    NAMES
+        .iter()
+        .position(|names| names.contains(&prefix))
+        .map(|index| index as i32 + 1)
+}
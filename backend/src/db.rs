@@ -1,3 +1,4 @@
+use axum::http::StatusCode;
 use sqlx::PgPool;
 use sqlx::postgres::PgPoolOptions; // Recommandé pour configurer le pool
 use std::env;
@@ -20,5 +21,17 @@ pub async fn run_migrations(pool: &PgPool) {
         .await
         .expect("Failed to run migrations");
 
-    println!("Migrations executed successfully");
+    eprintln!("Migrations executed successfully");
+}
+
+/// Code HTTP d'une écriture refusée par la base : 409 si elle viole une contrainte
+/// d'unicité (doublon), 500 pour toute autre erreur
+pub fn conflict_status(e: sqlx::Error, context: &str) -> StatusCode {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => StatusCode::CONFLICT,
+        _ => {
+            eprintln!("Erreur {}: {}", context, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
 }
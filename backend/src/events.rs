@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Canal Postgres alimenté par le déclencheur `audit_log_notify`
+pub const CHANNEL: &str = "task_events";
+
+/// Modification d'une tâche, d'une sous-tâche ou d'une complétion,
+/// telle que publiée par le journal d'audit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskEvent {
+    pub user_id: Uuid,
+    pub entity: String,
+    pub action: String,
+    pub task_id: i32,
+    pub entity_id: String,
+}
+
+/// Écoute le canal `task_events` et relaie les événements aux flux ouverts sur cette instance.
+/// La connexion est rétablie en cas d'erreur.
+pub fn spawn_listener(pool: PgPool) -> broadcast::Sender<TaskEvent> {
+    let (sender, _) = broadcast::channel(256);
+    let events = sender.clone();
+
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = listen(&pool, &events).await {
+                eprintln!("Erreur écoute des événements: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    });
+
+    sender
+}
+
+async fn listen(pool: &PgPool, events: &broadcast::Sender<TaskEvent>) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CHANNEL).await?;
+
+    loop {
+        let notification = listener.recv().await?;
+        match serde_json::from_str::<TaskEvent>(notification.payload()) {
+            // Aucun abonné : l'événement est simplement perdu
+            Ok(event) => {
+                let _ = events.send(event);
+            }
+            Err(e) => eprintln!("Événement illisible: {}", e),
+        }
+    }
+}
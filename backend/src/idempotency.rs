@@ -0,0 +1,306 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Path, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// En-tête choisi par le client pour identifier une requête qu'il pourra rejouer
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Taille maximale des corps mémorisés (celle acceptée par défaut par Axum)
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// En-têtes de réponse rejoués avec le corps
+const REPLAYED_HEADERS: [HeaderName; 2] = [header::CONTENT_TYPE, header::ETAG];
+
+/// Durée au-delà de laquelle une réservation sans réponse est abandonnée : la même requête
+/// peut alors reprendre la clé (processus arrêté avant d'avoir enregistré la réponse)
+pub const CLAIM_LEASE_SECONDS: f64 = 60.0;
+
+/// Rend les requêtes POST authentifiées rejouables : une requête portant un
+/// `Idempotency-Key` déjà vu renvoie la réponse d'origine sans être exécutée à nouveau.
+/// 422 si la clé a servi pour une autre requête, 409 si la requête d'origine est encore en cours.
+/// Les erreurs serveur ne sont pas mémorisées, pour pouvoir réessayer.
+///
+/// Les routes `/auth` n'en bénéficient pas : leurs réponses portent un jeton de session,
+/// qui ne doit pas être conservé en base (une inscription rejouée renvoie 409).
+pub async fn idempotency(
+    State(pool): State<PgPool>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let Some(key) = idempotency_key(&req)? else {
+        return Ok(next.run(req).await);
+    };
+    let user_id = *req
+        .extensions()
+        .get::<Uuid>()
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let path = req.uri().path().to_string();
+
+    run_once(pool, user_id, key, path, req, next).await
+}
+
+/// Même traitement pour les URL de déclenchement publiques : sans session, la clé est
+/// rattachée au propriétaire de la tâche, et la requête désignée par l'identifiant du
+/// déclencheur plutôt que par son URL (le jeton n'est conservé que haché)
+pub async fn trigger_idempotency(
+    State(pool): State<PgPool>,
+    Path(token): Path<String>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let Some(key) = idempotency_key(&req)? else {
+        return Ok(next.run(req).await);
+    };
+    let trigger = sqlx::query!(
+        r#"
+        SELECT tr.id, t.user_id
+        FROM task_triggers tr
+        JOIN tasks t ON t.id = tr.task_id
+        WHERE tr.token_hash = sha256(convert_to($1, 'UTF8'))
+        "#,
+        token
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur recherche déclencheur: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    // URL inconnue : le gestionnaire répond 404, rien à mémoriser
+    let Some(trigger) = trigger else {
+        return Ok(next.run(req).await);
+    };
+    let path = format!("/triggers/{}", trigger.id);
+
+    run_once(pool, trigger.user_id, key, path, req, next).await
+}
+
+/// Clé d'idempotence d'une requête POST, s'il y en a une (400 si elle est invalide)
+fn idempotency_key(req: &Request) -> Result<Option<String>, StatusCode> {
+    if req.method() != Method::POST {
+        return Ok(None);
+    }
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY) else {
+        return Ok(None);
+    };
+    key.to_str()
+        .ok()
+        .filter(|k| !k.is_empty() && k.len() <= 255)
+        .map(|k| Some(k.to_string()))
+        .ok_or(StatusCode::BAD_REQUEST)
+}
+
+/// Réservation d'une clé par la requête en cours. Si elle n'aboutit pas à une réponse
+/// enregistrée (client parti, panique du gestionnaire, échec de l'enregistrement),
+/// la clé est libérée à la destruction pour que le client puisse réessayer sans attendre
+/// la fin du bail.
+struct Claim {
+    pool: PgPool,
+    user_id: Uuid,
+    key: String,
+    claimed_at: DateTime<Utc>,
+    settled: bool,
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let (pool, user_id, key, claimed_at) = (
+            self.pool.clone(),
+            self.user_id,
+            std::mem::take(&mut self.key),
+            self.claimed_at,
+        );
+        runtime.spawn(async move {
+            if let Err(e) = release(&pool, user_id, &key, claimed_at).await {
+                eprintln!("Erreur libération clé d'idempotence: {}", e);
+            }
+        });
+    }
+}
+
+/// Supprime une réservation encore en cours, sauf si elle a été reprise entre-temps
+async fn release(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+    claimed_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM idempotency_keys
+        WHERE user_id = $1 AND key = $2 AND claimed_at = $3 AND status IS NULL
+        "#,
+        user_id,
+        key,
+        claimed_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Exécute la requête une seule fois pour cette clé, ou rejoue la réponse mémorisée
+async fn run_once(
+    pool: PgPool,
+    user_id: Uuid,
+    key: String,
+    path: String,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let (parts, body) = req.into_parts();
+    let body = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+    let method = parts.method.to_string();
+
+    // Une réservation abandonnée par la même requête au-delà du bail est reprise
+    let claimed_at = sqlx::query_scalar!(
+        r#"
+        INSERT INTO idempotency_keys (user_id, key, method, path, request_hash)
+        VALUES ($1, $2, $3, $4, md5($5::bytea))
+        ON CONFLICT (user_id, key) DO UPDATE SET claimed_at = now(), created_at = now()
+        WHERE idempotency_keys.status IS NULL
+          AND idempotency_keys.claimed_at < now() - make_interval(secs => $6)
+          AND idempotency_keys.method = EXCLUDED.method
+          AND idempotency_keys.path = EXCLUDED.path
+          AND idempotency_keys.request_hash = EXCLUDED.request_hash
+        RETURNING claimed_at
+        "#,
+        user_id,
+        key,
+        method,
+        path,
+        body.as_ref(),
+        CLAIM_LEASE_SECONDS
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur réservation clé d'idempotence: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let Some(claimed_at) = claimed_at else {
+        return replay(&pool, user_id, &key, &method, &path, &body).await;
+    };
+    let mut claim = Claim {
+        pool: pool.clone(),
+        user_id,
+        key,
+        claimed_at,
+        settled: false,
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    let status = response.status();
+
+    if status.is_server_error() {
+        release(&pool, user_id, &claim.key, claimed_at)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        claim.settled = true;
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let headers: serde_json::Map<String, serde_json::Value> = REPLAYED_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let value = parts.headers.get(name)?.to_str().ok()?;
+            Some((name.to_string(), value.into()))
+        })
+        .collect();
+
+    sqlx::query!(
+        r#"
+        UPDATE idempotency_keys SET status = $4, headers = $5, body = $6
+        WHERE user_id = $1 AND key = $2 AND claimed_at = $3
+        "#,
+        user_id,
+        claim.key,
+        claimed_at,
+        status.as_u16() as i16,
+        serde_json::Value::Object(headers),
+        body.as_ref()
+    )
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur enregistrement réponse idempotente: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    claim.settled = true;
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+/// Renvoie la réponse mémorisée pour une clé déjà utilisée
+async fn replay(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> Result<Response, StatusCode> {
+    let stored = sqlx::query!(
+        r#"
+        SELECT method = $3 AND path = $4 AND request_hash = md5($5::bytea) as "same_request!",
+               status, headers, body
+        FROM idempotency_keys
+        WHERE user_id = $1 AND key = $2
+        "#,
+        user_id,
+        key,
+        method,
+        path,
+        body
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    // Supprimée entre-temps (erreur serveur de la requête d'origine) : le client peut réessayer
+    .ok_or(StatusCode::CONFLICT)?;
+
+    if !stored.same_request {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let Some(status) = stored.status else {
+        return Err(StatusCode::CONFLICT);
+    };
+
+    let mut headers = HeaderMap::new();
+    if let Some(serde_json::Value::Object(stored_headers)) = stored.headers {
+        for (name, value) in stored_headers {
+            if let (Ok(name), Some(Ok(value))) = (
+                HeaderName::try_from(name),
+                value.as_str().map(HeaderValue::from_str),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+    }
+    headers.insert("idempotent-replayed", HeaderValue::from_static("true"));
+
+    let status =
+        StatusCode::from_u16(status as u16).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok((status, headers, stored.body.unwrap_or_default()).into_response())
+}
@@ -0,0 +1,135 @@
+use sqlx::PgPool;
+use std::env;
+use std::time::Duration;
+
+use crate::audit;
+use crate::notifier::Notifier;
+use crate::reminders;
+use crate::stats;
+use crate::undo::undo_window_minutes;
+use crate::webhooks;
+
+/// Nombre de jours pendant lesquels une tâche reste dans la corbeille (30 par défaut)
+pub fn trash_retention_days() -> i32 {
+    env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|days: &i32| *days >= 0)
+        .unwrap_or(30)
+}
+
+/// Supprime définitivement les tâches mises à la corbeille il y a plus de `retention_days` jours.
+/// Les jours, complétions, sous-tâches et étiquettes associés suivent par cascade.
+pub async fn purge_trash(pool: &PgPool, retention_days: i32) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let task_ids = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM tasks
+        WHERE deleted = true
+          AND deleted_at < now() - make_interval(days => $1)
+        FOR UPDATE
+        "#,
+        retention_days
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    audit::record_purge(&mut tx, None, &task_ids).await?;
+
+    let result = sqlx::query!("DELETE FROM tasks WHERE id = ANY($1)", &task_ids)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Supprime les jetons d'annulation expirés
+pub async fn purge_undo_tokens(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        "DELETE FROM undo_tokens WHERE created_at < now() - make_interval(mins => $1)",
+        undo_window_minutes()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Oublie les mutations de synchronisation reçues il y a plus de 30 jours
+/// (un client ne renvoie pas une mutation aussi ancienne)
+pub async fn purge_sync_mutations(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result =
+        sqlx::query!("DELETE FROM sync_mutations WHERE created_at < now() - interval '30 days'")
+            .execute(pool)
+            .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Oublie les réponses mémorisées pour les clés d'idempotence de plus de 24 heures
+pub async fn purge_idempotency_keys(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result =
+        sqlx::query!("DELETE FROM idempotency_keys WHERE created_at < now() - interval '24 hours'")
+            .execute(pool)
+            .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Lance le nettoyage en tâche de fond (corbeille, jetons d'annulation, mutations
+/// de synchronisation, clés d'idempotence) et le recalcul des derniers bilans, toutes les heures
+pub fn spawn_cleanup(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            match purge_trash(&pool, trash_retention_days()).await {
+                Ok(0) => {}
+                Ok(count) => println!("Corbeille : {} tâche(s) supprimée(s) définitivement", count),
+                Err(e) => eprintln!("Erreur purge corbeille: {}", e),
+            }
+            if let Err(e) = purge_undo_tokens(&pool).await {
+                eprintln!("Erreur purge jetons d'annulation: {}", e);
+            }
+            if let Err(e) = purge_sync_mutations(&pool).await {
+                eprintln!("Erreur purge mutations de synchronisation: {}", e);
+            }
+            if let Err(e) = purge_idempotency_keys(&pool).await {
+                eprintln!("Erreur purge clés d'idempotence: {}", e);
+            }
+            if let Err(e) = stats::rebuild_recent(&pool, stats::REBUILD_DAYS).await {
+                eprintln!("Erreur recalcul des bilans récents: {}", e);
+            }
+        }
+    });
+}
+
+/// Lance l'envoi des rappels et récapitulatifs dus, toutes les minutes
+pub fn spawn_reminders(pool: PgPool, notifiers: Vec<Box<dyn Notifier>>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = reminders::send_due(&pool, &notifiers).await {
+                eprintln!("Erreur envoi des rappels: {}", e);
+            }
+        }
+    });
+}
+
+/// Lance l'envoi des webhooks en file, toutes les 5 secondes
+pub fn spawn_webhooks(pool: PgPool) {
+    let sender = webhooks::Sender::public();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            if let Err(e) = webhooks::send_due(&pool, &sender).await {
+                eprintln!("Erreur envoi des webhooks: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,35 @@
+//! API du gestionnaire de tâches : le binaire `task-manager` assemble ces modules,
+//! les benchmarks les utilisent directement.
+
+pub mod admin;
+pub mod api;
+pub mod audit;
+pub mod auth;
+pub mod db;
+pub mod events;
+pub mod idempotency;
+pub mod jobs;
+pub mod middleware;
+pub mod models;
+pub mod notifier;
+pub mod openapi;
+pub mod precondition;
+pub mod reminders;
+pub mod routes;
+pub mod routes_audit;
+pub mod routes_auth;
+pub mod routes_batch;
+pub mod routes_events;
+pub mod routes_journal;
+pub mod routes_notifications;
+pub mod routes_sync;
+pub mod routes_tags;
+pub mod routes_teams;
+pub mod routes_triggers;
+pub mod routes_undo;
+pub mod routes_vacations;
+pub mod routes_webhooks;
+pub mod stats;
+pub mod undo;
+pub mod webhooks;
+pub mod webpush;
@@ -1,31 +1,152 @@
-mod auth;
-mod db;
-mod middleware;
-mod models;
-mod routes;
-mod routes_auth;
-
-use crate::routes_auth::auth_routes;
-use axum::{Router, middleware::from_fn};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use sqlx::PgPool;
+use std::io::BufRead;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use task_manager::auth::MIN_PASSWORD_LENGTH;
+use task_manager::{admin, api, db, events, jobs, notifier, stats};
 use tokio::net::TcpListener;
 use tower_http::services::ServeDir;
+use uuid::Uuid;
+
+#[derive(Parser)]
+#[command(
+    name = "task-manager",
+    version,
+    about = "Serveur du gestionnaire de tâches et commandes d'administration"
+)]
+struct Cli {
+    /// Sans commande : `serve`
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Appliquer les migrations et lancer le serveur HTTP
+    Serve,
+    /// Appliquer les migrations puis quitter
+    Migrate,
+    /// Gérer les comptes
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Supprimer définitivement les tâches de la corbeille
+    Purge {
+        /// Seulement celles supprimées depuis au moins ce nombre de jours
+        /// (`TRASH_RETENTION_DAYS` par défaut, 0 pour vider la corbeille)
+        #[arg(long)]
+        days: Option<i32>,
+    },
+    /// Recalculer les bilans quotidiens depuis les données brutes
+    BackfillStats {
+        /// Identifiant ou nom de l'utilisateur (tous par défaut)
+        #[arg(long)]
+        user: Option<String>,
+        /// Premier jour (AAAA-MM-JJ), par défaut celui de la première tâche
+        #[arg(long)]
+        from: Option<NaiveDate>,
+        /// Dernier jour (AAAA-MM-JJ), aujourd'hui par défaut
+        #[arg(long)]
+        to: Option<NaiveDate>,
+    },
+    /// Exporter toutes les données d'un utilisateur en JSON
+    Export {
+        /// Identifiant ou nom de l'utilisateur
+        #[arg(long)]
+        user: String,
+        /// Fichier de sortie (sortie standard par défaut)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    /// Créer un compte
+    Create {
+        username: String,
+        /// Lire le mot de passe sur l'entrée standard au lieu de le demander
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Remplacer le mot de passe d'un compte et révoquer ses sessions
+    ResetPassword {
+        /// Identifiant ou nom de l'utilisateur
+        user: String,
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Désactiver un compte : connexion et sessions refusées
+    Disable {
+        /// Identifiant ou nom de l'utilisateur
+        user: String,
+    },
+    /// Réactiver un compte désactivé
+    Enable {
+        /// Identifiant ou nom de l'utilisateur
+        user: String,
+    },
+}
 
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+
     let pool = db::init_pool().await;
     db::run_migrations(&pool).await;
 
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(pool).await,
+        Command::Migrate => {}
+        Command::User(command) => user(&pool, command).await,
+        Command::Purge { days } => {
+            let days = days.unwrap_or_else(jobs::trash_retention_days);
+            let count = jobs::purge_trash(&pool, days)
+                .await
+                .unwrap_or_else(|e| fail(format!("Erreur purge corbeille: {}", e)));
+            println!("Corbeille : {} tâche(s) supprimée(s) définitivement", count);
+        }
+        Command::BackfillStats { user, from, to } => {
+            let user_id = match user {
+                Some(user) => Some(find_user(&pool, &user).await),
+                None => None,
+            };
+            match stats::backfill(&pool, user_id, from, to).await {
+                Ok(count) => println!("Bilans quotidiens recalculés pour {} utilisateur(s)", count),
+                Err(e) => fail(format!("Erreur recalcul des bilans: {}", e)),
+            }
+        }
+        Command::Export { user, output } => {
+            let user_id = find_user(&pool, &user).await;
+            let export = admin::export_user(&pool, user_id)
+                .await
+                .unwrap_or_else(|e| fail(format!("Erreur export: {}", e)));
+            let json = serde_json::to_string_pretty(&export).expect("JSON valide");
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, json).unwrap_or_else(|e| {
+                        fail(format!("Erreur écriture {}: {}", path.display(), e))
+                    });
+                    eprintln!("Données de {} exportées dans {}", user, path.display());
+                }
+                None => println!("{}", json),
+            }
+        }
+    }
+}
+
+/// Lance les tâches de fond et le serveur HTTP
+async fn serve(pool: PgPool) {
+    jobs::spawn_cleanup(pool.clone());
+    jobs::spawn_reminders(pool.clone(), notifier::notifiers_from_env(&pool));
+    jobs::spawn_webhooks(pool.clone());
+    let events = events::spawn_listener(pool.clone());
+
     let frontend_path =
         std::env::var("FRONTEND_PATH").unwrap_or_else(|_| "../frontend".to_string());
 
-    let app = Router::new()
-        .nest("/api/auth", auth_routes(pool.clone()))
-        .nest(
-            "/api",
-            routes::routes(pool).layer(from_fn(middleware::auth)),
-        )
-        .fallback_service(ServeDir::new(frontend_path));
+    let app = api::router(pool, events).fallback_service(ServeDir::new(frontend_path));
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
     let listener = TcpListener::bind(addr).await.unwrap();
@@ -33,3 +154,93 @@ async fn main() {
     println!("Server listening on http://{}", addr);
     axum::serve(listener, app).await.unwrap();
 }
+
+/// Commandes `user`
+async fn user(pool: &PgPool, command: UserCommand) {
+    match command {
+        UserCommand::Create {
+            username,
+            password_stdin,
+        } => {
+            let username = username.trim();
+            if username.is_empty() {
+                fail("Nom d'utilisateur vide");
+            }
+            let password = read_password(password_stdin);
+            match admin::create_user(pool, username, &password).await {
+                Ok(Some(id)) => println!("Utilisateur {} créé ({})", username, id),
+                Ok(None) => fail(format!("Le nom {} est déjà pris", username)),
+                Err(e) => fail(format!("Erreur création utilisateur: {}", e)),
+            }
+        }
+        UserCommand::ResetPassword {
+            user,
+            password_stdin,
+        } => {
+            let user_id = find_user(pool, &user).await;
+            let password = read_password(password_stdin);
+            admin::reset_password(pool, user_id, &password)
+                .await
+                .unwrap_or_else(|e| fail(format!("Erreur réinitialisation: {}", e)));
+            println!("Mot de passe de {} remplacé, sessions révoquées", user);
+        }
+        UserCommand::Disable { user } => set_disabled(pool, &user, true).await,
+        UserCommand::Enable { user } => set_disabled(pool, &user, false).await,
+    }
+}
+
+async fn set_disabled(pool: &PgPool, user: &str, disabled: bool) {
+    let user_id = find_user(pool, user).await;
+    admin::set_disabled(pool, user_id, disabled)
+        .await
+        .unwrap_or_else(|e| fail(format!("Erreur mise à jour du compte: {}", e)));
+    if disabled {
+        println!("Compte {} désactivé, sessions révoquées", user);
+    } else {
+        println!("Compte {} réactivé", user);
+    }
+}
+
+/// Utilisateur désigné par son identifiant ou son nom, arrêt s'il n'existe pas
+async fn find_user(pool: &PgPool, user: &str) -> Uuid {
+    match admin::find_user(pool, user).await {
+        Ok(Some(id)) => id,
+        Ok(None) => fail(format!("Utilisateur {} introuvable", user)),
+        Err(e) => fail(format!("Erreur recherche utilisateur: {}", e)),
+    }
+}
+
+/// Mot de passe lu sur une ligne de l'entrée standard, ou demandé deux fois au terminal
+fn read_password(from_stdin: bool) -> String {
+    let password = if from_stdin {
+        let mut line = String::new();
+        std::io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .unwrap_or_else(|e| fail(format!("Erreur lecture entrée standard: {}", e)));
+        line.trim_end_matches(['\r', '\n']).to_string()
+    } else {
+        let password = rpassword::prompt_password("Mot de passe : ")
+            .unwrap_or_else(|e| fail(format!("Erreur lecture mot de passe: {}", e)));
+        let confirmation = rpassword::prompt_password("Confirmation : ")
+            .unwrap_or_else(|e| fail(format!("Erreur lecture mot de passe: {}", e)));
+        if password != confirmation {
+            fail("Les mots de passe ne correspondent pas");
+        }
+        password
+    };
+
+    if password.len() < MIN_PASSWORD_LENGTH {
+        fail(format!(
+            "Le mot de passe doit contenir au moins {} caractères",
+            MIN_PASSWORD_LENGTH
+        ));
+    }
+    password
+}
+
+/// Affiche l'erreur et quitte avec le code 1
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
@@ -1,13 +1,19 @@
 use crate::auth::decode_jwt;
 use axum::{
     body::Body,
-    http::{Request, StatusCode},
+    extract::{OriginalUri, State},
+    http::{HeaderValue, Request, StatusCode, header},
     middleware::Next,
     response::Response,
 };
+use sqlx::PgPool;
 use uuid::Uuid;
 
-pub async fn auth(mut req: Request<Body>, next: Next) -> Result<Response, StatusCode> {
+pub async fn auth(
+    State(pool): State<PgPool>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
     let header = req
         .headers()
         .get("Authorization")
@@ -20,7 +26,57 @@ pub async fn auth(mut req: Request<Body>, next: Next) -> Result<Response, Status
 
     let claims = decode_jwt(token).ok_or(StatusCode::UNAUTHORIZED)?;
 
+    // Compte supprimé ou désactivé, ou jeton émis avant une réinitialisation du mot de passe
+    // (ou pendant la même seconde : `tokens_valid_after` est arrondi à la seconde suivante)
+    let allowed = sqlx::query_scalar!(
+        r#"
+        SELECT disabled_at IS NULL
+               AND (tokens_valid_after IS NULL OR tokens_valid_after <= to_timestamp($2))
+               AS "allowed!"
+        FROM users WHERE id = $1
+        "#,
+        claims.sub,
+        claims.iat as f64
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .unwrap_or(false);
+    if !allowed {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     req.extensions_mut().insert::<Uuid>(claims.sub);
 
     Ok(next.run(req).await)
 }
+
+/// Date de dépréciation de l'alias `/api` (en-tête `Deprecation`, secondes Unix : 2026-10-19)
+const LEGACY_API_DEPRECATED_AT: i64 = 1_792_368_000;
+
+/// Date à partir de laquelle l'alias `/api` pourra être retiré
+const LEGACY_API_SUNSET: &str = "Fri, 30 Apr 2027 00:00:00 GMT";
+
+/// Signale sur chaque réponse de l'alias `/api` qu'il est déprécié (RFC 9745 et 8594),
+/// avec l'adresse équivalente sous `/api/v1`
+pub async fn deprecated_api(req: Request<Body>, next: Next) -> Response {
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.path().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let successor = format!("/api/v1{}", path.strip_prefix("/api").unwrap_or(&path));
+
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+    headers.insert(
+        "deprecation",
+        HeaderValue::from_str(&format!("@{}", LEGACY_API_DEPRECATED_AT)).unwrap(),
+    );
+    headers.insert("sunset", HeaderValue::from_static(LEGACY_API_SUNSET));
+    if let Ok(link) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor))
+    {
+        headers.insert(header::LINK, link);
+    }
+    response
+}
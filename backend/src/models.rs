@@ -1,5 +1,7 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 #[derive(FromRow)]
@@ -9,91 +11,410 @@ pub struct User {
     pub password_hash: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct RegisterRequest {
     pub username: String,
     pub password: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct AuthResponse {
     pub token: String,
 }
 
-// Structure pour les Tâches
-#[derive(Serialize, Deserialize, FromRow)]
+// Structure pour les Tâches (les champs par défaut sont absents de certaines réponses,
+// `GET /tasks` ne renvoie pas les jours par exemple)
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Task {
     pub id: i32,
     pub title: String,
     pub active: bool,
     #[sqlx(default)]
+    #[serde(default)]
     pub days: Vec<i32>,
     #[sqlx(default)]
+    #[serde(default)]
     pub completed: bool,
     #[sqlx(default)]
+    #[serde(default)]
     pub priority: i32,
     #[sqlx(default)]
+    #[serde(default)]
     pub has_subtasks: bool,
     #[sqlx(default)]
+    #[serde(default)]
     pub subtasks: Vec<Subtask>,
 }
 
 // Structure pour les Sous-tâches
-#[derive(Serialize, Deserialize, FromRow, Clone)]
+#[derive(Serialize, Deserialize, FromRow, Clone, ToSchema)]
 pub struct Subtask {
     pub id: i32,
+    #[serde(default)] // Absent des sous-tâches imbriquées dans une tâche
     pub task_id: i32,
     pub title: String,
     pub completed: bool,
     pub priority: i32,
+    pub version: i32,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct CreateTaskRequest {
     pub title: String,
     pub days: Vec<i32>,
     pub subtasks: Option<Vec<String>>,
+    pub tags: Option<Vec<i32>>,
+    pub measurement_type: Option<String>, // "boolean" (par défaut), "count", "duration" ou "value"
+    pub target: Option<f64>,              // Objectif journalier des tâches chiffrées
+    pub unit: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Default, ToSchema)]
 pub struct UpdateTaskRequest {
     pub title: Option<String>,
     pub days: Option<Vec<i32>>,
     pub active: Option<bool>,
     pub subtasks: Option<Vec<SubtaskUpdate>>, // Nouveau champ pour les sous-tâches
+    pub tags: Option<Vec<i32>>,                // Remplace les étiquettes de la tâche
+    pub measurement_type: Option<String>,      // Change le type de mesure (avec target et unit)
+    pub target: Option<f64>,
+    pub unit: Option<String>,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct SubtaskUpdate {
     pub id: Option<i32>, // Optionnel pour les nouvelles sous-tâches
     pub title: String,
-    pub completed: Option<bool>,
+    pub completed: Option<bool>, // Conservé si absent
+    pub version: Option<i32>,    // Version lue par le client, pour détecter les modifications concurrentes
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateSubtaskRequest {
     pub title: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct UpdateSubtaskRequest {
     pub completed: Option<bool>,
     pub title: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ToggleSubtaskRequest {
     pub task_id: i32,
     pub subtask_id: i32,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LogValueRequest {
+    pub value: f64,
+    pub date: Option<NaiveDate>,   // Aujourd'hui par défaut
+    pub increment: Option<bool>,   // Ajoute à la valeur du jour au lieu de la remplacer
+}
+
+/// Note et ressenti (1 à 5) d'une occurrence, envoyés au toggle ou séparément
+#[derive(Deserialize, ToSchema)]
+pub struct CompletionNoteRequest {
+    pub date: Option<NaiveDate>, // Aujourd'hui par défaut (ignoré par le toggle)
+    pub note: Option<String>,
+    pub rating: Option<i16>,
+}
+
+/// Marque une occurrence comme sautée volontairement (ou annule le saut)
+#[derive(Deserialize, ToSchema)]
+pub struct SkipRequest {
+    pub date: Option<NaiveDate>,  // Aujourd'hui par défaut
+    pub reason: Option<String>,
+    pub skipped: Option<bool>,    // false pour annuler (true par défaut)
+}
+
+/// État explicite d'archivage d'une tâche
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SetArchivedRequest {
+    pub archived: bool,
+}
+
+/// État explicite de complétion d'une occurrence
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SetCompletionRequest {
+    pub date: Option<NaiveDate>, // Aujourd'hui par défaut
+    pub completed: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateVacationRequest {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub reason: Option<String>,
+    pub task_ids: Option<Vec<i32>>, // Tâches mises en pause (toutes si absent)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct JournalQuery {
+    pub from: Option<NaiveDate>, // 30 derniers jours par défaut
+    pub to: Option<NaiveDate>,
+    pub q: Option<String>,       // Recherche plein texte dans les notes
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    pub page: Option<i64>,     // À partir de 1
+    pub per_page: Option<i64>, // 50 par défaut, 200 au maximum
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct UpdatePrioritiesRequest {
     pub ordered_task_ids: Vec<i32>,
 }
+
+// Structures pour les lots d'opérations
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchRequest {
+    pub operations: Vec<BatchOperation>,
+    pub atomic: Option<bool>, // Tout ou rien (par défaut), sinon chaque opération indépendamment
+}
+
+/// Opération d'un lot ; `version` joue le rôle de l'en-tête `If-Match` de la route équivalente
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+    Create(CreateTaskRequest),
+    Update {
+        task_id: i32,
+        version: Option<i32>,
+        #[serde(flatten)]
+        changes: UpdateTaskRequest,
+    },
+    Toggle {
+        task_id: i32,
+    },
+    Archive {
+        task_id: i32,
+        archived: bool,
+        version: Option<i32>,
+    },
+    Delete {
+        task_id: i32,
+        version: Option<i32>,
+    },
+    CreateSubtask {
+        task_id: i32,
+        title: String,
+        version: Option<i32>,
+    },
+    UpdateSubtask {
+        task_id: i32,
+        subtask_id: i32,
+        version: Option<i32>,
+        #[serde(flatten)]
+        changes: UpdateSubtaskRequest,
+    },
+    DeleteSubtask {
+        task_id: i32,
+        subtask_id: i32,
+        version: Option<i32>,
+    },
+    ToggleSubtask {
+        task_id: i32,
+        subtask_id: i32,
+        version: Option<i32>,
+    },
+}
+
+// Structures pour la synchronisation hors ligne
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SyncQuery {
+    pub since: Option<String>, // Curseur renvoyé par la synchronisation précédente (tout si absent)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SyncRequest {
+    pub mutations: Vec<SyncMutation>,
+}
+
+/// Modification faite par un client, éventuellement hors ligne
+#[derive(Deserialize, ToSchema)]
+pub struct SyncMutation {
+    pub id: Uuid,          // Généré par le client : renvoyer une mutation déjà reçue est sans effet
+    pub at: DateTime<Utc>, // Date de la modification côté client
+    #[serde(flatten)]
+    pub change: SyncChange,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncChange {
+    CreateTask(CreateTaskRequest),
+    UpdateTask {
+        task_id: i32,
+        title: Option<String>,
+        days: Option<Vec<i32>>,
+        active: Option<bool>,
+        deleted: Option<bool>,
+    },
+    UpdateSubtask {
+        task_id: i32,
+        subtask_id: i32,
+        title: Option<String>,
+        completed: Option<bool>,
+    },
+    SetCompletion {
+        task_id: i32,
+        date: NaiveDate,
+        completed: Option<bool>,
+        value: Option<f64>,
+        note: Option<String>, // Une note vide efface la note existante
+        rating: Option<i16>,
+        skipped: Option<bool>,
+        skip_reason: Option<String>,
+    },
+}
+
+// Structures pour les équipes
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTeamRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddMemberRequest {
+    pub username: String,
+    pub role: Option<String>, // "admin" ou "member" (par défaut)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTemplateRequest {
+    pub title: String,
+    pub days: Vec<i32>,
+    pub subtasks: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateTemplateRequest {
+    pub title: Option<String>,
+    pub days: Option<Vec<i32>>,
+    pub subtasks: Option<Vec<String>>,
+}
+
+// Structures pour les étiquettes
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub color: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTagRequest {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateTagRequest {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+/// Filtre `?tags=1,2` : ne garde que les tâches portant au moins une de ces étiquettes
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TagFilter {
+    pub tags: Option<String>,
+}
+
+/// Paramètres de `GET /api/v1/tasks/all` : filtres, tri et pagination par curseur
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskListQuery {
+    /// Recherche dans le titre, insensible à la casse
+    pub q: Option<String>,
+    /// Jours `1,3,5` : tâches prévues au moins un de ces jours
+    pub days: Option<String>,
+    /// `active`, `archived` ou `all` (par défaut)
+    pub status: Option<String>,
+    pub has_subtasks: Option<bool>,
+    pub tags: Option<String>,
+    /// `title`, `created_at`, `priority` ou `completion_rate`
+    pub sort: Option<String>,
+    /// `asc` ou `desc`
+    pub order: Option<String>,
+    pub limit: Option<i64>,
+    /// Curseur opaque renvoyé dans `next_cursor`
+    pub cursor: Option<String>,
+}
+
+/// Paramètres de `GET /api/v1/stats` : période de la heatmap et filtre d'étiquettes
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsQuery {
+    pub tags: Option<String>,
+    /// Nombre de jours de l'historique, aujourd'hui compris (30 par défaut, 366 au plus)
+    pub days: Option<i32>,
+}
+
+/// Heures de rappel d'une tâche (`HH:MM`), remplacent les précédentes
+#[derive(Deserialize, ToSchema)]
+pub struct SetRemindersRequest {
+    pub times: Vec<String>,
+}
+
+/// Canaux, heures calmes et récapitulatif ; un champ absent est désactivé
+#[derive(Deserialize, ToSchema)]
+pub struct NotificationSettingsRequest {
+    pub webhook_url: Option<String>,
+    pub email: Option<String>,
+    pub quiet_start: Option<String>, // HH:MM
+    pub quiet_end: Option<String>,
+    pub digest_at: Option<String>,
+}
+
+/// Abonnement renvoyé par `PushSubscription.toJSON()` dans le navigateur
+#[derive(Deserialize, ToSchema)]
+pub struct PushSubscriptionRequest {
+    pub endpoint: String,
+    pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PushSubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// Webhook sortant ; sans secret, un secret aléatoire est généré.
+/// `events` absent ou vide : tous les événements
+#[derive(Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>,
+}
+
+/// Modification partielle d'un webhook
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
+
+/// URL de déclenchement d'une tâche : `toggle`, `complete`, `uncomplete` ou `increment`
+/// (tâches chiffrées, de `amount`, 1 par défaut)
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTriggerRequest {
+    pub action: String,
+    pub amount: Option<f64>,
+    pub name: Option<String>,
+}
@@ -0,0 +1,228 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::env;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::webhooks::Sender;
+use crate::webpush::WebPushNotifier;
+
+pub type NotifyError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Rappel ou récapitulatif à envoyer, tel qu'enregistré dans `notifications`
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub id: i32,
+    #[serde(skip)]
+    pub user_id: Uuid,
+    pub kind: String, // "reminder" ou "digest"
+    pub task_id: Option<i32>,
+    pub date: NaiveDate,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Abonnement Web Push d'un navigateur (clés en base64url, comme `PushSubscription.toJSON()`)
+#[derive(Debug, Clone)]
+pub struct PushSubscription {
+    pub id: i32,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// Destinataire et canaux qu'il a configurés
+#[derive(Debug, Clone, Default)]
+pub struct Recipient {
+    pub user_id: Uuid,
+    pub username: String,
+    pub webhook_url: Option<String>,
+    pub email: Option<String>,
+    pub push_subscriptions: Vec<PushSubscription>,
+}
+
+/// Canal d'envoi des notifications. Chaque implémentation ignore les destinataires
+/// qui ne l'ont pas configuré.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Nom du canal, pour les journaux
+    fn name(&self) -> &'static str;
+
+    async fn send(
+        &self,
+        recipient: &Recipient,
+        notification: &Notification,
+    ) -> Result<(), NotifyError>;
+}
+
+/// Canaux disponibles d'après l'environnement : le webhook toujours,
+/// l'e-mail si `SMTP_HOST` est défini, Web Push si `VAPID_PRIVATE_KEY` l'est
+pub fn notifiers_from_env(pool: &PgPool) -> Vec<Box<dyn Notifier>> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(WebhookNotifier::new())];
+
+    match SmtpConfig::from_env().map(SmtpNotifier::new) {
+        Some(Ok(smtp)) => notifiers.push(Box::new(smtp)),
+        Some(Err(e)) => eprintln!("Configuration SMTP invalide, e-mails désactivés: {}", e),
+        None => {}
+    }
+
+    match WebPushNotifier::from_env(pool.clone()) {
+        Some(Ok(push)) => notifiers.push(Box::new(push)),
+        Some(Err(e)) => eprintln!("Clé VAPID invalide, Web Push désactivé: {}", e),
+        None => {}
+    }
+
+    notifiers
+}
+
+// --- WEBHOOK ---
+
+/// POST JSON de la notification vers l'URL choisie par l'utilisateur,
+/// avec les mêmes restrictions d'adresse que les webhooks
+pub struct WebhookNotifier {
+    sender: Sender,
+}
+
+impl WebhookNotifier {
+    pub fn new() -> Self {
+        Self {
+            sender: Sender::public(),
+        }
+    }
+
+    /// Envois autorisés vers la machine et le réseau local (serveurs de test)
+    pub fn local() -> Self {
+        Self {
+            sender: Sender::local(),
+        }
+    }
+}
+
+impl Default for WebhookNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send(
+        &self,
+        recipient: &Recipient,
+        notification: &Notification,
+    ) -> Result<(), NotifyError> {
+        let Some(url) = &recipient.webhook_url else {
+            return Ok(());
+        };
+        if !self.sender.can_reach(url) {
+            return Err("adresse non publique".into());
+        }
+
+        self.sender
+            .client()
+            .post(url)
+            .json(notification)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+// --- SMTP ---
+
+/// Connexion au serveur SMTP. `tls` : `starttls` (par défaut), `tls` ou `none`
+/// (serveur de test local, comme Mailpit).
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    pub tls: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+}
+
+impl SmtpConfig {
+    /// `SMTP_HOST`, `SMTP_PORT`, `SMTP_TLS`, `SMTP_USERNAME`, `SMTP_PASSWORD`, `SMTP_FROM`
+    pub fn from_env() -> Option<Self> {
+        let host = env::var("SMTP_HOST").ok().filter(|h| !h.is_empty())?;
+        Some(Self {
+            host,
+            port: env::var("SMTP_PORT").ok().and_then(|p| p.parse().ok()),
+            tls: env::var("SMTP_TLS").unwrap_or_else(|_| "starttls".to_string()),
+            username: env::var("SMTP_USERNAME").ok(),
+            password: env::var("SMTP_PASSWORD").ok(),
+            from: env::var("SMTP_FROM")
+                .unwrap_or_else(|_| "Task Manager <noreply@localhost>".to_string()),
+        })
+    }
+}
+
+/// Envoie la notification par e-mail à l'adresse choisie par l'utilisateur
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpNotifier {
+    pub fn new(config: SmtpConfig) -> Result<Self, NotifyError> {
+        let mut builder = match config.tls.as_str() {
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+            other => return Err(format!("SMTP_TLS inconnu: {}", other).into()),
+        };
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (config.username, config.password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.timeout(Some(Duration::from_secs(10))).build(),
+            from: config.from.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    async fn send(
+        &self,
+        recipient: &Recipient,
+        notification: &Notification,
+    ) -> Result<(), NotifyError> {
+        let Some(email) = &recipient.email else {
+            return Ok(());
+        };
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(Mailbox::new(
+                Some(recipient.username.clone()),
+                email.parse()?,
+            ))
+            .subject(&notification.title)
+            .body(notification.body.clone())?;
+        self.transport.send(message).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+//! Spécification OpenAPI générée depuis les annotations des gestionnaires et les types
+//! de `models.rs`, servie avec une page de documentation interactive.
+
+use axum::Router;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{
+    routes, routes_audit, routes_auth, routes_batch, routes_events, routes_journal,
+    routes_notifications, routes_sync, routes_tags, routes_teams, routes_triggers, routes_undo,
+    routes_vacations, routes_webhooks,
+};
+
+/// Chemin de la spécification
+pub const SPEC_PATH: &str = "/api/v1/openapi.json";
+
+/// Chemin de la page de documentation
+pub const DOCS_PATH: &str = "/api/v1/docs";
+
+/// Chemin de la spécification sous l'alias déprécié `/api`
+pub const LEGACY_SPEC_PATH: &str = "/api/openapi.json";
+
+/// Chemin de la page de documentation sous l'alias déprécié `/api`
+pub const LEGACY_DOCS_PATH: &str = "/api/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Task Manager API",
+        description = "Tâches récurrentes, complétions, statistiques, équipes et notifications. \
+                       Les routes sont authentifiées par le jeton renvoyé par `/auth/login`, \
+                       sauf mention contraire."
+    ),
+    servers((url = "/api/v1")),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    paths(
+        routes::get_today_tasks,
+        routes::create_task,
+        routes::get_all_tasks,
+        routes::get_trash,
+        routes::empty_trash,
+        routes::restore_task,
+        routes::purge_task,
+        routes::get_task,
+        routes::update_task,
+        routes::delete_task,
+        routes::toggle_archive,
+        routes::toggle_task,
+        routes::set_completion,
+        routes::set_archived,
+        routes::log_task_value,
+        routes::set_completion_note,
+        routes::skip_task,
+        routes::get_subtasks,
+        routes::create_subtask,
+        routes::update_subtask,
+        routes::delete_subtask,
+        routes::toggle_subtask,
+        routes::get_stats,
+        routes::get_measurement_stats,
+        routes::update_task_priorities,
+        routes_audit::get_task_history,
+        routes_auth::register,
+        routes_auth::login,
+        routes_batch::run_batch,
+        routes_events::stream_events,
+        routes_journal::get_journal,
+        routes_notifications::get_reminders,
+        routes_notifications::set_reminders,
+        routes_notifications::get_notifications,
+        routes_notifications::get_settings,
+        routes_notifications::update_settings,
+        routes_notifications::get_push_key,
+        routes_notifications::subscribe_push,
+        routes_notifications::unsubscribe_push,
+        routes_sync::pull_changes,
+        routes_sync::push_mutations,
+        routes_tags::get_tags,
+        routes_tags::create_tag,
+        routes_tags::update_tag,
+        routes_tags::delete_tag,
+        routes_tags::get_tag_stats,
+        routes_teams::get_teams,
+        routes_teams::create_team,
+        routes_teams::get_members,
+        routes_teams::add_member,
+        routes_teams::remove_member,
+        routes_teams::get_templates,
+        routes_teams::create_template,
+        routes_teams::update_template,
+        routes_teams::delete_template,
+        routes_teams::get_team_dashboard,
+        routes_triggers::get_triggers,
+        routes_triggers::create_trigger,
+        routes_triggers::delete_trigger,
+        routes_triggers::run_trigger,
+        routes_undo::undo_change,
+        routes_vacations::get_vacations,
+        routes_vacations::create_vacation,
+        routes_vacations::delete_vacation,
+        routes_webhooks::get_webhooks,
+        routes_webhooks::create_webhook,
+        routes_webhooks::update_webhook,
+        routes_webhooks::delete_webhook,
+        routes_webhooks::get_deliveries,
+        routes_webhooks::redeliver,
+    ),
+    tags(
+        (name = "auth", description = "Inscription et connexion"),
+        (name = "tasks", description = "Tâches, versionnées par `ETag` / `If-Match`"),
+        (name = "subtasks", description = "Sous-tâches"),
+        (name = "completions", description = "Complétions, valeurs, notes et sauts d'occurrences"),
+        (name = "trash", description = "Corbeille"),
+        (name = "history", description = "Journal des modifications"),
+        (name = "undo", description = "Annulation des dernières modifications"),
+        (name = "batch", description = "Lots d'opérations"),
+        (name = "sync", description = "Synchronisation hors ligne"),
+        (name = "events", description = "Notifications temps réel"),
+        (name = "stats", description = "Statistiques"),
+        (name = "journal", description = "Notes des occurrences"),
+        (name = "tags", description = "Étiquettes"),
+        (name = "teams", description = "Équipes et modèles de tâches"),
+        (name = "vacations", description = "Vacances"),
+        (name = "notifications", description = "Rappels, récapitulatif et canaux"),
+        (name = "webhooks", description = "Webhooks sortants"),
+        (name = "triggers", description = "URL de déclenchement"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Schéma d'authentification `Authorization: Bearer <jeton>`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .bearer_format("JWT")
+                        .build(),
+                ),
+            );
+    }
+}
+
+/// Spécification JSON et page Swagger UI, sans authentification
+pub fn docs_routes() -> Router {
+    SwaggerUi::new(DOCS_PATH)
+        .url(SPEC_PATH, ApiDoc::openapi())
+        .into()
+}
+
+/// Mêmes spécification et page aux anciens chemins, tant que l'alias `/api` est servi
+pub fn legacy_docs_routes() -> Router {
+    SwaggerUi::new(LEGACY_DOCS_PATH)
+        .url(LEGACY_SPEC_PATH, ApiDoc::openapi())
+        .into()
+}
@@ -0,0 +1,83 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{HeaderValue, StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// Versions attendues par le client, lues dans l'en-tête `If-Match`
+/// (None pour `If-Match: *`, qui accepte n'importe quelle version)
+pub struct IfMatch(pub Option<Vec<i32>>);
+
+impl IfMatch {
+    pub fn matches(&self, version: i32) -> bool {
+        self.0.as_ref().is_none_or(|versions| versions.contains(&version))
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = StatusCode;
+
+    /// 428 si l'en-tête est absent, 400 s'il est mal formé
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let value = parts
+            .headers
+            .get(header::IF_MATCH)
+            .ok_or(StatusCode::PRECONDITION_REQUIRED)?
+            .to_str()
+            .map_err(|_| StatusCode::BAD_REQUEST)?
+            .trim();
+
+        if value == "*" {
+            return Ok(IfMatch(None));
+        }
+
+        value
+            .split(',')
+            .map(|tag| {
+                let tag = tag.trim();
+                let tag = tag.strip_prefix("W/").unwrap_or(tag);
+                tag.trim_matches('"').parse::<i32>().map_err(|_| StatusCode::BAD_REQUEST)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|versions| IfMatch(Some(versions)))
+    }
+}
+
+/// Valeur de l'en-tête `ETag` pour une version
+pub fn etag(version: i32) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{}\"", version)).expect("ETag valide")
+}
+
+/// Réponse accompagnée de l'ETag de la version renvoyée
+pub fn with_etag(version: i32, body: serde_json::Value) -> Response {
+    ([(header::ETAG, etag(version))], Json(body)).into_response()
+}
+
+/// Erreur des écritures conditionnelles : un code HTTP simple, ou une version
+/// périmée (412, avec la représentation actuelle et son ETag)
+pub enum WriteError {
+    Status(StatusCode),
+    Stale { version: i32, current: serde_json::Value },
+}
+
+impl From<StatusCode> for WriteError {
+    fn from(status: StatusCode) -> Self {
+        WriteError::Status(status)
+    }
+}
+
+impl IntoResponse for WriteError {
+    fn into_response(self) -> Response {
+        match self {
+            WriteError::Status(status) => status.into_response(),
+            WriteError::Stale { version, current } => {
+                let mut response = with_etag(version, current);
+                *response.status_mut() = StatusCode::PRECONDITION_FAILED;
+                response
+            }
+        }
+    }
+}
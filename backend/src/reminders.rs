@@ -0,0 +1,147 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::notifier::{Notification, Notifier, PushSubscription, Recipient};
+
+/// Enregistre les rappels dus et renvoie ceux à envoyer : tâche prévue aujourd'hui,
+/// ni complétée ni excusée, heure de rappel passée et hors heures calmes.
+/// Un rappel tombé pendant les heures calmes part à leur fin, s'il est encore dû.
+/// Chaque rappel n'est enregistré qu'une fois par jour, même avec plusieurs instances.
+pub async fn claim_due_reminders(pool: &PgPool) -> Result<Vec<Notification>, sqlx::Error> {
+    sqlx::query_as!(
+        Notification,
+        r#"
+        INSERT INTO notifications (user_id, kind, task_id, reminder_id, date, title, body)
+        SELECT t.user_id, 'reminder', t.id, r.id, current_date,
+               'Rappel : ' || t.title, 'Pas encore faite aujourd''hui'
+        FROM task_reminders r
+        JOIN tasks t ON t.id = r.task_id
+        JOIN users u ON u.id = t.user_id
+        JOIN task_days td ON td.task_id = t.id AND td.day_of_week = extract(isodow from current_date)
+        LEFT JOIN notification_settings s ON s.user_id = t.user_id
+        WHERE t.active AND NOT t.deleted AND u.disabled_at IS NULL
+          AND r.remind_at <= LOCALTIME
+          -- Un rappel ajouté après son heure ne part qu'à partir du lendemain
+          AND NOT (r.created_at::date = current_date AND r.created_at::time > r.remind_at)
+          AND NOT in_quiet_hours(LOCALTIME, s.quiet_start, s.quiet_end)
+          AND NOT EXISTS (
+              SELECT 1 FROM task_completions tc
+              WHERE tc.task_id = t.id AND tc.date = current_date AND tc.completed
+          )
+          AND NOT is_excused(t.id, current_date)
+        ON CONFLICT (reminder_id, date) WHERE kind = 'reminder' DO NOTHING
+        RETURNING id, user_id, kind, task_id, date, title, body, created_at
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Enregistre les récapitulatifs dus (un par jour, à l'heure choisie, hors heures calmes)
+/// pour les utilisateurs qui ont des tâches prévues aujourd'hui
+pub async fn claim_due_digests(pool: &PgPool) -> Result<Vec<Notification>, sqlx::Error> {
+    sqlx::query_as!(
+        Notification,
+        r#"
+        INSERT INTO notifications (user_id, kind, date, title, body)
+        SELECT s.user_id, 'digest', current_date,
+               'Récapitulatif du ' || to_char(current_date, 'DD/MM/YYYY'),
+               CASE WHEN d.remaining = 0
+                    THEN format('Les %s tâche(s) du jour sont faites.', d.scheduled)
+                    ELSE format('%s tâche(s) sur %s à faire : %s', d.remaining, d.scheduled, d.titles)
+               END
+        FROM notification_settings s
+        JOIN users u ON u.id = s.user_id
+        CROSS JOIN LATERAL (
+            SELECT COUNT(*) AS scheduled,
+                   COUNT(*) FILTER (WHERE NOT COALESCE(tc.completed, false)) AS remaining,
+                   string_agg(t.title, ', ' ORDER BY t.title)
+                       FILTER (WHERE NOT COALESCE(tc.completed, false)) AS titles
+            FROM tasks t
+            JOIN task_days td ON td.task_id = t.id AND td.day_of_week = extract(isodow from current_date)
+            LEFT JOIN task_completions tc ON tc.task_id = t.id AND tc.date = current_date
+            WHERE t.user_id = s.user_id AND t.active AND NOT t.deleted
+              AND (COALESCE(tc.completed, false) OR NOT is_excused(t.id, current_date))
+        ) d
+        WHERE s.digest_at <= LOCALTIME AND u.disabled_at IS NULL
+          AND NOT in_quiet_hours(LOCALTIME, s.quiet_start, s.quiet_end)
+          AND d.scheduled > 0
+        ON CONFLICT (user_id, date) WHERE kind = 'digest' DO NOTHING
+        RETURNING id, user_id, kind, task_id, date, title, body, created_at
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Canaux configurés par l'utilisateur
+pub async fn recipient(pool: &PgPool, user_id: Uuid) -> Result<Recipient, sqlx::Error> {
+    let user = sqlx::query!(
+        r#"
+        SELECT u.username, s.webhook_url AS "webhook_url?", s.email AS "email?"
+        FROM users u
+        LEFT JOIN notification_settings s ON s.user_id = u.id
+        WHERE u.id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let push_subscriptions = sqlx::query_as!(
+        PushSubscription,
+        "SELECT id, endpoint, p256dh, auth FROM push_subscriptions WHERE user_id = $1",
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Recipient {
+        user_id,
+        username: user.username,
+        webhook_url: user.webhook_url,
+        email: user.email,
+        push_subscriptions,
+    })
+}
+
+/// Envoie une notification sur tous les canaux. Un échec est journalisé sans
+/// nouvel essai : la notification reste consultable dans `GET /api/v1/notifications`.
+pub async fn dispatch(pool: &PgPool, notifiers: &[Box<dyn Notifier>], notification: &Notification) {
+    let recipient = match recipient(pool, notification.user_id).await {
+        Ok(recipient) => recipient,
+        Err(e) => {
+            eprintln!(
+                "Erreur destinataire notification {}: {}",
+                notification.id, e
+            );
+            return;
+        }
+    };
+
+    for notifier in notifiers {
+        if let Err(e) = notifier.send(&recipient, notification).await {
+            eprintln!(
+                "Erreur envoi notification {} ({}): {}",
+                notification.id,
+                notifier.name(),
+                e
+            );
+        }
+    }
+}
+
+/// Enregistre puis envoie les rappels et récapitulatifs dus, renvoie leur nombre
+pub async fn send_due(
+    pool: &PgPool,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<usize, sqlx::Error> {
+    let mut due = claim_due_reminders(pool).await?;
+    due.extend(claim_due_digests(pool).await?);
+
+    for notification in &due {
+        dispatch(pool, notifiers, notification).await;
+    }
+
+    Ok(due.len())
+}
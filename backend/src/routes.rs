@@ -1,47 +1,454 @@
 use axum::{
     Extension, Json, Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    routing::{get, post},
+    routing::{delete, get, post, put},
 };
-use sqlx::PgPool;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
 use uuid::Uuid;
-use serde::Deserialize;
 
-use crate::models::{CreateTaskRequest, UpdateTaskRequest, CreateSubtaskRequest, UpdateSubtaskRequest, ToggleSubtaskRequest};
+use crate::audit::{self, AuditEntry};
+use crate::jobs::trash_retention_days;
+use crate::precondition::{IfMatch, WriteError, with_etag};
+use crate::stats;
+use crate::undo;
+use crate::models::{CreateTaskRequest, UpdateTaskRequest, CreateSubtaskRequest, UpdateSubtaskRequest, ToggleSubtaskRequest, TagFilter, TaskListQuery, LogValueRequest, CompletionNoteRequest, SkipRequest, SetArchivedRequest, SetCompletionRequest, StatsQuery, Subtask, SubtaskUpdate, UpdatePrioritiesRequest};
 
 pub fn routes(pool: PgPool) -> Router {
     Router::new()
         .route("/tasks", get(get_today_tasks).post(create_task))
         .route("/tasks/all", get(get_all_tasks))
+        .route("/tasks/trash", get(get_trash).delete(empty_trash))
+        .route("/tasks/:id/restore", post(restore_task))
+        .route("/tasks/:id/permanent", delete(purge_task))
         .route(
             "/tasks/:id",
-            post(update_task).delete(delete_task).patch(toggle_archive),
+            get(get_task).post(update_task).delete(delete_task).patch(toggle_archive),
         )
         .route("/tasks/:id/toggle", post(toggle_task))
+        .route("/tasks/:id/completion", put(set_completion))
+        .route("/tasks/:id/archived", put(set_archived))
+        .route("/tasks/:id/log", post(log_task_value))
+        .route("/tasks/:id/note", post(set_completion_note))
+        .route("/tasks/:id/skip", post(skip_task))
         .route("/tasks/:id/subtasks", get(get_subtasks).post(create_subtask))
         .route("/tasks/:task_id/subtasks/:subtask_id", 
             post(update_subtask).delete(delete_subtask))
         .route("/subtasks/toggle", post(toggle_subtask))
         .route("/stats", get(get_stats))
+        .route("/stats/measurements", get(get_measurement_stats))
         .route("/tasks/priorities", post(update_task_priorities))
         .with_state(pool)
 }
 
+// --- OUTILS ---
+
+/// Transforme le filtre `?tags=1,2` en liste d'identifiants (None si absent)
+fn parse_tag_filter(filter: &TagFilter) -> Result<Option<Vec<i32>>, StatusCode> {
+    parse_id_list(filter.tags.as_deref())
+}
+
+/// Transforme une liste `1,2,3` en entiers (None si absente ou vide)
+fn parse_id_list(list: Option<&str>) -> Result<Option<Vec<i32>>, StatusCode> {
+    match list.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(ids) => ids
+            .split(',')
+            .map(|id| id.trim().parse::<i32>().map_err(|_| StatusCode::BAD_REQUEST))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+    }
+}
+
+/// Position dans une liste de tâches triée : clé de tri et identifiant du dernier élément.
+/// Le tri et le sens y figurent pour refuser un curseur réutilisé avec d'autres paramètres.
+#[derive(Serialize, Deserialize)]
+struct ListCursor {
+    sort: String,
+    desc: bool,
+    text: String,
+    num: f64,
+    id: i32,
+}
+
+impl ListCursor {
+    /// Encode le curseur en hexadécimal pour qu'il passe tel quel dans une URL
+    fn encode(&self) -> String {
+        serde_json::to_vec(self)
+            .unwrap_or_default()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        if !cursor.len().is_multiple_of(2) || !cursor.is_ascii() {
+            return None;
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// Valide le couple type de mesure / objectif : une tâche chiffrée exige un objectif positif,
+/// une tâche booléenne n'en a pas
+fn validate_measurement(
+    measurement_type: Option<&str>,
+    target: Option<f64>,
+) -> Result<(String, Option<f64>), StatusCode> {
+    match (measurement_type.unwrap_or("boolean"), target) {
+        ("boolean", _) => Ok(("boolean".to_string(), None)),
+        (kind @ ("count" | "duration" | "value"), Some(target)) if target > 0.0 => {
+            Ok((kind.to_string(), Some(target)))
+        }
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// Pourcentage de progression d'une valeur vers son objectif (plafonné à 100)
+fn progress_percent(value: f64, target: f64) -> i32 {
+    ((value / target).min(1.0) * 100.0).round() as i32
+}
+
+/// Vérifie qu'une note de ressenti est comprise entre 1 et 5
+fn validate_rating(rating: Option<i16>) -> Result<(), StatusCode> {
+    match rating {
+        Some(r) if !(1..=5).contains(&r) => Err(StatusCode::BAD_REQUEST),
+        _ => Ok(()),
+    }
+}
+
+/// Remplace les étiquettes d'une tâche (seules celles de l'utilisateur sont retenues)
+async fn set_task_tags(
+    tx: &mut Transaction<'_, Postgres>,
+    task_id: i32,
+    user_id: Uuid,
+    tag_ids: &[i32],
+) -> Result<(), StatusCode> {
+    sqlx::query!("DELETE FROM task_tags WHERE task_id = $1", task_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
+            eprintln!("Erreur suppression étiquettes: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO task_tags (task_id, tag_id)
+        SELECT $1, id FROM tags WHERE id = ANY($2) AND user_id = $3
+        "#,
+        task_id,
+        tag_ids,
+        user_id
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur ajout étiquettes: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
+
+/// Aligne les sous-tâches d'une tâche sur la liste fournie, sans recréer les existantes :
+/// mise à jour par identifiant, ajout des nouvelles, suppression des absentes, et l'ordre
+/// de la liste devient la priorité. Renvoie 409 si une sous-tâche a été supprimée ou
+/// modifiée (`version` différente) entre-temps.
+async fn reconcile_subtasks(
+    tx: &mut Transaction<'_, Postgres>,
+    task_id: i32,
+    subtasks: &[SubtaskUpdate],
+) -> Result<(), StatusCode> {
+    let subtasks: Vec<_> = subtasks
+        .iter()
+        .filter(|st| !st.title.trim().is_empty())
+        .collect();
+
+    // Verrouiller les sous-tâches actuelles le temps de la réconciliation
+    let existing = sqlx::query!(
+        "SELECT id, version FROM subtasks WHERE task_id = $1 FOR UPDATE",
+        task_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération sous-tâches: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut kept_ids = Vec::new();
+    for subtask in &subtasks {
+        let Some(id) = subtask.id else { continue };
+        if kept_ids.contains(&id) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let current = existing
+            .iter()
+            .find(|st| st.id == id)
+            .ok_or(StatusCode::CONFLICT)?;
+        if subtask.version.is_some_and(|v| v != current.version) {
+            return Err(StatusCode::CONFLICT);
+        }
+        kept_ids.push(id);
+    }
+
+    sqlx::query!(
+        "DELETE FROM subtasks WHERE task_id = $1 AND NOT (id = ANY($2))",
+        task_id,
+        &kept_ids
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur suppression sous-tâches: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    for (priority, subtask) in subtasks.iter().enumerate() {
+        match subtask.id {
+            Some(id) => {
+                // L'état de complétion est conservé s'il n'est pas fourni
+                sqlx::query!(
+                    r#"
+                    UPDATE subtasks
+                    SET title = $1, priority = $2, completed = COALESCE($3, completed)
+                    WHERE id = $4 AND task_id = $5
+                    "#,
+                    subtask.title.trim(),
+                    priority as i32,
+                    subtask.completed,
+                    id,
+                    task_id
+                )
+                .execute(&mut **tx)
+                .await
+                .map_err(|e| {
+                    eprintln!("Erreur mise à jour sous-tâche: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            }
+            None => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO subtasks (task_id, title, priority, completed)
+                    VALUES ($1, $2, $3, COALESCE($4, false))
+                    "#,
+                    task_id,
+                    subtask.title.trim(),
+                    priority as i32,
+                    subtask.completed
+                )
+                .execute(&mut **tx)
+                .await
+                .map_err(|e| {
+                    eprintln!("Erreur insertion sous-tâche: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            }
+        }
+    }
+
+    sqlx::query!(
+        "UPDATE tasks SET has_subtasks = $1 WHERE id = $2",
+        !subtasks.is_empty(),
+        task_id
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur mise à jour has_subtasks: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
+
+/// Représentation complète des tâches de l'utilisateur (toutes si `task_ids` vaut None)
+pub(crate) async fn task_representations(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    task_ids: Option<&[i32]>,
+) -> Result<Vec<serde_json::Value>, StatusCode> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT json_build_object(
+                   'id', t.id,
+                   'title', t.title,
+                   'active', t.active,
+                   'deleted', t.deleted,
+                   'has_subtasks', t.has_subtasks,
+                   'measurement_type', t.measurement_type,
+                   'target', t.target,
+                   'unit', t.unit,
+                   'version', t.version,
+                   'days', COALESCE((
+                       SELECT json_agg(td.day_of_week ORDER BY td.day_of_week)
+                       FROM task_days td WHERE td.task_id = t.id
+                   ), '[]'),
+                   'tags', COALESCE((
+                       SELECT json_agg(json_build_object('id', g.id, 'name', g.name, 'color', g.color) ORDER BY g.name)
+                       FROM task_tags tt JOIN tags g ON g.id = tt.tag_id
+                       WHERE tt.task_id = t.id
+                   ), '[]'),
+                   'subtasks', COALESCE((
+                       SELECT json_agg(json_build_object(
+                           'id', s.id, 'title', s.title, 'completed', s.completed,
+                           'priority', s.priority, 'version', s.version
+                       ) ORDER BY s.priority, s.id)
+                       FROM subtasks s WHERE s.task_id = t.id
+                   ), '[]')
+               ) as "task!: serde_json::Value"
+        FROM tasks t
+        WHERE t.user_id = $1 AND ($2::int[] IS NULL OR t.id = ANY($2))
+        ORDER BY t.id
+        "#,
+        user_id,
+        task_ids
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération tâche: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Représentation complète d'une tâche de l'utilisateur et sa version (renvoyée en ETag)
+async fn task_representation(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    task_id: i32,
+) -> Result<Option<(i32, serde_json::Value)>, StatusCode> {
+    let task = task_representations(conn, user_id, Some(&[task_id])).await?.pop();
+    Ok(task.map(|t| (t["version"].as_i64().unwrap_or_default() as i32, t)))
+}
+
+/// Verrouille la tâche et vérifie que sa version correspond à l'en-tête `If-Match`
+/// (412 avec la représentation actuelle sinon)
+async fn check_task_version(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    task_id: i32,
+    if_match: &IfMatch,
+) -> Result<(), WriteError> {
+    let version = sqlx::query_scalar!(
+        "SELECT version FROM tasks WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        task_id,
+        user_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !if_match.matches(version) {
+        let (version, current) = task_representation(tx, user_id, task_id)
+            .await?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        return Err(WriteError::Stale { version, current });
+    }
+
+    Ok(())
+}
+
+/// Verrouille la sous-tâche et vérifie que sa version correspond à l'en-tête `If-Match`
+async fn check_subtask_version(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    task_id: i32,
+    subtask_id: i32,
+    if_match: &IfMatch,
+) -> Result<(), WriteError> {
+    let subtask = sqlx::query!(
+        r#"
+        SELECT s.id, s.title, s.completed, s.priority, s.version
+        FROM subtasks s
+        JOIN tasks t ON t.id = s.task_id
+        WHERE s.id = $1 AND s.task_id = $2 AND t.user_id = $3
+        FOR UPDATE OF s
+        "#,
+        subtask_id,
+        task_id,
+        user_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !if_match.matches(subtask.version) {
+        return Err(WriteError::Stale {
+            version: subtask.version,
+            current: serde_json::json!({
+                "id": subtask.id,
+                "title": subtask.title,
+                "completed": subtask.completed,
+                "priority": subtask.priority,
+                "version": subtask.version
+            }),
+        });
+    }
+
+    Ok(())
+}
+
+/// Version actuelle d'une sous-tâche, pour l'ETag des réponses
+async fn subtask_version(conn: &mut PgConnection, subtask_id: i32) -> Result<i32, StatusCode> {
+    sqlx::query_scalar!("SELECT version FROM subtasks WHERE id = $1", subtask_id)
+        .fetch_one(conn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
 // --- GESTIONNAIRES (HANDLERS) ---
 
 /// Récupère les tâches prévues pour aujourd'hui avec leurs sous-tâches
+#[utoipa::path(
+    get,
+    path = "/tasks",
+    tag = "tasks",
+    params(TagFilter),
+    responses(
+        (status = 200, description = "Tâches prévues aujourd'hui", body = Vec<serde_json::Value>),
+        (status = 400, description = "Filtre invalide"),
+    )
+)]
 pub async fn get_today_tasks(
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
+    Query(filter): Query<TagFilter>,
 ) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let tag_ids = parse_tag_filter(&filter)?;
+
     // Récupérer les tâches du jour avec ou sans completion
     let tasks = sqlx::query!(
         r#"
-        SELECT t.id, t.title, t.active, 
+        SELECT t.id, t.title, t.active, t.measurement_type, t.target, t.unit, t.version,
                COALESCE(tc.completed, false) as "completed!",
                COALESCE(tc.priority, 0) as "priority!",
-               EXISTS(SELECT 1 FROM subtasks s WHERE s.task_id = t.id) as "has_subtasks!"
+               COALESCE(tc.value, 0) as "value!",
+               COALESCE(tc.skipped AND NOT tc.completed, false) as "skipped!",
+               tc.skip_reason as "skip_reason?",
+               -- Hors vacances, seule une occurrence sautée est excusée : `is_excused` n'est appelé
+               -- que si des vacances de l'utilisateur couvrent la journée
+               EXISTS(SELECT 1 FROM vacations v WHERE v.user_id = $1
+                      AND current_date BETWEEN v.start_date AND v.end_date)
+                   AND is_excused(t.id, current_date)
+                   AND NOT COALESCE(tc.skipped, false) as "paused!",
+               EXISTS(SELECT 1 FROM subtasks s WHERE s.task_id = t.id) as "has_subtasks!",
+               COALESCE((
+                   SELECT json_agg(json_build_object('id', g.id, 'name', g.name, 'color', g.color) ORDER BY g.name)
+                   FROM task_tags tt JOIN tags g ON g.id = tt.tag_id
+                   WHERE tt.task_id = t.id
+               ), '[]') as "tags!: serde_json::Value"
         FROM tasks t
         JOIN task_days td ON t.id = td.task_id
         LEFT JOIN task_completions tc ON t.id = tc.task_id AND tc.date = current_date
@@ -49,9 +456,12 @@ pub async fn get_today_tasks(
           AND td.day_of_week = extract(isodow from current_date)
           AND t.active = true 
           AND t.deleted = false
+          AND ($2::int[] IS NULL OR EXISTS(
+              SELECT 1 FROM task_tags tt WHERE tt.task_id = t.id AND tt.tag_id = ANY($2)))
         ORDER BY tc.priority ASC, t.id ASC
         "#,
-        user_id
+        user_id,
+        tag_ids.as_deref()
     )
     .fetch_all(&pool)
     .await
@@ -60,29 +470,35 @@ pub async fn get_today_tasks(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    // Sous-tâches de toutes les tâches du jour en une seule requête
+    let task_ids: Vec<i32> = tasks.iter().filter(|t| t.has_subtasks).map(|t| t.id).collect();
+    let mut subtasks_by_task: HashMap<i32, Vec<Subtask>> = HashMap::new();
+    if !task_ids.is_empty() {
+        let subtasks = sqlx::query_as!(
+            Subtask,
+            r#"
+            SELECT id, task_id, title, completed, priority, version
+            FROM subtasks
+            WHERE task_id = ANY($1)
+            ORDER BY priority ASC, id ASC
+            "#,
+            &task_ids
+        )
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Erreur lors de la récupération des sous-tâches: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        for subtask in subtasks {
+            subtasks_by_task.entry(subtask.task_id).or_default().push(subtask);
+        }
+    }
+
     let mut result = Vec::new();
-    
+
     for task in tasks {
-        // Récupérer les sous-tâches SI la tâche en a
-        let subtasks = if task.has_subtasks {
-            sqlx::query!(
-                r#"
-                SELECT id, title, completed, priority
-                FROM subtasks
-                WHERE task_id = $1
-                ORDER BY priority ASC, id ASC
-                "#,
-                task.id
-            )
-            .fetch_all(&pool)
-            .await
-            .map_err(|e| {
-                eprintln!("Erreur lors de la récupération des sous-tâches: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?
-        } else {
-            vec![]
-        };
+        let subtasks = subtasks_by_task.remove(&task.id).unwrap_or_default();
 
         // Calculer si la tâche est complétée (toutes les sous-tâches doivent être complétées)
         let all_subtasks_completed = !subtasks.is_empty() && subtasks.iter().all(|st| st.completed);
@@ -100,21 +516,29 @@ pub async fn get_today_tasks(
             (completed_count as f64 / subtasks.len() as f64 * 100.0) as i32
         };
 
+        // Progression des tâches chiffrées vers l'objectif du jour
+        let progress = task.target.map(|target| progress_percent(task.value, target));
+
         let task_json = serde_json::json!({
             "id": task.id,
             "title": task.title,
             "active": task.active,
+            "version": task.version,
             "completed": completed,
             "priority": task.priority,
+            "measurement_type": task.measurement_type,
+            "target": task.target,
+            "unit": task.unit,
+            "value": task.target.map(|_| task.value),
+            "progress": progress,
+            "skipped": task.skipped,
+            "skip_reason": task.skip_reason,
+            "paused": task.paused,
             "has_subtasks": task.has_subtasks,
+            "tags": task.tags,
             "subtasks_count": subtasks.len(),
             "subtask_completion": subtask_completion,
-            "subtasks": subtasks.into_iter().map(|st| serde_json::json!({
-                "id": st.id,
-                "title": st.title,
-                "completed": st.completed,
-                "priority": st.priority
-            })).collect::<Vec<_>>()
+            "subtasks": subtasks
         });
         
         result.push(task_json);
@@ -124,6 +548,16 @@ pub async fn get_today_tasks(
 }
 
 /// Crée une nouvelle tâche avec éventuellement des sous-tâches
+#[utoipa::path(
+    post,
+    path = "/tasks",
+    tag = "tasks",
+    request_body = CreateTaskRequest,
+    responses(
+        (status = 201, description = "Tâche créée"),
+        (status = 400, description = "Données invalides"),
+    )
+)]
 pub async fn create_task(
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
@@ -134,13 +568,38 @@ pub async fn create_task(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    insert_task(&mut tx, user_id, payload).await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::CREATED)
+}
+
+/// Crée une tâche avec ses jours, sous-tâches et étiquettes, dans la transaction de l'appelant
+pub(crate) async fn insert_task(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    payload: CreateTaskRequest,
+) -> Result<i32, StatusCode> {
+    let (measurement_type, target) =
+        validate_measurement(payload.measurement_type.as_deref(), payload.target)?;
+
     // Créer la tâche principale
     let task = sqlx::query!(
-        "INSERT INTO tasks (user_id, title) VALUES ($1, $2) RETURNING id",
+        r#"
+        INSERT INTO tasks (user_id, title, measurement_type, target, unit)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
         user_id,
-        payload.title
+        payload.title,
+        measurement_type,
+        target,
+        payload.unit
     )
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut **tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -151,7 +610,7 @@ pub async fn create_task(
             task.id,
             day
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     }
@@ -165,7 +624,7 @@ pub async fn create_task(
                 title,
                 priority as i32
             )
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         }
@@ -175,34 +634,209 @@ pub async fn create_task(
             "UPDATE tasks SET has_subtasks = true WHERE id = $1",
             task.id
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     }
 
-    tx.commit()
+    // Associer les étiquettes
+    if let Some(tag_ids) = &payload.tags {
+        set_task_tags(tx, task.id, user_id, tag_ids).await?;
+    }
+
+    let after = audit::task_snapshot(tx, task.id).await?;
+    audit::record(tx, AuditEntry::task(user_id, task.id, "create").after(after)).await?;
+
+    Ok(task.id)
+}
+
+/// Récupère une tâche avec ses sous-tâches, sa version en ETag
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    responses(
+        (status = 200, description = "Tâche et sous-tâches, version dans `ETag`", body = serde_json::Value),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn get_task(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Response, StatusCode> {
+    let mut conn = pool
+        .acquire()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    Ok(StatusCode::CREATED)
+
+    let (version, task) = task_representation(&mut conn, user_id, id)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(with_etag(version, task))
 }
 
-/// Liste toutes les tâches avec leurs sous-tâches
+/// Liste les tâches avec leurs sous-tâches, filtrées, triées et paginées par curseur.
+/// Renvoie aussi le nombre de tâches correspondant aux filtres et les totaux de l'utilisateur.
+#[utoipa::path(
+    get,
+    path = "/tasks/all",
+    tag = "tasks",
+    params(TaskListQuery),
+    responses(
+        (status = 200, description = "Page de tâches (`tasks`, `next_cursor`, `total`, `totals`)", body = serde_json::Value),
+        (status = 400, description = "Filtre, tri ou curseur invalide"),
+    )
+)]
 pub async fn get_all_tasks(
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
-) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    Query(params): Query<TaskListQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let tag_ids = parse_id_list(params.tags.as_deref())?;
+    let days = parse_id_list(params.days.as_deref())?;
+    if days.iter().flatten().any(|d| !(1..=7).contains(d)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let active = match params.status.as_deref().unwrap_or("all") {
+        "all" => None,
+        "active" => Some(true),
+        "archived" => Some(false),
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    let sort = params.sort.unwrap_or_else(|| "created_at".to_string());
+    if !["title", "created_at", "priority", "completion_rate"].contains(&sort.as_str()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let desc = match params.order.as_deref().unwrap_or("asc") {
+        "asc" => false,
+        "desc" => true,
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+    let cursor = match params.cursor.as_deref().filter(|c| !c.is_empty()) {
+        Some(c) => {
+            let cursor = ListCursor::decode(c).ok_or(StatusCode::BAD_REQUEST)?;
+            if cursor.sort != sort || cursor.desc != desc {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            Some(cursor)
+        }
+        None => None,
+    };
+    let q = params.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+
+    // Chaque tâche reçoit une clé de tri texte (titre) et une numérique (le reste) :
+    // la comparaison de ligne (texte, nombre, id) sert à la fois au tri et au curseur.
+    // Priorité : celle du jour ; taux de complétion : 30 derniers jours, hors jours excusés,
+    // chaque jour étant jugé avec le planning alors en vigueur.
+    // Les jours excusés sont calculés ensemble (mêmes règles que `is_excused`) plutôt
+    // qu'occurrence par occurrence, ce qui coûterait un appel par tâche et par jour.
     let rows = sqlx::query!(
         r#"
-        SELECT t.id, t.title, t.active, t.has_subtasks,
-               array_agg(DISTINCT td.day_of_week) as "days!"
-        FROM tasks t
-        LEFT JOIN task_days td ON t.id = td.task_id
-        WHERE t.user_id = $1 AND t.deleted = false
-        GROUP BY t.id
-        ORDER BY t.id
+        WITH paused AS (
+            SELECT DISTINCT t.id AS task_id, current_date - n AS day
+            FROM vacations v
+            JOIN tasks t ON t.user_id = v.user_id
+            CROSS JOIN generate_series(0, 29) AS n
+            WHERE v.user_id = $1
+              AND current_date - n BETWEEN v.start_date AND v.end_date
+              AND (v.all_tasks
+                   OR EXISTS (SELECT 1 FROM vacation_tasks vt WHERE vt.vacation_id = v.id AND vt.task_id = t.id))
+        ),
+        rates AS (
+            SELECT td.task_id,
+                   (COUNT(*) FILTER (WHERE c.completed))::float8 / NULLIF(COUNT(*) FILTER (
+                       WHERE c.completed OR NOT (COALESCE(c.skipped, false) OR p.task_id IS NOT NULL)
+                   ), 0) AS completion_rate
+            FROM tasks t
+            JOIN task_schedules td ON td.task_id = t.id
+            JOIN generate_series(0, 29) AS n ON td.day_of_week = extract(isodow from current_date - n)
+                AND current_date - n >= td.effective_from
+                AND (td.effective_to IS NULL OR current_date - n < td.effective_to)
+                AND current_date - n >= t.created_at::date
+            LEFT JOIN task_completions c ON c.task_id = td.task_id AND c.date = current_date - n
+            LEFT JOIN paused p ON p.task_id = td.task_id AND p.day = current_date - n
+            WHERE t.user_id = $1 AND t.deleted = false
+            GROUP BY td.task_id
+        ),
+        filtered AS (
+            SELECT t.id, t.title, t.active, t.has_subtasks, t.version,
+                   t.measurement_type, t.target, t.unit, t.created_at,
+                   COALESCE(tc.priority, 0) AS priority,
+                   rate.completion_rate,
+                   CASE WHEN $7 = 'title' THEN lower(t.title) ELSE '' END AS sort_text,
+                   CASE $7
+                       WHEN 'created_at' THEN extract(epoch FROM t.created_at)::float8
+                       WHEN 'priority' THEN COALESCE(tc.priority, 0)::float8
+                       WHEN 'completion_rate' THEN COALESCE(rate.completion_rate, -1)
+                       ELSE 0
+                   END AS sort_num
+            FROM tasks t
+            LEFT JOIN task_completions tc ON tc.task_id = t.id AND tc.date = current_date
+            LEFT JOIN rates rate ON rate.task_id = t.id
+            WHERE t.user_id = $1 AND t.deleted = false
+              AND ($2::text IS NULL OR strpos(lower(t.title), lower($2)) > 0)
+              AND ($3::int[] IS NULL OR EXISTS(
+                  SELECT 1 FROM task_days td WHERE td.task_id = t.id AND td.day_of_week = ANY($3)))
+              AND ($4::bool IS NULL OR t.active = $4)
+              AND ($5::bool IS NULL OR t.has_subtasks = $5)
+              AND ($6::int[] IS NULL OR EXISTS(
+                  SELECT 1 FROM task_tags tt WHERE tt.task_id = t.id AND tt.tag_id = ANY($6)))
+        ),
+        page AS (
+            SELECT f.*,
+                   row_number() OVER (ORDER BY
+                       CASE WHEN $8 THEN f.sort_text END DESC,
+                       CASE WHEN $8 THEN f.sort_num END DESC,
+                       CASE WHEN $8 THEN f.id END DESC,
+                       f.sort_text, f.sort_num, f.id) AS rn
+            FROM filtered f
+            WHERE $11::int IS NULL
+               OR CASE WHEN $8
+                      THEN (f.sort_text, f.sort_num, f.id) < ($9::text, $10::float8, $11)
+                      ELSE (f.sort_text, f.sort_num, f.id) > ($9, $10, $11)
+                  END
+            ORDER BY rn
+            LIMIT $12
+        )
+        SELECT (SELECT COUNT(*) FROM filtered) AS "total!",
+               p.id AS "id?", p.title AS "title?", p.active AS "active?",
+               p.has_subtasks AS "has_subtasks?", p.version AS "version?",
+               p.measurement_type AS "measurement_type?", p.target, p.unit,
+               p.created_at AS "created_at?", p.priority AS "priority?", p.completion_rate,
+               p.sort_text AS "sort_text?", p.sort_num AS "sort_num?",
+               COALESCE((SELECT array_agg(td.day_of_week ORDER BY td.day_of_week)
+                         FROM task_days td WHERE td.task_id = p.id), '{}') AS "days!",
+               COALESCE((
+                   SELECT json_agg(json_build_object('id', g.id, 'name', g.name, 'color', g.color) ORDER BY g.name)
+                   FROM task_tags tt JOIN tags g ON g.id = tt.tag_id
+                   WHERE tt.task_id = p.id
+               ), '[]') AS "tags!: serde_json::Value",
+               COALESCE((
+                   SELECT json_agg(json_build_object(
+                       'id', s.id, 'title', s.title, 'completed', s.completed,
+                       'priority', s.priority, 'version', s.version) ORDER BY s.priority, s.id)
+                   FROM subtasks s WHERE s.task_id = p.id
+               ), '[]') AS "subtasks!: serde_json::Value"
+        FROM (SELECT 1) AS one
+        LEFT JOIN page p ON true
+        ORDER BY p.rn
         "#,
-        user_id
+        user_id,
+        q,
+        days.as_deref(),
+        active,
+        params.has_subtasks,
+        tag_ids.as_deref(),
+        sort,
+        desc,
+        cursor.as_ref().map(|c| c.text.as_str()),
+        cursor.as_ref().map(|c| c.num),
+        cursor.as_ref().map(|c| c.id),
+        limit + 1
     )
     .fetch_all(&pool)
     .await
@@ -211,62 +845,143 @@ pub async fn get_all_tasks(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    let mut result = Vec::new();
-    
-    for row in rows {
-        // Récupérer les sous-tâches si la tâche en a
-        let subtasks = if row.has_subtasks {
-            sqlx::query!(
-                "SELECT id, title, completed, priority FROM subtasks WHERE task_id = $1 ORDER BY priority ASC",
-                row.id
-            )
-            .fetch_all(&pool)
-            .await
-            .map_err(|e| {
-                eprintln!("Erreur récupération sous-tâches: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?
-        } else {
-            vec![]
-        };
-
-        let task_json = serde_json::json!({
-            "id": row.id,
+    let total = rows.first().map(|r| r.total).unwrap_or(0);
+    // Une ligne de plus que la limite indique qu'une page suit
+    let has_more = rows.len() as i64 > limit;
+    let mut tasks = Vec::new();
+    let mut next_cursor = None;
+
+    for row in rows.into_iter().take(limit as usize) {
+        let Some(id) = row.id else { break };
+        if has_more {
+            next_cursor = Some(ListCursor {
+                sort: sort.clone(),
+                desc,
+                text: row.sort_text.unwrap_or_default(),
+                num: row.sort_num.unwrap_or_default(),
+                id,
+            });
+        }
+        let subtasks_count = row.subtasks.as_array().map_or(0, Vec::len);
+        tasks.push(serde_json::json!({
+            "id": id,
             "title": row.title,
             "active": row.active,
             "has_subtasks": row.has_subtasks,
             "days": row.days,
-            "subtasks_count": subtasks.len(),
-            "subtasks": subtasks.into_iter().map(|st| serde_json::json!({
-                "id": st.id,
-                "title": st.title,
-                "completed": st.completed,
-                "priority": st.priority
-            })).collect::<Vec<_>>()
-        });
-        
-        result.push(task_json);
+            "tags": row.tags,
+            "measurement_type": row.measurement_type,
+            "target": row.target,
+            "unit": row.unit,
+            "version": row.version,
+            "created_at": row.created_at,
+            "priority": row.priority,
+            "completion_rate": row.completion_rate.map(|r| (r * 100.0).round()),
+            "subtasks_count": subtasks_count,
+            "subtasks": row.subtasks
+        }));
     }
 
-    Ok(Json(result))
+    let counts = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "all!",
+               COUNT(*) FILTER (WHERE t.active) AS "active!",
+               COUNT(*) FILTER (WHERE NOT t.active) AS "archived!",
+               (SELECT COUNT(*) FROM subtasks s JOIN tasks st ON st.id = s.task_id
+                WHERE st.user_id = $1 AND st.deleted = false) AS "subtasks!",
+               (SELECT COUNT(DISTINCT td.day_of_week) FROM task_days td JOIN tasks dt ON dt.id = td.task_id
+                WHERE dt.user_id = $1 AND dt.deleted = false) AS "days_covered!"
+        FROM tasks t
+        WHERE t.user_id = $1 AND t.deleted = false
+        "#,
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur comptage tâches: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(serde_json::json!({
+        "tasks": tasks,
+        "next_cursor": next_cursor.map(|c| c.encode()),
+        "total": total,
+        "counts": {
+            "all": counts.all,
+            "active": counts.active,
+            "archived": counts.archived,
+            "subtasks": counts.subtasks,
+            "days_covered": counts.days_covered
+        }
+    })))
 }
 
-/// Marque une tâche comme complétée ou non pour la journée actuelle
+/// Marque une tâche comme complétée ou non pour la journée actuelle.
+/// Le corps optionnel permet de joindre une note et un ressenti à l'occurrence.
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/toggle",
+    tag = "completions",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    request_body = Option<CompletionNoteRequest>,
+    responses(
+        (status = 200, description = "Occurrence du jour basculée"),
+        (status = 400, description = "Données invalides, ou tâche chiffrée (utiliser `/log`)"),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
 pub async fn toggle_task(
     Path(id): Path<i32>,
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
+    payload: Option<Json<CompletionNoteRequest>>,
 ) -> Result<StatusCode, StatusCode> {
+    let note = payload.map(|Json(p)| p);
+    validate_rating(note.as_ref().and_then(|n| n.rating))?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    apply_toggle(&mut tx, user_id, id, note, "toggle").await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Bascule la complétion du jour d'une tâche (et de ses sous-tâches) dans la transaction de l'appelant.
+/// `action` est l'action inscrite au journal d'audit.
+pub(crate) async fn apply_toggle(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    id: i32,
+    note: Option<CompletionNoteRequest>,
+    action: &str,
+) -> Result<(), StatusCode> {
     // Vérifier si la tâche a des sous-tâches
     let has_subtasks = sqlx::query!(
-        "SELECT has_subtasks FROM tasks WHERE id = $1 AND user_id = $2",
+        "SELECT has_subtasks, measurement_type FROM tasks WHERE id = $1 AND user_id = $2",
         id,
         user_id
     )
-    .fetch_optional(&pool)
+    .fetch_optional(&mut **tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // Une tâche chiffrée est complétée par sa valeur (`/log`), jamais directement :
+    // l'occurrence et les statistiques de mesure resteraient en désaccord
+    if has_subtasks.as_ref().is_some_and(|row| row.measurement_type != "boolean") {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let date = audit::resolve_date(tx, None).await?;
+    let before = audit::completion_snapshot(tx, id, date).await?;
+
     match has_subtasks {
         Some(row) if row.has_subtasks => {
             // Si la tâche a des sous-tâches, basculer l'état de toutes les sous-tâches
@@ -279,7 +994,7 @@ pub async fn toggle_task(
                 "#,
                 id
             )
-            .fetch_optional(&pool)
+            .fetch_optional(&mut **tx)
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -298,21 +1013,45 @@ pub async fn toggle_task(
                 id,
                 new_completed
             )
-            .execute(&pool)
+            .execute(&mut **tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            // État des sous-tâches avant bascule, pour le journal (et leur annulation)
+            let subtask_ids = sqlx::query_scalar!(
+                "SELECT id FROM subtasks WHERE task_id = $1 ORDER BY id",
+                id
+            )
+            .fetch_all(&mut **tx)
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+            let mut snapshots = Vec::with_capacity(subtask_ids.len());
+            for &subtask_id in &subtask_ids {
+                snapshots.push((subtask_id, audit::subtask_snapshot(tx, subtask_id).await?));
+            }
+
             // Mettre à jour toutes les sous-tâches
             sqlx::query!(
                 "UPDATE subtasks SET completed = $1 WHERE task_id = $2",
                 new_completed,
                 id
             )
-            .execute(&pool)
+            .execute(&mut **tx)
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            for (subtask_id, before) in snapshots {
+                let after = audit::subtask_snapshot(tx, subtask_id).await?;
+                audit::record(
+                    tx,
+                    AuditEntry::subtask(user_id, id, subtask_id, "update").before(before).after(after),
+                )
+                .await?;
+            }
         }
-        _ => {
+        None => return Err(StatusCode::NOT_FOUND),
+        Some(_) => {
             // Tâche sans sous-tâches, comportement normal
             sqlx::query!(
                 r#"
@@ -322,42 +1061,457 @@ pub async fn toggle_task(
                 "#,
                 id
             )
-            .execute(&pool)
+            .execute(&mut **tx)
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         }
     }
 
+    // Joindre la note et le ressenti éventuels à l'occurrence du jour
+    if let Some(note) = note {
+        sqlx::query!(
+            r#"
+            UPDATE task_completions
+            SET note = COALESCE($1, note), rating = COALESCE($2, rating)
+            WHERE task_id = $3 AND date = current_date
+            "#,
+            note.note,
+            note.rating,
+            id
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    let after = audit::completion_snapshot(tx, id, date).await?;
+    audit::record(
+        tx,
+        AuditEntry::completion(user_id, id, date, action).before(before).after(after),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Fixe l'état de complétion d'une occurrence (aujourd'hui par défaut).
+/// Contrairement au toggle, rejouer la requête ne change rien.
+#[utoipa::path(
+    put,
+    path = "/tasks/{id}/completion",
+    tag = "completions",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    request_body = SetCompletionRequest,
+    responses(
+        (status = 200, description = "État de l'occurrence", body = serde_json::Value),
+        (status = 400, description = "Tâche chiffrée : la valeur s'enregistre avec `/log`"),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn set_completion(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<SetCompletionRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let task = sqlx::query!(
+        "SELECT has_subtasks, measurement_type FROM tasks WHERE id = $1 AND user_id = $2 AND deleted = false FOR UPDATE",
+        id,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Comme pour le toggle, une tâche chiffrée se complète par `/log`
+    if task.measurement_type != "boolean" {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let today = audit::resolve_date(&mut tx, None).await?;
+    let date = audit::resolve_past_date(&mut tx, payload.date).await?;
+    let before = audit::completion_snapshot(&mut tx, id, date).await?;
+
+    // Une occurrence complétée n'est plus sautée
+    sqlx::query!(
+        r#"
+        INSERT INTO task_completions (task_id, date, completed)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (task_id, date) DO UPDATE SET
+            completed = $3,
+            skipped = task_completions.skipped AND NOT $3,
+            skip_reason = CASE WHEN $3 THEN NULL ELSE task_completions.skip_reason END
+        "#,
+        id,
+        date,
+        payload.completed
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur mise à jour complétion: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Comme pour le toggle, les sous-tâches suivent l'état du jour
+    if task.has_subtasks && date == today {
+        sqlx::query!(
+            "UPDATE subtasks SET completed = $1 WHERE task_id = $2",
+            payload.completed,
+            id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    let after = audit::completion_snapshot(&mut tx, id, date).await?;
+    audit::record(
+        &mut tx,
+        AuditEntry::completion(user_id, id, date, "set").before(before).after(after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({ "date": date, "completed": payload.completed })))
+}
+
+/// Marque une occurrence comme sautée (malade, imprévu...) ou annule le saut.
+/// Une occurrence sautée n'est plus comptée comme prévue dans les statistiques.
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/skip",
+    tag = "completions",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    request_body = SkipRequest,
+    responses(
+        (status = 200, description = "Occurrence sautée ou rétablie"),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn skip_task(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<SkipRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let skipped = payload.skipped.unwrap_or(true);
+    let reason = if skipped { payload.reason } else { None };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let date = audit::resolve_date(&mut tx, payload.date).await?;
+    let before = audit::completion_snapshot(&mut tx, id, date).await?;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO task_completions (task_id, date, completed, skipped, skip_reason)
+        SELECT id, COALESCE($3, current_date), false, $4, $5
+        FROM tasks WHERE id = $1 AND user_id = $2 AND deleted = false
+        ON CONFLICT (task_id, date) DO UPDATE SET
+            completed = task_completions.completed AND NOT $4,
+            skipped = $4,
+            skip_reason = $5
+        "#,
+        id,
+        user_id,
+        payload.date,
+        skipped,
+        reason
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur saut occurrence: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let after = audit::completion_snapshot(&mut tx, id, date).await?;
+    audit::record(
+        &mut tx,
+        AuditEntry::completion(user_id, id, date, "skip").before(before).after(after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Ajoute ou modifie la note et le ressenti d'une occurrence sans changer son état
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/note",
+    tag = "completions",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    request_body = CompletionNoteRequest,
+    responses(
+        (status = 200, description = "Note enregistrée"),
+        (status = 400, description = "Données invalides"),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn set_completion_note(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<CompletionNoteRequest>,
+) -> Result<StatusCode, StatusCode> {
+    validate_rating(payload.rating)?;
+
+    // Une note vide efface la note existante
+    let note = payload.note.as_deref().map(str::trim);
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let date = audit::resolve_past_date(&mut tx, payload.date).await?;
+    let before = audit::completion_snapshot(&mut tx, id, date).await?;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO task_completions (task_id, date, completed, note, rating)
+        SELECT id, COALESCE($3, current_date), false, NULLIF($4, ''), $5
+        FROM tasks WHERE id = $1 AND user_id = $2 AND deleted = false
+        ON CONFLICT (task_id, date) DO UPDATE SET
+            note = CASE WHEN $4::text IS NULL THEN task_completions.note ELSE NULLIF($4, '') END,
+            rating = COALESCE($5, task_completions.rating)
+        "#,
+        id,
+        user_id,
+        payload.date,
+        note,
+        payload.rating
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur enregistrement note: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let after = audit::completion_snapshot(&mut tx, id, date).await?;
+    audit::record(
+        &mut tx,
+        AuditEntry::completion(user_id, id, date, "note").before(before).after(after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     Ok(StatusCode::OK)
 }
 
+/// Enregistre (ou incrémente) la valeur mesurée d'une tâche chiffrée pour une date.
+/// La tâche est complétée dès que la valeur atteint l'objectif.
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/log",
+    tag = "completions",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    request_body = LogValueRequest,
+    responses(
+        (status = 200, description = "Valeur et état de l'occurrence", body = serde_json::Value),
+        (status = 400, description = "Tâche non chiffrée ou valeur invalide"),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn log_task_value(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<LogValueRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if !payload.value.is_finite() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let increment = payload.increment.unwrap_or(false);
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let logged = apply_log_value(&mut tx, user_id, id, payload.date, payload.value, increment, "log").await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(logged))
+}
+
+/// Enregistre (ou incrémente) la valeur d'une tâche chiffrée dans la transaction de l'appelant
+/// et renvoie l'occurrence. `action` est l'action inscrite au journal d'audit.
+pub(crate) async fn apply_log_value(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    id: i32,
+    date: Option<chrono::NaiveDate>,
+    value: f64,
+    increment: bool,
+    action: &str,
+) -> Result<serde_json::Value, StatusCode> {
+    let resolved = audit::resolve_past_date(tx, date).await?;
+    let before = audit::completion_snapshot(tx, id, resolved).await?;
+
+    let row = sqlx::query!(
+        r#"
+        WITH task AS (
+            SELECT id, target FROM tasks
+            WHERE id = $1 AND user_id = $2 AND deleted = false AND measurement_type <> 'boolean'
+        ),
+        new_value AS (
+            SELECT task.id, task.target,
+                   GREATEST(
+                       CASE WHEN $5 THEN COALESCE(tc.value, 0) + $4 ELSE $4 END,
+                       0
+                   ) AS value
+            FROM task
+            LEFT JOIN task_completions tc ON tc.task_id = task.id AND tc.date = COALESCE($3, current_date)
+        )
+        INSERT INTO task_completions (task_id, date, completed, value)
+        SELECT id, COALESCE($3, current_date), value >= target, value FROM new_value
+        ON CONFLICT (task_id, date)
+        DO UPDATE SET value = EXCLUDED.value, completed = EXCLUDED.completed
+        RETURNING date, value as "value!", completed,
+                  (SELECT target FROM task) as "target!"
+        "#,
+        id,
+        user_id,
+        date,
+        value,
+        increment
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur enregistrement valeur: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let after = audit::completion_snapshot(tx, id, resolved).await?;
+    audit::record(
+        tx,
+        AuditEntry::completion(user_id, id, resolved, action).before(before).after(after),
+    )
+    .await?;
+
+    Ok(serde_json::json!({
+        "date": row.date,
+        "value": row.value,
+        "target": row.target,
+        "completed": row.completed,
+        "progress": progress_percent(row.value, row.target)
+    }))
+}
+
 /// Basculer l'état d'une sous-tâche et vérifier si la tâche parente est complète
+#[utoipa::path(
+    post,
+    path = "/subtasks/toggle",
+    tag = "subtasks",
+    params(
+        ("If-Match" = String, Header, description = "Version attendue entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    request_body = ToggleSubtaskRequest,
+    responses(
+        (status = 200, description = "Nouvelle version", body = serde_json::Value),
+        (status = 404, description = "Sous-tâche introuvable"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+        (status = 428, description = "En-tête `If-Match` absent"),
+    )
+)]
 pub async fn toggle_subtask(
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
+    if_match: IfMatch,
     Json(payload): Json<ToggleSubtaskRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<Response, WriteError> {
     let mut tx = pool
         .begin()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    // Basculer l'état de la sous-tâche
+    let version = apply_subtask_toggle(&mut tx, user_id, &if_match, payload).await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(with_etag(version, serde_json::json!({ "version": version })))
+}
+
+/// Bascule une sous-tâche dans la transaction de l'appelant et renvoie sa nouvelle version
+pub(crate) async fn apply_subtask_toggle(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    if_match: &IfMatch,
+    payload: ToggleSubtaskRequest,
+) -> Result<i32, WriteError> {
+    check_subtask_version(tx, user_id, payload.task_id, payload.subtask_id, if_match).await?;
+
+    let date = audit::resolve_date(tx, None).await?;
+    let completion_before = audit::completion_snapshot(tx, payload.task_id, date).await?;
+    let before = audit::subtask_snapshot(tx, payload.subtask_id).await?;
+
+    // Basculer l'état de la sous-tâche (uniquement sur une tâche de l'utilisateur)
     sqlx::query!(
-        "UPDATE subtasks SET completed = NOT completed WHERE id = $1 AND task_id = $2",
+        r#"
+        UPDATE subtasks SET completed = NOT completed
+        WHERE id = $1 AND task_id = $2
+          AND EXISTS(SELECT 1 FROM tasks WHERE id = $2 AND user_id = $3)
+        RETURNING id
+        "#,
         payload.subtask_id,
-        payload.task_id
+        payload.task_id,
+        user_id
     )
-    .execute(&mut *tx)
+    .fetch_optional(&mut **tx)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let after = audit::subtask_snapshot(tx, payload.subtask_id).await?;
+    audit::record(
+        tx,
+        AuditEntry::subtask(user_id, payload.task_id, payload.subtask_id, "toggle")
+            .before(before)
+            .after(after),
+    )
+    .await?;
 
     // Vérifier si toutes les sous-tâches sont complétées
     let remaining_subtasks = sqlx::query!(
         "SELECT COUNT(*) as count FROM subtasks WHERE task_id = $1 AND completed = false",
         payload.task_id
     )
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut **tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -373,18 +1527,33 @@ pub async fn toggle_subtask(
         payload.task_id,
         all_completed
     )
-    .execute(&mut *tx)
+    .execute(&mut **tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    tx.commit()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let completion_after = audit::completion_snapshot(tx, payload.task_id, date).await?;
+    audit::record(
+        tx,
+        AuditEntry::completion(user_id, payload.task_id, date, "toggle")
+            .before(completion_before)
+            .after(completion_after),
+    )
+    .await?;
 
-    Ok(StatusCode::OK)
+    Ok(subtask_version(tx, payload.subtask_id).await?)
 }
 
 /// Récupérer les sous-tâches d'une tâche
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}/subtasks",
+    tag = "subtasks",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    responses(
+        (status = 200, description = "Sous-tâches de la tâche", body = Vec<serde_json::Value>),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
 pub async fn get_subtasks(
     Path(task_id): Path<i32>,
     State(pool): State<PgPool>,
@@ -405,7 +1574,7 @@ pub async fn get_subtasks(
     }
 
     let subtasks = sqlx::query!(
-        "SELECT id, title, completed, priority FROM subtasks WHERE task_id = $1 ORDER BY priority ASC",
+        "SELECT id, title, completed, priority, version FROM subtasks WHERE task_id = $1 ORDER BY priority ASC",
         task_id
     )
     .fetch_all(&pool)
@@ -418,20 +1587,40 @@ pub async fn get_subtasks(
             "id": st.id,
             "title": st.title,
             "completed": st.completed,
-            "priority": st.priority
+            "priority": st.priority,
+            "version": st.version
         }))
         .collect();
 
     Ok(Json(result))
 }
 
-/// Créer une sous-tâche pour une tâche existante
+/// Créer une sous-tâche pour une tâche existante. Elle change la version de la tâche :
+/// l'en-tête `If-Match` porte sur celle-ci.
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/subtasks",
+    tag = "subtasks",
+    params(
+        ("id" = i32, Path, description = "Identifiant de la tâche"),
+        ("If-Match" = String, Header, description = "Version attendue de la tâche entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    request_body = CreateSubtaskRequest,
+    responses(
+        (status = 200, description = "Sous-tâche créée et nouvelle version de la tâche", body = serde_json::Value),
+        (status = 400, description = "Données invalides"),
+        (status = 404, description = "Tâche introuvable"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+        (status = 428, description = "En-tête `If-Match` absent"),
+    )
+)]
 pub async fn create_subtask(
     Path(task_id): Path<i32>,
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
+    if_match: IfMatch,
     Json(payload): Json<CreateSubtaskRequest>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, WriteError> {
     let mut tx = pool
         .begin()
         .await
@@ -440,13 +1629,45 @@ pub async fn create_subtask(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    // Vérifier que l'utilisateur a accès à cette tâche
+    let subtask_id = insert_subtask(&mut tx, user_id, task_id, &if_match, &payload.title).await?;
+    let version = sqlx::query_scalar!("SELECT version FROM tasks WHERE id = $1", task_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|e| {
+            eprintln!("Erreur commit transaction: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "subtask_id": subtask_id,
+        "version": version,
+        "message": "Sous-tâche créée avec succès"
+    })))
+}
+
+/// Ajoute une sous-tâche en fin de liste dans la transaction de l'appelant,
+/// après vérification de la version de la tâche
+pub(crate) async fn insert_subtask(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    task_id: i32,
+    if_match: &IfMatch,
+    title: &str,
+) -> Result<i32, WriteError> {
+    // Vérifier l'accès et la version de la tâche (verrouillée jusqu'au commit)
+    check_task_version(tx, user_id, task_id, if_match).await?;
+
     let task_exists = sqlx::query!(
         "SELECT id, has_subtasks FROM tasks WHERE id = $1 AND user_id = $2",
         task_id,
         user_id
     )
-    .fetch_optional(&mut *tx)
+    .fetch_optional(&mut **tx)
     .await
     .map_err(|e| {
         eprintln!("Erreur vérification tâche: {}", e);
@@ -459,7 +1680,7 @@ pub async fn create_subtask(
         "SELECT COALESCE(MAX(priority), -1) as max_priority FROM subtasks WHERE task_id = $1",
         task_id
     )
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut **tx)
     .await
     .map_err(|e| {
         eprintln!("Erreur récupération priorité max: {}", e);
@@ -470,10 +1691,10 @@ pub async fn create_subtask(
     let subtask = sqlx::query!(
         "INSERT INTO subtasks (task_id, title, priority) VALUES ($1, $2, $3) RETURNING id",
         task_id,
-        payload.title,
+        title,
         max_priority.max_priority.unwrap_or(-1) + 1
     )
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut **tx)
     .await
     .map_err(|e| {
         eprintln!("Erreur création sous-tâche: {}", e);
@@ -486,7 +1707,7 @@ pub async fn create_subtask(
             "UPDATE tasks SET has_subtasks = true WHERE id = $1",
             task_id
         )
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await
         .map_err(|e| {
             eprintln!("Erreur mise à jour has_subtasks: {}", e);
@@ -494,42 +1715,81 @@ pub async fn create_subtask(
         })?;
     }
 
-    tx.commit()
-        .await
-        .map_err(|e| {
-            eprintln!("Erreur commit transaction: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    let after = audit::subtask_snapshot(tx, subtask.id).await?;
+    audit::record(tx, AuditEntry::subtask(user_id, task_id, subtask.id, "create").after(after)).await?;
 
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "subtask_id": subtask.id,
-        "message": "Sous-tâche créée avec succès"
-    })))
+    Ok(subtask.id)
 }
 
 
 /// Mettre à jour une sous-tâche
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/subtasks/{subtask_id}",
+    tag = "subtasks",
+    params(
+        ("task_id" = i32, Path, description = "Identifiant de la tâche"),
+        ("subtask_id" = i32, Path, description = "Identifiant de la sous-tâche"),
+        ("If-Match" = String, Header, description = "Version attendue entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    request_body = UpdateSubtaskRequest,
+    responses(
+        (status = 200, description = "Nouvelle version", body = serde_json::Value),
+        (status = 404, description = "Sous-tâche introuvable"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+        (status = 428, description = "En-tête `If-Match` absent"),
+    )
+)]
 pub async fn update_subtask(
     Path((task_id, subtask_id)): Path<(i32, i32)>,
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
+    if_match: IfMatch,
     Json(payload): Json<UpdateSubtaskRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<Response, WriteError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let version = apply_subtask_update(&mut tx, user_id, task_id, subtask_id, &if_match, payload).await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(with_etag(version, serde_json::json!({ "version": version })))
+}
+
+/// Met à jour une sous-tâche dans la transaction de l'appelant et renvoie sa nouvelle version
+pub(crate) async fn apply_subtask_update(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    task_id: i32,
+    subtask_id: i32,
+    if_match: &IfMatch,
+    payload: UpdateSubtaskRequest,
+) -> Result<i32, WriteError> {
     // Vérifier que l'utilisateur a accès à cette tâche
     let task_exists = sqlx::query!(
         "SELECT id FROM tasks WHERE id = $1 AND user_id = $2",
         task_id,
         user_id
     )
-    .fetch_optional(&pool)
+    .fetch_optional(&mut **tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     if task_exists.is_none() {
-        return Err(StatusCode::NOT_FOUND);
+        return Err(StatusCode::NOT_FOUND.into());
     }
 
+    check_subtask_version(tx, user_id, task_id, subtask_id, if_match).await?;
+
+    let date = audit::resolve_date(tx, None).await?;
+    let before = audit::subtask_snapshot(tx, subtask_id).await?;
+    let completion_before = audit::completion_snapshot(tx, task_id, date).await?;
+
     // Mettre à jour le titre si fourni
     if let Some(title) = payload.title {
         sqlx::query!(
@@ -538,7 +1798,7 @@ pub async fn update_subtask(
             subtask_id,
             task_id
         )
-        .execute(&pool)
+        .execute(&mut **tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     }
@@ -551,7 +1811,7 @@ pub async fn update_subtask(
             subtask_id,
             task_id
         )
-        .execute(&pool)
+        .execute(&mut **tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -560,7 +1820,7 @@ pub async fn update_subtask(
             "SELECT COUNT(*) as count FROM subtasks WHERE task_id = $1 AND completed = false",
             task_id
         )
-        .fetch_one(&pool)
+        .fetch_one(&mut **tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -576,50 +1836,114 @@ pub async fn update_subtask(
             task_id,
             all_completed
         )
-        .execute(&pool)
+        .execute(&mut **tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     }
 
-    Ok(StatusCode::OK)
+    let after = audit::subtask_snapshot(tx, subtask_id).await?;
+    audit::record(
+        tx,
+        AuditEntry::subtask(user_id, task_id, subtask_id, "update").before(before).after(after),
+    )
+    .await?;
+    let completion_after = audit::completion_snapshot(tx, task_id, date).await?;
+    audit::record(
+        tx,
+        AuditEntry::completion(user_id, task_id, date, "update")
+            .before(completion_before)
+            .after(completion_after),
+    )
+    .await?;
+
+    Ok(subtask_version(tx, subtask_id).await?)
 }
 
 /// Supprimer une sous-tâche
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}/subtasks/{subtask_id}",
+    tag = "subtasks",
+    params(
+        ("task_id" = i32, Path, description = "Identifiant de la tâche"),
+        ("subtask_id" = i32, Path, description = "Identifiant de la sous-tâche"),
+        ("If-Match" = String, Header, description = "Version attendue entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    responses(
+        (status = 200, description = "Sous-tâche supprimée"),
+        (status = 404, description = "Sous-tâche introuvable"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+        (status = 428, description = "En-tête `If-Match` absent"),
+    )
+)]
 pub async fn delete_subtask(
     Path((task_id, subtask_id)): Path<(i32, i32)>,
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
-) -> Result<StatusCode, StatusCode> {
+    if_match: IfMatch,
+) -> Result<StatusCode, WriteError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    remove_subtask(&mut tx, user_id, task_id, subtask_id, &if_match).await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Supprime une sous-tâche dans la transaction de l'appelant
+pub(crate) async fn remove_subtask(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    task_id: i32,
+    subtask_id: i32,
+    if_match: &IfMatch,
+) -> Result<(), WriteError> {
     // Vérifier que l'utilisateur a accès à cette tâche
     let task_exists = sqlx::query!(
         "SELECT id FROM tasks WHERE id = $1 AND user_id = $2",
         task_id,
         user_id
     )
-    .fetch_optional(&pool)
+    .fetch_optional(&mut **tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     if task_exists.is_none() {
-        return Err(StatusCode::NOT_FOUND);
+        return Err(StatusCode::NOT_FOUND.into());
     }
 
+    check_subtask_version(tx, user_id, task_id, subtask_id, if_match).await?;
+
+    let before = audit::subtask_snapshot(tx, subtask_id).await?;
+
     // Supprimer la sous-tâche
     sqlx::query!(
         "DELETE FROM subtasks WHERE id = $1 AND task_id = $2",
         subtask_id,
         task_id
     )
-    .execute(&pool)
+    .execute(&mut **tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    audit::record(
+        tx,
+        AuditEntry::subtask(user_id, task_id, subtask_id, "delete").before(before),
+    )
+    .await?;
+
     // Vérifier s'il reste des sous-tâches
     let remaining_subtasks = sqlx::query!(
         "SELECT COUNT(*) as count FROM subtasks WHERE task_id = $1",
         task_id
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut **tx)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -629,42 +1953,78 @@ pub async fn delete_subtask(
             "UPDATE tasks SET has_subtasks = false WHERE id = $1",
             task_id
         )
-        .execute(&pool)
+        .execute(&mut **tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     }
 
-    Ok(StatusCode::OK)
+    Ok(())
 }
 
 /// Met à jour les informations d'une tâche (titre, jours, statut et sous-tâches).
+/// Renvoie un jeton permettant de revenir à l'état précédent, sous-tâches comprises.
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(
+        ("id" = i32, Path, description = "Identifiant de la tâche"),
+        ("If-Match" = String, Header, description = "Version attendue entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    request_body = UpdateTaskRequest,
+    responses(
+        (status = 200, description = "Nouvelle version et jeton d'annulation", body = serde_json::Value),
+        (status = 400, description = "Données invalides"),
+        (status = 404, description = "Tâche introuvable"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+        (status = 428, description = "En-tête `If-Match` absent"),
+    )
+)]
 pub async fn update_task(
     Path(id): Path<i32>,
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
+    if_match: IfMatch,
     Json(payload): Json<UpdateTaskRequest>,
-) -> Result<StatusCode, StatusCode> {
-    // Vérifier que l'utilisateur a accès à cette tâche
-    let task_exists = sqlx::query!(
-        "SELECT id FROM tasks WHERE id = $1 AND user_id = $2",
-        id,
-        user_id
-    )
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| {
-        eprintln!("Erreur vérification tâche: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+) -> Result<Response, WriteError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| {
+            eprintln!("Erreur début transaction: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
-    if task_exists.is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    let version = apply_task_update(&mut tx, user_id, id, &if_match, payload).await?;
+    let undo_token = undo::issue(&mut tx, user_id).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| {
+            eprintln!("Erreur commit transaction: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(with_etag(version, serde_json::json!({ "version": version, "undo_token": undo_token })))
+}
+
+/// Applique la mise à jour d'une tâche dans la transaction de l'appelant et renvoie sa nouvelle version
+pub(crate) async fn apply_task_update(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    id: i32,
+    if_match: &IfMatch,
+    payload: UpdateTaskRequest,
+) -> Result<i32, WriteError> {
+    // Vérifier l'accès et la version de la tâche (verrouillée jusqu'au commit)
+    check_task_version(tx, user_id, id, if_match).await?;
+
+    let before = audit::task_snapshot(tx, id).await?;
 
     // 1. Mise à jour du titre
     if let Some(title) = &payload.title {
         sqlx::query!("UPDATE tasks SET title = $1 WHERE id = $2", title, id)
-            .execute(&pool)
+            .execute(&mut **tx)
             .await
             .map_err(|e| {
                 eprintln!("Erreur mise à jour titre: {}", e);
@@ -675,7 +2035,7 @@ pub async fn update_task(
     // 2. Mise à jour du statut actif/archivé
     if let Some(active) = payload.active {
         sqlx::query!("UPDATE tasks SET active = $1 WHERE id = $2", active, id)
-            .execute(&pool)
+            .execute(&mut **tx)
             .await
             .map_err(|e| {
                 eprintln!("Erreur mise à jour statut: {}", e);
@@ -683,18 +2043,28 @@ pub async fn update_task(
             })?;
     }
 
-    // 3. Mise à jour des jours
+    // 3. Mise à jour du type de mesure et de l'objectif
+    if let Some(kind) = &payload.measurement_type {
+        let (measurement_type, target) = validate_measurement(Some(kind), payload.target)?;
+        sqlx::query!(
+            "UPDATE tasks SET measurement_type = $1, target = $2, unit = $3 WHERE id = $4",
+            measurement_type,
+            target,
+            payload.unit,
+            id
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
+            eprintln!("Erreur mise à jour mesure: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    // 4. Mise à jour des jours
     if let Some(days) = &payload.days {
-        let mut tx = pool
-            .begin()
-            .await
-            .map_err(|e| {
-                eprintln!("Erreur début transaction: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
-        
         sqlx::query!("DELETE FROM task_days WHERE task_id = $1", id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await
             .map_err(|e| {
                 eprintln!("Erreur suppression jours: {}", e);
@@ -707,256 +2077,542 @@ pub async fn update_task(
                 id,
                 day
             )
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await
             .map_err(|e| {
                 eprintln!("Erreur insertion jour {}: {}", day, e);
                 StatusCode::INTERNAL_SERVER_ERROR
             })?;
         }
-        
-        tx.commit()
-            .await
-            .map_err(|e| {
-                eprintln!("Erreur commit transaction: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
     }
 
-    // 4. Mise à jour des sous-tâches si fournies
+    // 5. Mise à jour des étiquettes si fournies
+    if let Some(tag_ids) = &payload.tags {
+        set_task_tags(tx, id, user_id, tag_ids).await?;
+    }
+
+    // 6. Réconciliation des sous-tâches si fournies
     if let Some(subtasks) = &payload.subtasks {
-        let mut tx = pool
-            .begin()
-            .await
-            .map_err(|e| {
-                eprintln!("Erreur début transaction sous-tâches: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
+        reconcile_subtasks(tx, id, subtasks).await?;
+    }
 
-        // Supprimer les sous-tâches existantes
-        sqlx::query!("DELETE FROM subtasks WHERE task_id = $1", id)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| {
-                eprintln!("Erreur suppression sous-tâches: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
+    let after = audit::task_snapshot(tx, id).await?;
+    audit::record(tx, AuditEntry::task(user_id, id, "update").before(before).after(after)).await?;
+    let version = sqlx::query_scalar!("SELECT version FROM tasks WHERE id = $1", id)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-        // Ajouter les nouvelles sous-tâches
-        for (priority, subtask) in subtasks.iter().enumerate() {
-            if !subtask.title.trim().is_empty() {
-                sqlx::query!(
-                    "INSERT INTO subtasks (task_id, title, priority) VALUES ($1, $2, $3)",
-                    id,
-                    subtask.title,
-                    priority as i32
-                )
-                .execute(&mut *tx)
-                .await
-                .map_err(|e| {
-                    eprintln!("Erreur insertion sous-tâche: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
-            }
-        }
+    Ok(version)
+}
 
-        // Mettre à jour le flag has_subtasks
-        let has_subtasks = !subtasks.is_empty();
-        sqlx::query!(
-            "UPDATE tasks SET has_subtasks = $1 WHERE id = $2",
-            has_subtasks,
-            id
-        )
-        .execute(&mut *tx)
+/// Marque une tâche comme supprimée (Soft delete).
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(
+        ("id" = i32, Path, description = "Identifiant de la tâche"),
+        ("If-Match" = String, Header, description = "Version attendue entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    responses(
+        (status = 200, description = "Tâche placée dans la corbeille, jeton d'annulation", body = serde_json::Value),
+        (status = 404, description = "Tâche introuvable"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+        (status = 428, description = "En-tête `If-Match` absent"),
+    )
+)]
+pub async fn delete_task(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    if_match: IfMatch,
+) -> Result<Json<serde_json::Value>, WriteError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    set_deleted(&mut tx, user_id, id, true, Some(&if_match)).await?;
+    let undo_token = undo::issue(&mut tx, user_id).await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({ "undo_token": undo_token })))
+}
+
+/// Met une tâche à la corbeille ou l'en sort dans la transaction de l'appelant, en traçant
+/// le changement (après vérification de la version si `if_match` est fourni).
+/// Mettre à la corbeille une tâche qui y est déjà ne fait rien.
+pub(crate) async fn set_deleted(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    id: i32,
+    deleted: bool,
+    if_match: Option<&IfMatch>,
+) -> Result<(), WriteError> {
+    if let Some(if_match) = if_match {
+        check_task_version(tx, user_id, id, if_match).await?;
+    }
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE tasks
+        SET deleted = $3, deleted_at = CASE WHEN $3 THEN now() END
+        WHERE id = $1 AND user_id = $2 AND deleted = NOT $3
+        "#,
+        id,
+        user_id,
+        deleted
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return if deleted {
+            Ok(())
+        } else {
+            Err(StatusCode::NOT_FOUND.into())
+        };
+    }
+
+    let action = if deleted { "delete" } else { "restore" };
+    audit::record(
+        tx,
+        AuditEntry::task(user_id, id, action)
+            .before(Some(serde_json::json!({ "deleted": !deleted })))
+            .after(Some(serde_json::json!({ "deleted": deleted }))),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Liste les tâches de la corbeille avec leur date de purge prévue
+#[utoipa::path(
+    get,
+    path = "/tasks/trash",
+    tag = "trash",
+    responses(
+        (status = 200, description = "Tâches supprimées et date de purge prévue", body = Vec<serde_json::Value>),
+    )
+)]
+pub async fn get_trash(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let tasks = sqlx::query!(
+        r#"
+        SELECT id, title, version, deleted_at as "deleted_at!",
+               deleted_at + make_interval(days => $2) as "purge_at!"
+        FROM tasks
+        WHERE user_id = $1 AND deleted = true
+        ORDER BY deleted_at DESC
+        "#,
+        user_id,
+        trash_retention_days()
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération corbeille: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result = tasks
+        .into_iter()
+        .map(|t| serde_json::json!({
+            "id": t.id,
+            "title": t.title,
+            "version": t.version,
+            "deleted_at": t.deleted_at,
+            "purge_at": t.purge_at
+        }))
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Restaure une tâche de la corbeille
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/restore",
+    tag = "trash",
+    params(
+        ("id" = i32, Path, description = "Identifiant de la tâche"),
+        ("If-Match" = String, Header, description = "Version attendue entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    responses(
+        (status = 200, description = "Tâche restaurée", body = serde_json::Value),
+        (status = 404, description = "Tâche absente de la corbeille"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+        (status = 428, description = "En-tête `If-Match` absent"),
+    )
+)]
+pub async fn restore_task(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    if_match: IfMatch,
+) -> Result<Json<serde_json::Value>, WriteError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    set_deleted(&mut tx, user_id, id, false, Some(&if_match)).await?;
+    let undo_token = undo::issue(&mut tx, user_id).await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({ "undo_token": undo_token })))
+}
+
+/// Supprime définitivement une tâche de la corbeille (jours, complétions et sous-tâches compris)
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}/permanent",
+    tag = "trash",
+    params(
+        ("id" = i32, Path, description = "Identifiant de la tâche"),
+        ("If-Match" = String, Header, description = "Version attendue entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    responses(
+        (status = 200, description = "Tâche supprimée définitivement"),
+        (status = 404, description = "Tâche absente de la corbeille"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+        (status = 428, description = "En-tête `If-Match` absent"),
+    )
+)]
+pub async fn purge_task(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    if_match: IfMatch,
+) -> Result<StatusCode, WriteError> {
+    let purged = purge_tasks(&pool, user_id, Some((id, &if_match))).await?;
+    if purged == 0 {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Supprime définitivement les tâches de la corbeille de l'utilisateur (une seule si `task`
+/// est fourni, après vérification de sa version), après les avoir tracées dans le journal
+async fn purge_tasks(
+    pool: &PgPool,
+    user_id: Uuid,
+    task: Option<(i32, &IfMatch)>,
+) -> Result<u64, WriteError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if let Some((id, if_match)) = task {
+        check_task_version(&mut tx, user_id, id, if_match).await?;
+    }
+    let id = task.map(|(id, _)| id);
+
+    let task_ids = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM tasks
+        WHERE user_id = $1 AND deleted = true AND ($2::int IS NULL OR id = $2)
+        FOR UPDATE
+        "#,
+        user_id,
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record_purge(&mut tx, Some(user_id), &task_ids)
         .await
         .map_err(|e| {
-            eprintln!("Erreur mise à jour has_subtasks: {}", e);
+            eprintln!("Erreur journal d'audit: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-        tx.commit()
-            .await
-            .map_err(|e| {
-                eprintln!("Erreur commit sous-tâches: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
-    }
+    let result = sqlx::query!("DELETE FROM tasks WHERE id = ANY($1)", &task_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(StatusCode::OK)
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(result.rows_affected())
 }
 
-/// Marque une tâche comme supprimée (Soft delete).
-pub async fn delete_task(
-    Path(id): Path<i32>, 
-    State(pool): State<PgPool>, 
-    Extension(user_id): Extension<Uuid>
-) -> StatusCode {
-    let _ = sqlx::query!(
-        "UPDATE tasks SET deleted = true WHERE id = $1 AND user_id = $2",
-        id,
-        user_id
+/// Vide la corbeille de l'utilisateur. Sans `If-Match` : la corbeille entière est visée,
+/// quelle que soit la version de chaque tâche.
+#[utoipa::path(
+    delete,
+    path = "/tasks/trash",
+    tag = "trash",
+    responses(
+        (status = 200, description = "Nombre de tâches supprimées définitivement", body = serde_json::Value),
     )
-    .execute(&pool)
-    .await;
-    StatusCode::OK
+)]
+pub async fn empty_trash(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<serde_json::Value>, WriteError> {
+    let purged = purge_tasks(&pool, user_id, None).await?;
+
+    Ok(Json(serde_json::json!({ "purged": purged })))
 }
 
-/// Active ou archive une tâche.
+/// Active ou archive une tâche (renvoie un jeton d'annulation).
+#[utoipa::path(
+    patch,
+    path = "/tasks/{id}",
+    tag = "tasks",
+    params(
+        ("id" = i32, Path, description = "Identifiant de la tâche"),
+        ("If-Match" = String, Header, description = "Version attendue entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    responses(
+        (status = 200, description = "Nouvel état, version et jeton d'annulation", body = serde_json::Value),
+        (status = 404, description = "Tâche introuvable"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+        (status = 428, description = "En-tête `If-Match` absent"),
+    )
+)]
 pub async fn toggle_archive(
-    Path(id): Path<i32>, 
-    State(pool): State<PgPool>, 
-    Extension(user_id): Extension<Uuid>
-) -> StatusCode {
-    let _ = sqlx::query!(
-        "UPDATE tasks SET active = NOT active WHERE id = $1 AND user_id = $2",
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    if_match: IfMatch,
+) -> Result<Response, WriteError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (active, version) = set_active(&mut tx, user_id, id, None, &if_match).await?;
+    let undo_token = undo::issue(&mut tx, user_id).await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(with_etag(
+        version,
+        serde_json::json!({ "active": active, "version": version, "undo_token": undo_token }),
+    ))
+}
+
+/// Archive ou active explicitement une tâche : rejouer la requête ne change rien.
+/// `If-Match` est facultatif, l'état demandé ne dépendant pas de l'état actuel.
+#[utoipa::path(
+    put,
+    path = "/tasks/{id}/archived",
+    tag = "tasks",
+    params(
+        ("id" = i32, Path, description = "Identifiant de la tâche"),
+        ("If-Match" = Option<String>, Header, description = "Version attendue entre guillemets (`\"3\"`), ou `*`"),
+    ),
+    request_body = SetArchivedRequest,
+    responses(
+        (status = 200, description = "Nouvel état et version", body = serde_json::Value),
+        (status = 404, description = "Tâche introuvable"),
+        (status = 412, description = "Version périmée : représentation actuelle et son `ETag`", body = serde_json::Value),
+    )
+)]
+pub async fn set_archived(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    if_match: Option<IfMatch>,
+    Json(payload): Json<SetArchivedRequest>,
+) -> Result<Response, WriteError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let if_match = if_match.unwrap_or(IfMatch(None));
+    let (active, version) = set_active(&mut tx, user_id, id, Some(!payload.archived), &if_match).await?;
+    let undo_token = undo::issue(&mut tx, user_id).await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(with_etag(
+        version,
+        serde_json::json!({ "active": active, "version": version, "undo_token": undo_token }),
+    ))
+}
+
+/// Archive ou active une tâche (bascule si `active` vaut None) dans la transaction de l'appelant.
+/// Renvoie le nouvel état et la version de la tâche.
+pub(crate) async fn set_active(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    id: i32,
+    active: Option<bool>,
+    if_match: &IfMatch,
+) -> Result<(bool, i32), WriteError> {
+    check_task_version(tx, user_id, id, if_match).await?;
+
+    let task = sqlx::query!(
+        r#"
+        UPDATE tasks t SET active = COALESCE($3, NOT old.active)
+        FROM (SELECT id, active FROM tasks WHERE id = $1) old
+        WHERE t.id = old.id AND t.user_id = $2
+        RETURNING t.active, old.active as was_active, t.version
+        "#,
         id,
-        user_id
+        user_id,
+        active
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let action = if task.active { "activate" } else { "archive" };
+    audit::record(
+        tx,
+        AuditEntry::task(user_id, id, action)
+            .before(Some(serde_json::json!({ "active": task.was_active })))
+            .after(Some(serde_json::json!({ "active": task.active }))),
     )
-    .execute(&pool)
-    .await;
-    StatusCode::OK
+    .await?;
+
+    Ok((task.active, task.version))
 }
 
-/// Calcule les statistiques de complétion pour la heatmap des 30 derniers jours
+/// Calcule les statistiques de complétion pour la heatmap (30 derniers jours par défaut)
+/// à partir des bilans quotidiens
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "stats",
+    params(StatsQuery),
+    responses(
+        (status = 200, description = "Historique quotidien et totaux", body = serde_json::Value),
+        (status = 400, description = "Période ou filtre invalide"),
+    )
+)]
 pub async fn get_stats(
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
+    Query(query): Query<StatsQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    // 1. Requête pour la heatmap - compte maintenant les sous-tâches aussi
+    let tag_ids = parse_id_list(query.tags.as_deref())?;
+    let days = query.days.unwrap_or(30);
+    if !(1..=366).contains(&days) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    stats::ensure_recent(&pool, user_id, days).await?;
+
+    // 1. Heatmap : totaux de l'utilisateur, ou somme des tâches portant les étiquettes.
+    // Une tâche prévue compte avec ses sous-tâches restantes, une tâche complétée
+    // avec ses sous-tâches complétées.
     let rows = sqlx::query!(
         r#"
         WITH day_series AS (
-            SELECT generate_series(current_date - interval '29 days', current_date, '1 day')::date AS stats_date
-        ),
-        -- Compte toutes les tâches et sous-tâches prévues pour chaque jour
-        scheduled_counts AS (
-            SELECT 
-                d.stats_date,
-                COUNT(DISTINCT t.id) + COUNT(DISTINCT s.id) as total_scheduled
-            FROM day_series d
-            LEFT JOIN task_days td ON td.day_of_week = extract(isodow from d.stats_date)
-            LEFT JOIN tasks t ON t.id = td.task_id AND t.user_id = $1 AND t.deleted = false AND t.active = true
-            LEFT JOIN subtasks s ON s.task_id = t.id AND s.completed = false
-            GROUP BY d.stats_date
-        ),
-        -- Compte toutes les complétions (tâches + sous-tâches) pour chaque jour
-        completed_counts AS (
-            SELECT 
-                tc.date,
-                COUNT(DISTINCT tc.task_id) + COUNT(DISTINCT s.id) as total_completed
-            FROM task_completions tc
-            LEFT JOIN tasks t ON t.id = tc.task_id AND t.user_id = $1
-            LEFT JOIN subtasks s ON s.task_id = tc.task_id AND s.completed = true
-            WHERE tc.completed = true
-            GROUP BY tc.date
+            SELECT generate_series(current_date - ($2::int - 1), current_date, interval '1 day')::date AS stats_date
         )
-        SELECT 
-            s.stats_date as "date!",
-            COALESCE(c.total_completed, 0)::int as "completed!",
-            COALESCE(s.total_scheduled, 0)::int as "scheduled!"
-        FROM scheduled_counts s
-        LEFT JOIN completed_counts c ON c.date = s.stats_date
-        ORDER BY s.stats_date ASC
+        SELECT
+            d.stats_date as "date!",
+            COALESCE(CASE WHEN $3::int[] IS NULL THEN u.completed_units ELSE tagged.completed END, 0)::int as "completed!",
+            COALESCE(CASE WHEN $3::int[] IS NULL THEN u.scheduled_units ELSE tagged.scheduled END, 0)::int as "scheduled!"
+        FROM day_series d
+        LEFT JOIN user_daily_stats u ON u.user_id = $1 AND u.date = d.stats_date
+        LEFT JOIN LATERAL (
+            SELECT SUM(1 + s.subtasks_total - s.subtasks_completed) FILTER (WHERE s.scheduled) AS scheduled,
+                   SUM(1 + s.subtasks_completed) FILTER (WHERE s.completed) AS completed
+            FROM task_daily_stats s
+            WHERE $3::int[] IS NOT NULL
+              AND s.user_id = $1 AND s.date = d.stats_date
+              AND EXISTS(SELECT 1 FROM task_tags tt WHERE tt.task_id = s.task_id AND tt.tag_id = ANY($3))
+        ) tagged ON true
+        ORDER BY d.stats_date ASC
         "#,
-        user_id
+        user_id,
+        days,
+        tag_ids.as_deref()
     )
     .fetch_all(&pool)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|e| {
+        eprintln!("Erreur statistiques: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    // 2. Requête pour les totaux globaux CORRIGÉS
+    // 2. Totaux sur tout l'historique calculé
     let totals = sqlx::query!(
         r#"
-        -- Total des tâches créées
-        SELECT 
-            (SELECT COUNT(*) FROM tasks WHERE user_id = $1 AND deleted = false) as total_tasks,
-            -- Total des complétions (chaque jour où une tâche ou sous-tâche a été complétée)
-            (SELECT COUNT(*) FROM task_completions tc 
-             JOIN tasks t ON t.id = tc.task_id 
-             WHERE t.user_id = $1 AND tc.completed = true) as total_completion_days,
-            -- Total des jours où des tâches étaient prévues (pour calculer le taux)
-            (SELECT COUNT(*) FROM (
-                SELECT DISTINCT td.day_of_week, t.id
-                FROM tasks t
-                JOIN task_days td ON t.id = td.task_id
-                WHERE t.user_id = $1 AND t.deleted = false AND t.active = true
-            ) as scheduled) as total_scheduled_days
-        "#,
-        user_id
-    )
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let history: Vec<serde_json::Value> = rows
-        .into_iter()
-        .map(|r| {
-            let percent = if r.scheduled > 0 {
-                (r.completed as f64 / r.scheduled as f64 * 100.0) as i32
-            } else {
-                0
-            };
-            serde_json::json!({ "date": r.date, "percent": percent })
-        })
-    .collect();
-
-    // 3. Taux du jour (corrigé pour inclure les sous-tâches)
-    let today_stats = sqlx::query!(
-        r#"
-        -- Tâches principales prévues aujourd'hui
-        WITH scheduled_tasks AS (
-            SELECT t.id
-            FROM tasks t
-            JOIN task_days td ON t.id = td.task_id
-            WHERE t.user_id = $1 AND t.deleted = false AND t.active = true
-                AND td.day_of_week = extract(isodow from current_date)
-        ),
-        -- Sous-tâches non complétées des tâches prévues
-        scheduled_subtasks AS (
-            SELECT s.id
-            FROM scheduled_tasks st
-            JOIN subtasks s ON s.task_id = st.id AND s.completed = false
-        ),
-        -- Tâches complétées aujourd'hui
-        completed_tasks AS (
-            SELECT tc.task_id
-            FROM task_completions tc
-            JOIN scheduled_tasks st ON st.id = tc.task_id
-            WHERE tc.date = current_date AND tc.completed = true
-        ),
-        -- Sous-tâches complétées aujourd'hui
-        completed_subtasks AS (
-            SELECT s.id
-            FROM scheduled_tasks st
-            JOIN subtasks s ON s.task_id = st.id AND s.completed = true
+        WITH tagged AS (
+            SELECT s.scheduled, s.completed
+            FROM task_daily_stats s
+            WHERE $2::int[] IS NOT NULL
+              AND s.user_id = $1
+              AND EXISTS(SELECT 1 FROM task_tags tt WHERE tt.task_id = s.task_id AND tt.tag_id = ANY($2))
         )
-        SELECT 
-            (SELECT COUNT(*) FROM scheduled_tasks) as scheduled_tasks,
-            (SELECT COUNT(*) FROM scheduled_subtasks) as scheduled_subtasks,
-            (SELECT COUNT(*) FROM completed_tasks) as completed_tasks,
-            (SELECT COUNT(*) FROM completed_subtasks) as completed_subtasks
+        SELECT
+            (SELECT COUNT(*) FROM tasks t
+             WHERE t.user_id = $1 AND t.deleted = false
+               AND ($2::int[] IS NULL OR EXISTS(
+                   SELECT 1 FROM task_tags tt WHERE tt.task_id = t.id AND tt.tag_id = ANY($2)))
+            ) as "total_tasks!",
+            CASE WHEN $2::int[] IS NULL
+                THEN (SELECT COALESCE(SUM(completed_tasks), 0) FROM user_daily_stats WHERE user_id = $1)
+                ELSE (SELECT COUNT(*) FILTER (WHERE completed) FROM tagged)
+            END as "completed_tasks!",
+            CASE WHEN $2::int[] IS NULL
+                THEN (SELECT COALESCE(SUM(done_tasks), 0) FROM user_daily_stats WHERE user_id = $1)
+                ELSE (SELECT COUNT(*) FILTER (WHERE scheduled AND completed) FROM tagged)
+            END as "done_tasks!",
+            CASE WHEN $2::int[] IS NULL
+                THEN (SELECT COALESCE(SUM(scheduled_tasks), 0) FROM user_daily_stats WHERE user_id = $1)
+                ELSE (SELECT COUNT(*) FILTER (WHERE scheduled) FROM tagged)
+            END as "scheduled_tasks!"
         "#,
-        user_id
+        user_id,
+        tag_ids.as_deref()
     )
     .fetch_one(&pool)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let total_scheduled_today = (today_stats.scheduled_tasks.unwrap_or(0) + today_stats.scheduled_subtasks.unwrap_or(0)) as f64;
-    let total_completed_today = (today_stats.completed_tasks.unwrap_or(0) + today_stats.completed_subtasks.unwrap_or(0)) as f64;
+    .map_err(|e| {
+        eprintln!("Erreur totaux statistiques: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    let today_percent = if total_scheduled_today > 0.0 {
-        (total_completed_today / total_scheduled_today * 100.0).round() as i32
-    } else {
-        0
+    let percent = |completed: i32, scheduled: i32| {
+        if scheduled > 0 {
+            completed as f64 / scheduled as f64 * 100.0
+        } else {
+            0.0
+        }
     };
 
-    // Calcul du taux de réussite corrigé
-    let total_scheduled = totals.total_scheduled_days.unwrap_or(0) as f64;
-    let total_completed = totals.total_completion_days.unwrap_or(0) as f64;
-    
-    let success_rate = if total_scheduled > 0.0 {
-        ((total_completed / total_scheduled) * 100.0).round() as i32
+    // 3. Taux du jour : dernière journée de la heatmap
+    let today_percent = rows
+        .last()
+        .map_or(0, |r| percent(r.completed, r.scheduled).round() as i32);
+
+    let history: Vec<serde_json::Value> = rows
+        .into_iter()
+        .map(|r| serde_json::json!({ "date": r.date, "percent": percent(r.completed, r.scheduled) as i32 }))
+        .collect();
+
+    // Taux de réussite : occurrences prévues et complétées sur occurrences prévues
+    let success_rate = if totals.scheduled_tasks > 0 {
+        (totals.done_tasks as f64 / totals.scheduled_tasks as f64 * 100.0).round() as i32
     } else {
         0
     };
@@ -965,43 +2621,157 @@ pub async fn get_stats(
         "history": history,
         "summary": {
             "total_created": totals.total_tasks,
-            "total_completed_ever": totals.total_completion_days,
-            "total_scheduled_days": totals.total_scheduled_days,
+            "total_completed_ever": totals.completed_tasks,
+            "total_scheduled_days": totals.scheduled_tasks,
             "success_rate": success_rate,
             "today_percent": today_percent
         }
     })))
 }
 
-#[derive(Deserialize)]
-pub struct UpdatePrioritiesRequest {
-    pub ordered_task_ids: Vec<i32>,
+/// Progression et totaux des tâches chiffrées sur les 30 derniers jours
+#[utoipa::path(
+    get,
+    path = "/stats/measurements",
+    tag = "stats",
+    params(TagFilter),
+    responses(
+        (status = 200, description = "Valeurs et progression des tâches chiffrées", body = Vec<serde_json::Value>),
+        (status = 400, description = "Filtre invalide"),
+    )
+)]
+pub async fn get_measurement_stats(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Query(filter): Query<TagFilter>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let tag_ids = parse_tag_filter(&filter)?;
+
+    stats::ensure_recent(&pool, user_id, 30).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        WITH day_series AS (
+            SELECT generate_series(current_date - interval '29 days', current_date, '1 day')::date AS stats_date
+        )
+        SELECT t.id, t.title, t.measurement_type, t.unit,
+               t.target as "target!",
+               d.stats_date as "date!",
+               COALESCE(s.value, 0) as "value!",
+               COALESCE(s.scheduled, false) as "scheduled!"
+        FROM tasks t
+        CROSS JOIN day_series d
+        LEFT JOIN task_daily_stats s ON s.task_id = t.id AND s.date = d.stats_date
+        WHERE t.user_id = $1 AND t.deleted = false AND t.active = true
+          AND t.measurement_type <> 'boolean'
+          AND ($2::int[] IS NULL OR EXISTS(
+              SELECT 1 FROM task_tags tt WHERE tt.task_id = t.id AND tt.tag_id = ANY($2)))
+        ORDER BY t.id, d.stats_date
+        "#,
+        user_id,
+        tag_ids.as_deref()
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur statistiques mesures: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Regrouper les 30 jours de chaque tâche
+    let mut result = Vec::new();
+    for task_rows in rows.chunk_by(|a, b| a.id == b.id) {
+        let first = &task_rows[0];
+        let scheduled: Vec<_> = task_rows.iter().filter(|r| r.scheduled).collect();
+        let total: f64 = task_rows.iter().map(|r| r.value).sum();
+        let days_met = task_rows.iter().filter(|r| r.value >= r.target).count();
+        let progress = if scheduled.is_empty() {
+            0
+        } else {
+            let sum: i32 = scheduled.iter().map(|r| progress_percent(r.value, r.target)).sum();
+            (sum as f64 / scheduled.len() as f64).round() as i32
+        };
+
+        result.push(serde_json::json!({
+            "id": first.id,
+            "title": first.title,
+            "measurement_type": first.measurement_type,
+            "unit": first.unit,
+            "target": first.target,
+            "history": task_rows.iter().map(|r| serde_json::json!({
+                "date": r.date,
+                "value": r.value,
+                "scheduled": r.scheduled,
+                "progress": progress_percent(r.value, r.target)
+            })).collect::<Vec<_>>(),
+            "summary": {
+                "total": total,
+                "average_per_scheduled_day": if scheduled.is_empty() { 0.0 } else {
+                    scheduled.iter().map(|r| r.value).sum::<f64>() / scheduled.len() as f64
+                },
+                "scheduled_days": scheduled.len(),
+                "days_target_met": days_met,
+                "progress_percent": progress
+            }
+        }));
+    }
+
+    Ok(Json(result))
 }
 
+/// Réordonne les tâches du jour (renvoie un jeton d'annulation)
+#[utoipa::path(
+    post,
+    path = "/tasks/priorities",
+    tag = "tasks",
+    request_body = UpdatePrioritiesRequest,
+    responses(
+        (status = 200, description = "Jeton d'annulation", body = serde_json::Value),
+        (status = 400, description = "Données invalides"),
+    )
+)]
 pub async fn update_task_priorities(
     State(pool): State<PgPool>,
     Extension(user_id): Extension<Uuid>,
     Json(payload): Json<UpdatePrioritiesRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<Json<serde_json::Value>, StatusCode> {
     let mut tx = pool.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    for (priority, task_id) in payload.ordered_task_ids.iter().enumerate() {
-        sqlx::query!(
+    let date = audit::resolve_date(&mut tx, None).await?;
+
+    for (priority, &task_id) in payload.ordered_task_ids.iter().enumerate() {
+        let before = audit::completion_snapshot(&mut tx, task_id, date).await?;
+
+        // Seules les tâches de l'utilisateur sont réordonnées
+        let result = sqlx::query!(
             r#"
             INSERT INTO task_completions (task_id, date, completed, priority)
-            VALUES ($1, current_date, COALESCE(
-                (SELECT completed FROM task_completions WHERE task_id=$1 AND date=current_date), false), $2)
+            SELECT id, current_date, COALESCE(
+                (SELECT completed FROM task_completions WHERE task_id=$1 AND date=current_date), false), $2
+            FROM tasks WHERE id = $1 AND user_id = $3
             ON CONFLICT (task_id, date)
             DO UPDATE SET priority = $2
             "#,
             task_id,
-            priority as i32
+            priority as i32,
+            user_id
         )
         .execute(&mut *tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        if result.rows_affected() > 0 {
+            let after = audit::completion_snapshot(&mut tx, task_id, date).await?;
+            audit::record(
+                &mut tx,
+                AuditEntry::completion(user_id, task_id, date, "reorder").before(before).after(after),
+            )
+            .await?;
+        }
     }
 
+    let undo_token = undo::issue(&mut tx, user_id).await?;
+
     tx.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(StatusCode::OK)
+    Ok(Json(serde_json::json!({ "undo_token": undo_token })))
 }
@@ -0,0 +1,109 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::HistoryQuery;
+
+pub fn audit_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/tasks/:id/history", get(get_task_history))
+        .with_state(pool)
+}
+
+/// Historique paginé des modifications d'une tâche, du plus récent au plus ancien.
+/// Reste consultable après la suppression définitive de la tâche.
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}/history",
+    tag = "history",
+    params(
+        ("id" = i32, Path, description = "Identifiant de la tâche"),
+        HistoryQuery,
+    ),
+    responses(
+        (status = 200, description = "Page de l'historique", body = serde_json::Value),
+        (status = 400, description = "Page ou taille de page invalide"),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn get_task_history(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let page = query.page.unwrap_or(1);
+    let per_page = query.per_page.unwrap_or(50);
+    if page < 1 || !(1..=200).contains(&per_page) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // Page trop lointaine pour un décalage représentable
+    let offset = (page - 1)
+        .checked_mul(per_page)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let total = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM audit_log WHERE task_id = $1 AND owner_id = $2"#,
+        id,
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur comptage historique: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if total == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let entries = sqlx::query!(
+        r#"
+        SELECT a.id, a.actor_id, u.username as "actor_username?", a.entity, a.entity_id,
+               a.action, a.before, a.after, a.created_at
+        FROM audit_log a
+        LEFT JOIN users u ON u.id = a.actor_id
+        WHERE a.task_id = $1 AND a.owner_id = $2
+        ORDER BY a.id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+        id,
+        user_id,
+        per_page,
+        offset
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération historique: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let entries: Vec<_> = entries
+        .into_iter()
+        .map(|e| serde_json::json!({
+            "id": e.id,
+            "actor_id": e.actor_id,
+            "actor_username": e.actor_username,
+            "entity": e.entity,
+            "entity_id": e.entity_id,
+            "action": e.action,
+            "before": e.before,
+            "after": e.after,
+            "created_at": e.created_at
+        }))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "entries": entries,
+        "page": page,
+        "per_page": per_page,
+        "total": total
+    })))
+}
@@ -2,7 +2,7 @@ use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::auth::{create_jwt, hash_password, verify_password};
+use crate::auth::{MIN_PASSWORD_LENGTH, create_jwt, hash_password, verify_password};
 use crate::models::{AuthResponse, LoginRequest, RegisterRequest};
 
 pub fn auth_routes(pool: PgPool) -> Router {
@@ -12,11 +12,23 @@ pub fn auth_routes(pool: PgPool) -> Router {
         .with_state(pool)
 }
 
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, description = "Compte créé"),
+        (status = 400, description = "Nom vide ou mot de passe trop court"),
+        (status = 409, description = "Nom déjà pris"),
+    ),
+    security(())
+)]
 async fn register(
     State(pool): State<PgPool>,
     Json(payload): Json<RegisterRequest>,
 ) -> Result<StatusCode, StatusCode> {
-    if payload.username.trim().is_empty() || payload.password.len() < 8 {
+    if payload.username.trim().is_empty() || payload.password.len() < MIN_PASSWORD_LENGTH {
         return Err(StatusCode::BAD_REQUEST);
     }
 
@@ -36,12 +48,24 @@ async fn register(
     Ok(StatusCode::CREATED)
 }
 
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Jeton de session", body = AuthResponse),
+        (status = 401, description = "Identifiants invalides"),
+        (status = 403, description = "Compte désactivé"),
+    ),
+    security(())
+)]
 async fn login(
     State(pool): State<PgPool>,
     Json(payload): Json<LoginRequest>,
 ) -> Result<Json<AuthResponse>, StatusCode> {
     let user = sqlx::query!(
-        "SELECT id, password_hash FROM users WHERE username = $1",
+        r#"SELECT id, password_hash, disabled_at IS NOT NULL AS "disabled!" FROM users WHERE username = $1"#,
         payload.username
     )
     .fetch_optional(&pool)
@@ -52,6 +76,10 @@ async fn login(
     if !verify_password(&payload.password, &user.password_hash) {
         return Err(StatusCode::UNAUTHORIZED);
     }
+    // Compte désactivé par un administrateur
+    if user.disabled {
+        return Err(StatusCode::FORBIDDEN);
+    }
 
     let token = create_jwt(user.id);
 
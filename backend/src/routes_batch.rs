@@ -0,0 +1,241 @@
+use axum::{Extension, Json, Router, extract::State, http::StatusCode, routing::post};
+use sqlx::{Connection, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::models::{BatchOperation, BatchRequest, ToggleSubtaskRequest};
+use crate::precondition::{IfMatch, WriteError};
+use crate::routes::{
+    apply_subtask_toggle, apply_subtask_update, apply_task_update, apply_toggle, insert_subtask,
+    insert_task, remove_subtask, set_active, set_deleted,
+};
+use crate::undo;
+
+/// Nombre maximal d'opérations par lot
+const MAX_OPERATIONS: usize = 200;
+
+pub fn batch_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/batch", post(run_batch))
+        .with_state(pool)
+}
+
+/// Version attendue d'une opération, obligatoire comme l'en-tête `If-Match` des routes unitaires
+fn expected_version(version: Option<i32>) -> Result<IfMatch, WriteError> {
+    version
+        .map(|v| IfMatch(Some(vec![v])))
+        .ok_or(WriteError::Status(StatusCode::PRECONDITION_REQUIRED))
+}
+
+impl BatchOperation {
+    /// Opérations que `POST /api/v1/undo/:token` sait défaire
+    fn undoable(&self) -> bool {
+        matches!(
+            self,
+            BatchOperation::Update { .. }
+                | BatchOperation::Toggle { .. }
+                | BatchOperation::Archive { .. }
+                | BatchOperation::Delete { .. }
+        )
+    }
+}
+
+// --- GESTIONNAIRES (HANDLERS) ---
+
+/// Applique une liste d'opérations dans une seule transaction et renvoie le résultat de chacune
+/// (`status` est le code HTTP qu'aurait renvoyé la route équivalente).
+/// En mode atomique (par défaut), la première opération en échec annule tout le lot et les
+/// suivantes ne sont pas tentées (424) ; sinon seules les opérations en échec sont écartées.
+#[utoipa::path(
+    post,
+    path = "/batch",
+    tag = "batch",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, description = "Résultat de chaque opération", body = serde_json::Value),
+        (status = 413, description = "Plus de 200 opérations"),
+    )
+)]
+pub async fn run_batch(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<BatchRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if payload.operations.len() > MAX_OPERATIONS {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    let atomic = payload.atomic.unwrap_or(true);
+    let undoable = payload.operations.iter().all(BatchOperation::undoable);
+    let count = payload.operations.len();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut results = Vec::with_capacity(count);
+    let mut failed = false;
+    for operation in payload.operations {
+        // Chaque opération dans son point de sauvegarde, pour n'écarter que celles en échec
+        let mut savepoint = tx
+            .begin()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let outcome = apply_operation(&mut savepoint, user_id, operation).await;
+        if outcome.is_ok() {
+            savepoint.commit().await
+        } else {
+            savepoint.rollback().await
+        }
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let result = match outcome {
+            Ok((status, mut body)) => {
+                body["status"] = status.as_u16().into();
+                body
+            }
+            Err(WriteError::Status(StatusCode::INTERNAL_SERVER_ERROR)) => {
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+            Err(WriteError::Status(status)) => serde_json::json!({ "status": status.as_u16() }),
+            Err(WriteError::Stale { version, current }) => serde_json::json!({
+                "status": StatusCode::PRECONDITION_FAILED.as_u16(),
+                "version": version,
+                "current": current
+            }),
+        };
+        let ok = result["status"].as_u64().is_some_and(|s| s < 400);
+        results.push(result);
+
+        if !ok {
+            failed = true;
+            if atomic {
+                break;
+            }
+        }
+    }
+
+    if atomic && failed {
+        tx.rollback()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let skipped = count - results.len();
+        results.extend(std::iter::repeat_n(
+            serde_json::json!({ "status": StatusCode::FAILED_DEPENDENCY.as_u16() }),
+            skipped,
+        ));
+        return Ok(Json(serde_json::json!({
+            "committed": false,
+            "results": results,
+            "undo_token": null
+        })));
+    }
+
+    let undo_token = if undoable {
+        undo::issue(&mut tx, user_id).await?
+    } else {
+        None
+    };
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({
+        "committed": true,
+        "results": results,
+        "undo_token": undo_token
+    })))
+}
+
+/// Applique une opération avec les mêmes règles que la route équivalente
+async fn apply_operation(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    operation: BatchOperation,
+) -> Result<(StatusCode, serde_json::Value), WriteError> {
+    match operation {
+        BatchOperation::Create(payload) => {
+            let task_id = insert_task(tx, user_id, payload).await?;
+            Ok((
+                StatusCode::CREATED,
+                serde_json::json!({ "task_id": task_id }),
+            ))
+        }
+        BatchOperation::Update {
+            task_id,
+            version,
+            changes,
+        } => {
+            let if_match = expected_version(version)?;
+            let version = apply_task_update(tx, user_id, task_id, &if_match, changes).await?;
+            Ok((StatusCode::OK, serde_json::json!({ "version": version })))
+        }
+        BatchOperation::Toggle { task_id } => {
+            apply_toggle(tx, user_id, task_id, None, "toggle").await?;
+            Ok((StatusCode::OK, serde_json::json!({})))
+        }
+        BatchOperation::Archive {
+            task_id,
+            archived,
+            version,
+        } => {
+            let if_match = expected_version(version)?;
+            let (active, version) =
+                set_active(tx, user_id, task_id, Some(!archived), &if_match).await?;
+            Ok((
+                StatusCode::OK,
+                serde_json::json!({ "active": active, "version": version }),
+            ))
+        }
+        BatchOperation::Delete { task_id, version } => {
+            let if_match = expected_version(version)?;
+            set_deleted(tx, user_id, task_id, true, Some(&if_match)).await?;
+            Ok((StatusCode::OK, serde_json::json!({})))
+        }
+        BatchOperation::CreateSubtask {
+            task_id,
+            title,
+            version,
+        } => {
+            let if_match = expected_version(version)?;
+            let subtask_id = insert_subtask(tx, user_id, task_id, &if_match, &title).await?;
+            Ok((
+                StatusCode::CREATED,
+                serde_json::json!({ "subtask_id": subtask_id }),
+            ))
+        }
+        BatchOperation::UpdateSubtask {
+            task_id,
+            subtask_id,
+            version,
+            changes,
+        } => {
+            let if_match = expected_version(version)?;
+            let version =
+                apply_subtask_update(tx, user_id, task_id, subtask_id, &if_match, changes).await?;
+            Ok((StatusCode::OK, serde_json::json!({ "version": version })))
+        }
+        BatchOperation::DeleteSubtask {
+            task_id,
+            subtask_id,
+            version,
+        } => {
+            let if_match = expected_version(version)?;
+            remove_subtask(tx, user_id, task_id, subtask_id, &if_match).await?;
+            Ok((StatusCode::OK, serde_json::json!({})))
+        }
+        BatchOperation::ToggleSubtask {
+            task_id,
+            subtask_id,
+            version,
+        } => {
+            let if_match = expected_version(version)?;
+            let payload = ToggleSubtaskRequest {
+                task_id,
+                subtask_id,
+            };
+            let version = apply_subtask_toggle(tx, user_id, &if_match, payload).await?;
+            Ok((StatusCode::OK, serde_json::json!({ "version": version })))
+        }
+    }
+}
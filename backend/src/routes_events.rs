@@ -0,0 +1,48 @@
+use axum::{
+    Extension, Router,
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+};
+use tokio::sync::broadcast;
+use tokio_stream::{
+    Stream, StreamExt,
+    wrappers::{BroadcastStream, errors::BroadcastStreamRecvError},
+};
+use uuid::Uuid;
+
+use crate::events::TaskEvent;
+
+pub fn event_routes(events: broadcast::Sender<TaskEvent>) -> Router {
+    Router::new()
+        .route("/events", get(stream_events))
+        .with_state(events)
+}
+
+/// Flux Server-Sent Events des modifications de l'utilisateur.
+/// Chaque événement porte le type d'élément modifié (`task`, `subtask`, `completion`) ;
+/// un événement `resync` signale que des événements ont été perdus et qu'il faut tout recharger.
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "events",
+    responses(
+        (status = 200, description = "Flux Server-Sent Events", content_type = "text/event-stream"),
+    )
+)]
+pub async fn stream_events(
+    State(events): State<broadcast::Sender<TaskEvent>>,
+    Extension(user_id): Extension<Uuid>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let stream = BroadcastStream::new(events.subscribe()).filter_map(move |event| match event {
+        Ok(event) if event.user_id == user_id => {
+            Some(Event::default().event(&event.entity).json_data(&event))
+        }
+        Ok(_) => None,
+        Err(BroadcastStreamRecvError::Lagged(_)) => {
+            Some(Ok(Event::default().event("resync").data("{}")))
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,80 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::JournalQuery;
+
+pub fn journal_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/journal", get(get_journal))
+        .with_state(pool)
+}
+
+/// Liste les notes de toutes les tâches sur une période, avec recherche plein texte
+#[utoipa::path(
+    get,
+    path = "/journal",
+    tag = "journal",
+    params(JournalQuery),
+    responses(
+        (status = 200, description = "Notes de la période", body = Vec<serde_json::Value>),
+        (status = 400, description = "Période invalide"),
+    )
+)]
+pub async fn get_journal(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Query(query): Query<JournalQuery>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let search = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+
+    let entries = sqlx::query!(
+        r#"
+        SELECT tc.task_id, t.title, tc.date, tc.completed, tc.note, tc.rating
+        FROM task_completions tc
+        JOIN tasks t ON t.id = tc.task_id
+        WHERE t.user_id = $1
+          AND t.deleted = false
+          AND (tc.note IS NOT NULL OR tc.rating IS NOT NULL)
+          AND tc.date BETWEEN COALESCE($2, current_date - 29) AND COALESCE($3, current_date)
+          AND ($4::text IS NULL
+               OR to_tsvector('simple', COALESCE(tc.note, '')) @@ plainto_tsquery('simple', $4))
+        ORDER BY tc.date DESC, t.title
+        "#,
+        user_id,
+        query.from,
+        query.to,
+        search
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération journal: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result = entries
+        .into_iter()
+        .map(|e| serde_json::json!({
+            "task_id": e.task_id,
+            "title": e.title,
+            "date": e.date,
+            "completed": e.completed,
+            "note": e.note,
+            "rating": e.rating
+        }))
+        .collect();
+
+    Ok(Json(result))
+}
@@ -0,0 +1,466 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+};
+use chrono::NaiveTime;
+use lettre::Address;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{NotificationSettingsRequest, PushSubscriptionRequest, SetRemindersRequest};
+use crate::notifier::SmtpConfig;
+use crate::webhooks;
+use crate::webpush::VapidKey;
+
+/// Nombre maximal d'heures de rappel par tâche
+const MAX_REMINDERS: usize = 10;
+
+pub fn notification_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route(
+            "/tasks/:id/reminders",
+            get(get_reminders).put(set_reminders),
+        )
+        .route("/notifications", get(get_notifications))
+        .route(
+            "/notifications/settings",
+            get(get_settings).put(update_settings),
+        )
+        .route("/notifications/push/key", get(get_push_key))
+        .route("/notifications/push/subscriptions", post(subscribe_push))
+        .route(
+            "/notifications/push/subscriptions/:id",
+            delete(unsubscribe_push),
+        )
+        .with_state(pool)
+}
+
+/// Lit une heure `HH:MM` (ou `HH:MM:SS`)
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .ok()
+}
+
+/// Heure facultative : absente ou vide = désactivée, invalide = `Err`
+fn parse_optional_time(value: Option<&str>) -> Result<Option<NaiveTime>, StatusCode> {
+    match value.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => parse_time(v).map(Some).ok_or(StatusCode::BAD_REQUEST),
+        None => Ok(None),
+    }
+}
+
+fn format_time(time: Option<NaiveTime>) -> Option<String> {
+    time.map(|t| t.format("%H:%M").to_string())
+}
+
+// --- GESTIONNAIRES (HANDLERS) ---
+
+/// Liste les heures de rappel d'une tâche
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}/reminders",
+    tag = "notifications",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    responses(
+        (status = 200, description = "Heures de rappel", body = serde_json::Value),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn get_reminders(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let task = sqlx::query!(
+        r#"
+        SELECT COALESCE(array_agg(r.remind_at ORDER BY r.remind_at)
+                        FILTER (WHERE r.id IS NOT NULL), '{}') AS "times!"
+        FROM tasks t
+        LEFT JOIN task_reminders r ON r.task_id = t.id
+        WHERE t.id = $1 AND t.user_id = $2 AND t.deleted = false
+        GROUP BY t.id
+        "#,
+        id,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération rappels: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let times: Vec<_> = task
+        .times
+        .into_iter()
+        .map(|t| format_time(Some(t)))
+        .collect();
+
+    Ok(Json(serde_json::json!({ "task_id": id, "times": times })))
+}
+
+/// Remplace les heures de rappel d'une tâche. Les heures conservées gardent leur
+/// historique d'envoi : un rappel déjà parti aujourd'hui ne repart pas.
+#[utoipa::path(
+    put,
+    path = "/tasks/{id}/reminders",
+    tag = "notifications",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    request_body = SetRemindersRequest,
+    responses(
+        (status = 200, description = "Heures de rappel enregistrées", body = serde_json::Value),
+        (status = 400, description = "Heure invalide ou trop de rappels"),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn set_reminders(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<SetRemindersRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let mut times = payload
+        .times
+        .iter()
+        .map(|t| parse_time(t.trim()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    times.sort();
+    times.dedup();
+    if times.len() > MAX_REMINDERS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM tasks WHERE id = $1 AND user_id = $2 AND deleted = false) AS "exists!""#,
+        id,
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !exists {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    sqlx::query!(
+        "DELETE FROM task_reminders WHERE task_id = $1 AND remind_at <> ALL($2)",
+        id,
+        &times
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur suppression rappels: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO task_reminders (task_id, remind_at)
+        SELECT $1, unnest($2::time[])
+        ON CONFLICT (task_id, remind_at) DO NOTHING
+        "#,
+        id,
+        &times
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur ajout rappels: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let times: Vec<_> = times.into_iter().map(|t| format_time(Some(t))).collect();
+
+    Ok(Json(serde_json::json!({ "task_id": id, "times": times })))
+}
+
+/// Dernières notifications envoyées (rappels et récapitulatifs)
+#[utoipa::path(
+    get,
+    path = "/notifications",
+    tag = "notifications",
+    responses(
+        (status = 200, description = "Dernières notifications", body = Vec<serde_json::Value>),
+    )
+)]
+pub async fn get_notifications(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let notifications = sqlx::query!(
+        r#"
+        SELECT id, kind, task_id, date, title, body, created_at
+        FROM notifications
+        WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT 50
+        "#,
+        user_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération notifications: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result = notifications
+        .into_iter()
+        .map(|n| {
+            serde_json::json!({
+                "id": n.id,
+                "kind": n.kind,
+                "task_id": n.task_id,
+                "date": n.date,
+                "title": n.title,
+                "body": n.body,
+                "created_at": n.created_at
+            })
+        })
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Réglages de l'utilisateur et canaux activés sur le serveur
+#[utoipa::path(
+    get,
+    path = "/notifications/settings",
+    tag = "notifications",
+    responses(
+        (status = 200, description = "Réglages et canaux disponibles", body = serde_json::Value),
+    )
+)]
+pub async fn get_settings(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let settings = sqlx::query!(
+        r#"
+        SELECT webhook_url, email, quiet_start, quiet_end, digest_at
+        FROM notification_settings
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération réglages notifications: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let push_subscriptions = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM push_subscriptions WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let channels = serde_json::json!({
+        "webhook": true,
+        "email": SmtpConfig::from_env().is_some(),
+        "webpush": VapidKey::from_env().is_some_and(|k| k.is_ok())
+    });
+
+    Ok(Json(serde_json::json!({
+        "webhook_url": settings.as_ref().and_then(|s| s.webhook_url.clone()),
+        "email": settings.as_ref().and_then(|s| s.email.clone()),
+        "quiet_start": format_time(settings.as_ref().and_then(|s| s.quiet_start)),
+        "quiet_end": format_time(settings.as_ref().and_then(|s| s.quiet_end)),
+        "digest_at": format_time(settings.as_ref().and_then(|s| s.digest_at)),
+        "push_subscriptions": push_subscriptions,
+        "channels": channels
+    })))
+}
+
+/// Remplace les réglages de notification
+#[utoipa::path(
+    put,
+    path = "/notifications/settings",
+    tag = "notifications",
+    request_body = NotificationSettingsRequest,
+    responses(
+        (status = 204, description = "Réglages enregistrés"),
+        (status = 400, description = "Données invalides, ou URL locale"),
+    )
+)]
+pub async fn update_settings(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<NotificationSettingsRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let webhook_url = payload
+        .webhook_url
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty());
+    if let Some(url) = &webhook_url
+        && !webhooks::is_allowed_url(url)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let email = payload
+        .email
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty());
+    if let Some(email) = &email
+        && email.parse::<Address>().is_err()
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let quiet_start = parse_optional_time(payload.quiet_start.as_deref())?;
+    let quiet_end = parse_optional_time(payload.quiet_end.as_deref())?;
+    let digest_at = parse_optional_time(payload.digest_at.as_deref())?;
+    // Heures calmes : début et fin ensemble, et une plage non vide
+    if quiet_start.is_some() != quiet_end.is_some()
+        || (quiet_start.is_some() && quiet_start == quiet_end)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO notification_settings (user_id, webhook_url, email, quiet_start, quiet_end, digest_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id) DO UPDATE SET
+            webhook_url = EXCLUDED.webhook_url,
+            email = EXCLUDED.email,
+            quiet_start = EXCLUDED.quiet_start,
+            quiet_end = EXCLUDED.quiet_end,
+            digest_at = EXCLUDED.digest_at
+        "#,
+        user_id,
+        webhook_url,
+        email,
+        quiet_start,
+        quiet_end,
+        digest_at
+    )
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur mise à jour réglages notifications: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Clé publique VAPID pour `pushManager.subscribe` (404 si Web Push n'est pas configuré)
+#[utoipa::path(
+    get,
+    path = "/notifications/push/key",
+    tag = "notifications",
+    responses(
+        (status = 200, description = "Clé publique VAPID", body = serde_json::Value),
+        (status = 404, description = "Web Push non configuré"),
+    )
+)]
+pub async fn get_push_key() -> Result<Json<serde_json::Value>, StatusCode> {
+    let key = VapidKey::from_env()
+        .and_then(Result::ok)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(serde_json::json!({ "public_key": key.public_key() })))
+}
+
+/// Enregistre l'abonnement Web Push du navigateur (ou le rattache à l'utilisateur)
+#[utoipa::path(
+    post,
+    path = "/notifications/push/subscriptions",
+    tag = "notifications",
+    request_body = PushSubscriptionRequest,
+    responses(
+        (status = 201, description = "Abonnement enregistré", body = serde_json::Value),
+        (status = 400, description = "Données invalides, ou URL locale"),
+    )
+)]
+pub async fn subscribe_push(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<PushSubscriptionRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
+    if !webhooks::is_allowed_url(&payload.endpoint)
+        || payload.keys.p256dh.is_empty()
+        || payload.keys.auth.is_empty()
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO push_subscriptions (user_id, endpoint, p256dh, auth)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (endpoint) DO UPDATE SET
+            user_id = EXCLUDED.user_id,
+            p256dh = EXCLUDED.p256dh,
+            auth = EXCLUDED.auth
+        RETURNING id
+        "#,
+        user_id,
+        payload.endpoint,
+        payload.keys.p256dh,
+        payload.keys.auth
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur enregistrement abonnement push: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "id": id }))))
+}
+
+/// Supprime un abonnement Web Push
+#[utoipa::path(
+    delete,
+    path = "/notifications/push/subscriptions/{id}",
+    tag = "notifications",
+    params(("id" = i32, Path, description = "Identifiant de l'abonnement")),
+    responses(
+        (status = 204, description = "Abonnement supprimé"),
+        (status = 404, description = "Abonnement introuvable"),
+    )
+)]
+pub async fn unsubscribe_push(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let result = sqlx::query!(
+        "DELETE FROM push_subscriptions WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur suppression abonnement push: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
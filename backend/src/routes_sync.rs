@@ -0,0 +1,624 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Connection, PgConnection, PgPool, Postgres, Transaction};
+use std::collections::{BTreeSet, HashSet};
+use uuid::Uuid;
+
+use crate::audit::{self, AuditEntry};
+use crate::models::{SyncChange, SyncQuery, SyncRequest};
+use crate::routes::{insert_task, task_representations};
+
+/// Nombre maximal de mutations acceptées par requête
+const MAX_MUTATIONS: usize = 500;
+
+pub fn sync_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/sync", get(pull_changes).post(push_mutations))
+        .with_state(pool)
+}
+
+/// Issue d'une mutation appliquée ou écartée
+enum Outcome {
+    Applied { task_id: Option<i32> },
+    Conflict,
+}
+
+// --- GESTIONNAIRES (HANDLERS) ---
+
+/// Renvoie les tâches (avec leurs sous-tâches) et complétions modifiées depuis le curseur,
+/// ainsi que celles qui ont disparu, et le curseur de la prochaine synchronisation.
+///
+/// Le curseur est la plus ancienne transaction encore en cours au moment de la lecture :
+/// toutes les transactions antérieures sont terminées et visibles, donc les fenêtres
+/// successives `[since, cursor[` du journal d'audit ne perdent aucune modification.
+#[utoipa::path(
+    get,
+    path = "/sync",
+    tag = "sync",
+    params(SyncQuery),
+    responses(
+        (status = 200, description = "Éléments modifiés et nouveau curseur", body = serde_json::Value),
+        (status = 400, description = "Curseur invalide"),
+    )
+)]
+pub async fn pull_changes(
+    Query(query): Query<SyncQuery>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let since = query
+        .since
+        .as_deref()
+        .map(str::parse::<i64>)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Toutes les lectures voient le même instantané que le curseur
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let cursor =
+        sqlx::query_scalar!(r#"SELECT txid_snapshot_xmin(txid_current_snapshot()) as "cursor!""#)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let Some(since) = since else {
+        // Première synchronisation : tout l'état de l'utilisateur
+        let tasks = task_representations(&mut tx, user_id, None).await?;
+        let completions = completions(&mut tx, user_id, None).await?;
+        return Ok(Json(serde_json::json!({
+            "cursor": cursor.to_string(),
+            "tasks": tasks,
+            "completions": completions.into_iter().map(|(_, c)| c).collect::<Vec<_>>(),
+            "deleted": { "tasks": [], "completions": [] }
+        })));
+    };
+
+    let changes = sqlx::query!(
+        r#"
+        SELECT DISTINCT entity, entity_id, task_id
+        FROM audit_log
+        WHERE owner_id = $1 AND tx_id >= $2 AND tx_id < $3
+        "#,
+        user_id,
+        since,
+        cursor
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur lecture des modifications: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Une sous-tâche modifiée renvoie sa tâche, dont la liste de sous-tâches fait foi
+    let mut task_ids = BTreeSet::new();
+    let mut completion_keys = BTreeSet::new();
+    for change in &changes {
+        match change.entity.as_str() {
+            "completion" => {
+                let date = change
+                    .entity_id
+                    .split_once('@')
+                    .and_then(|(_, date)| date.parse::<NaiveDate>().ok())
+                    .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+                completion_keys.insert((change.task_id, date));
+            }
+            _ => {
+                task_ids.insert(change.task_id);
+            }
+        }
+    }
+
+    let task_ids: Vec<i32> = task_ids.into_iter().collect();
+    let tasks = task_representations(&mut tx, user_id, Some(&task_ids)).await?;
+    let found_tasks: HashSet<i64> = tasks.iter().filter_map(|t| t["id"].as_i64()).collect();
+    let deleted_tasks: Vec<i32> = task_ids
+        .into_iter()
+        .filter(|id| !found_tasks.contains(&(*id as i64)))
+        .collect();
+
+    let completion_keys: Vec<(i32, NaiveDate)> = completion_keys.into_iter().collect();
+    let completions = completions(&mut tx, user_id, Some(&completion_keys)).await?;
+    let found_completions: HashSet<(i32, NaiveDate)> =
+        completions.iter().map(|(key, _)| *key).collect();
+    let deleted_completions: Vec<serde_json::Value> = completion_keys
+        .into_iter()
+        .filter(|key| !found_completions.contains(key))
+        .map(|(task_id, date)| serde_json::json!({ "task_id": task_id, "date": date }))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "cursor": cursor.to_string(),
+        "tasks": tasks,
+        "completions": completions.into_iter().map(|(_, c)| c).collect::<Vec<_>>(),
+        "deleted": { "tasks": deleted_tasks, "completions": deleted_completions }
+    })))
+}
+
+/// Applique un lot de mutations d'un client, dans l'ordre de leur date côté client.
+/// En cas de conflit, la modification la plus récente l'emporte : une mutation est écartée
+/// (`conflict`) si l'élément a été modifié depuis sa date, le serveur gagnant en cas d'égalité.
+/// Une mutation invalide est refusée (`rejected`) sans bloquer les suivantes.
+#[utoipa::path(
+    post,
+    path = "/sync",
+    tag = "sync",
+    request_body = SyncRequest,
+    responses(
+        (status = 200, description = "Résultat de chaque mutation", body = serde_json::Value),
+        (status = 413, description = "Trop de mutations"),
+    )
+)]
+pub async fn push_mutations(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<SyncRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if payload.mutations.len() > MAX_MUTATIONS {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let mut mutations = payload.mutations;
+    mutations.sort_by_key(|m| (m.at, m.id));
+
+    // Une horloge client en avance ne doit pas gagner tous les conflits à venir
+    let now = Utc::now();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut results = Vec::with_capacity(mutations.len());
+    for mutation in mutations {
+        // Mutation déjà reçue (renvoi après une coupure) : même résultat, sans la rejouer
+        let previous = sqlx::query_scalar!(
+            "SELECT result FROM sync_mutations WHERE user_id = $1 AND mutation_id = $2",
+            user_id,
+            mutation.id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(result) = previous {
+            results.push(result);
+            continue;
+        }
+
+        // Chaque mutation dans son point de sauvegarde, pour écarter les seules refusées
+        let mut savepoint = tx
+            .begin()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let mutation_id = mutation.id;
+        let at = mutation.at.min(now);
+        let outcome = apply_mutation(&mut savepoint, user_id, at, mutation.change).await;
+        if matches!(outcome, Ok(Outcome::Applied { .. })) {
+            savepoint.commit().await
+        } else {
+            savepoint.rollback().await
+        }
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let result = match outcome {
+            Ok(Outcome::Applied {
+                task_id: Some(task_id),
+            }) => serde_json::json!({
+                "id": mutation_id, "status": "applied", "task_id": task_id
+            }),
+            Ok(Outcome::Applied { task_id: None }) => {
+                serde_json::json!({ "id": mutation_id, "status": "applied" })
+            }
+            Ok(Outcome::Conflict) => serde_json::json!({ "id": mutation_id, "status": "conflict" }),
+            Err(StatusCode::INTERNAL_SERVER_ERROR) => {
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+            Err(status) => serde_json::json!({
+                "id": mutation_id, "status": "rejected", "error": status.as_u16()
+            }),
+        };
+
+        sqlx::query!(
+            "INSERT INTO sync_mutations (user_id, mutation_id, result) VALUES ($1, $2, $3)",
+            user_id,
+            mutation_id,
+            result
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            eprintln!("Erreur enregistrement mutation: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        results.push(result);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({ "results": results })))
+}
+
+// --- MUTATIONS ---
+
+async fn apply_mutation(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    at: DateTime<Utc>,
+    change: SyncChange,
+) -> Result<Outcome, StatusCode> {
+    match change {
+        SyncChange::CreateTask(mut payload) => {
+            payload.days.sort_unstable();
+            payload.days.dedup();
+            if payload.title.trim().is_empty() || payload.days.iter().any(|d| !(1..=7).contains(d))
+            {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            let task_id = insert_task(tx, user_id, payload).await?;
+            Ok(Outcome::Applied {
+                task_id: Some(task_id),
+            })
+        }
+        SyncChange::UpdateTask {
+            task_id,
+            title,
+            days,
+            active,
+            deleted,
+        } => {
+            if title.as_deref().is_some_and(|t| t.trim().is_empty())
+                || days
+                    .as_ref()
+                    .is_some_and(|d| d.iter().any(|d| !(1..=7).contains(d)))
+            {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            update_task(tx, user_id, at, task_id, title, days, active, deleted).await
+        }
+        SyncChange::UpdateSubtask {
+            task_id,
+            subtask_id,
+            title,
+            completed,
+        } => {
+            if title.as_deref().is_some_and(|t| t.trim().is_empty()) {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            update_subtask(tx, user_id, at, task_id, subtask_id, title, completed).await
+        }
+        SyncChange::SetCompletion {
+            task_id,
+            date,
+            completed,
+            value,
+            note,
+            rating,
+            skipped,
+            skip_reason,
+        } => {
+            if rating.is_some_and(|r| !(1..=5).contains(&r))
+                || value.is_some_and(|v| !v.is_finite() || v < 0.0)
+                || date > at.date_naive()
+            {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            let change = CompletionChange {
+                completed,
+                value,
+                note,
+                rating,
+                skipped,
+                skip_reason,
+            };
+            set_completion(tx, user_id, at, task_id, date, change).await
+        }
+    }
+}
+
+/// Verrouille une tâche de l'utilisateur jusqu'à la fin de la synchronisation
+async fn lock_task(conn: &mut PgConnection, user_id: Uuid, task_id: i32) -> Result<(), StatusCode> {
+    sqlx::query_scalar!(
+        "SELECT id FROM tasks WHERE id = $1 AND user_id = $2 AND deleted = false FOR UPDATE",
+        task_id,
+        user_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(())
+}
+
+/// Vrai si l'élément a été modifié côté serveur à la date du client ou après
+async fn changed_since(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    entity: &str,
+    entity_id: &str,
+    at: DateTime<Utc>,
+) -> Result<bool, StatusCode> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM audit_log
+            WHERE owner_id = $1 AND entity = $2 AND entity_id = $3
+              AND COALESCE(changed_at, created_at) >= $4
+        ) as "changed!"
+        "#,
+        user_id,
+        entity,
+        entity_id,
+        at
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur détection conflit: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn update_task(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    at: DateTime<Utc>,
+    task_id: i32,
+    title: Option<String>,
+    days: Option<Vec<i32>>,
+    active: Option<bool>,
+    deleted: Option<bool>,
+) -> Result<Outcome, StatusCode> {
+    // Une tâche à la corbeille peut encore être restaurée
+    sqlx::query_scalar!(
+        "SELECT id FROM tasks WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        task_id,
+        user_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    if changed_since(tx, user_id, "task", &task_id.to_string(), at).await? {
+        return Ok(Outcome::Conflict);
+    }
+
+    let before = audit::task_snapshot(tx, task_id).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE tasks SET
+            title = COALESCE($2, title),
+            active = COALESCE($3, active),
+            deleted = COALESCE($4, deleted),
+            deleted_at = CASE
+                WHEN $4 IS NULL THEN deleted_at
+                WHEN $4 THEN COALESCE(deleted_at, now())
+            END
+        WHERE id = $1
+        "#,
+        task_id,
+        title.as_deref().map(str::trim),
+        active,
+        deleted
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur synchronisation tâche: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if let Some(days) = days {
+        sqlx::query!("DELETE FROM task_days WHERE task_id = $1", task_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        sqlx::query!(
+            r#"
+            INSERT INTO task_days (task_id, day_of_week)
+            SELECT DISTINCT $1::int, d FROM unnest($2::int[]) AS d
+            "#,
+            task_id,
+            &days
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    let after = audit::task_snapshot(tx, task_id).await?;
+    audit::record(
+        tx,
+        AuditEntry::task(user_id, task_id, "sync")
+            .before(before)
+            .after(after)
+            .changed_at(at),
+    )
+    .await?;
+
+    Ok(Outcome::Applied { task_id: None })
+}
+
+async fn update_subtask(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    at: DateTime<Utc>,
+    task_id: i32,
+    subtask_id: i32,
+    title: Option<String>,
+    completed: Option<bool>,
+) -> Result<Outcome, StatusCode> {
+    lock_task(tx, user_id, task_id).await?;
+
+    if changed_since(tx, user_id, "subtask", &subtask_id.to_string(), at).await? {
+        return Ok(Outcome::Conflict);
+    }
+
+    let before = audit::subtask_snapshot(tx, subtask_id).await?;
+
+    sqlx::query_scalar!(
+        r#"
+        UPDATE subtasks
+        SET title = COALESCE($3, title), completed = COALESCE($4, completed)
+        WHERE id = $1 AND task_id = $2
+        RETURNING id
+        "#,
+        subtask_id,
+        task_id,
+        title.as_deref().map(str::trim),
+        completed
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur synchronisation sous-tâche: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let after = audit::subtask_snapshot(tx, subtask_id).await?;
+    audit::record(
+        tx,
+        AuditEntry::subtask(user_id, task_id, subtask_id, "sync")
+            .before(before)
+            .after(after)
+            .changed_at(at),
+    )
+    .await?;
+
+    Ok(Outcome::Applied { task_id: None })
+}
+
+/// Champs d'une occurrence modifiés par le client (les autres sont conservés)
+struct CompletionChange {
+    completed: Option<bool>,
+    value: Option<f64>,
+    note: Option<String>,
+    rating: Option<i16>,
+    skipped: Option<bool>,
+    skip_reason: Option<String>,
+}
+
+async fn set_completion(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    at: DateTime<Utc>,
+    task_id: i32,
+    date: NaiveDate,
+    change: CompletionChange,
+) -> Result<Outcome, StatusCode> {
+    lock_task(tx, user_id, task_id).await?;
+
+    let entity_id = format!("{}@{}", task_id, date);
+    if changed_since(tx, user_id, "completion", &entity_id, at).await? {
+        return Ok(Outcome::Conflict);
+    }
+
+    let before = audit::completion_snapshot(tx, task_id, date).await?;
+
+    // Comme pour le saut d'une occurrence, une occurrence sautée n'est pas complétée
+    sqlx::query!(
+        r#"
+        INSERT INTO task_completions (task_id, date, completed, value, note, rating, skipped, skip_reason)
+        VALUES (
+            $1, $2,
+            COALESCE($3, false) AND NOT COALESCE($7, false),
+            $4, NULLIF($5, ''), $6,
+            COALESCE($7, false),
+            CASE WHEN $7 THEN $8 END
+        )
+        ON CONFLICT (task_id, date) DO UPDATE SET
+            completed = COALESCE($3, task_completions.completed)
+                AND NOT COALESCE($7, task_completions.skipped),
+            value = COALESCE($4, task_completions.value),
+            note = CASE WHEN $5::text IS NULL THEN task_completions.note ELSE NULLIF($5, '') END,
+            rating = COALESCE($6, task_completions.rating),
+            skipped = COALESCE($7, task_completions.skipped),
+            skip_reason = CASE
+                WHEN $7 IS NULL THEN task_completions.skip_reason
+                WHEN $7 THEN $8
+            END
+        "#,
+        task_id,
+        date,
+        change.completed,
+        change.value,
+        change.note.as_deref().map(str::trim),
+        change.rating,
+        change.skipped,
+        change.skip_reason
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur synchronisation complétion: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let after = audit::completion_snapshot(tx, task_id, date).await?;
+    audit::record(
+        tx,
+        AuditEntry::completion(user_id, task_id, date, "sync")
+            .before(before)
+            .after(after)
+            .changed_at(at),
+    )
+    .await?;
+
+    Ok(Outcome::Applied { task_id: None })
+}
+
+/// Complétions des tâches de l'utilisateur (toutes si `keys` vaut None)
+async fn completions(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    keys: Option<&[(i32, NaiveDate)]>,
+) -> Result<Vec<((i32, NaiveDate), serde_json::Value)>, StatusCode> {
+    let (task_ids, dates): (Vec<i32>, Vec<NaiveDate>) =
+        keys.unwrap_or_default().iter().copied().unzip();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT tc.task_id as "task_id!", tc.date, to_jsonb(tc) as "completion!"
+        FROM task_completions tc
+        JOIN tasks t ON t.id = tc.task_id
+        WHERE t.user_id = $1
+          AND ($2 OR (tc.task_id, tc.date) IN (SELECT * FROM unnest($3::int[], $4::date[])))
+        ORDER BY tc.task_id, tc.date
+        "#,
+        user_id,
+        keys.is_none(),
+        &task_ids,
+        &dates
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération complétions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| ((r.task_id, r.date), r.completion))
+        .collect())
+}
@@ -0,0 +1,248 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::{CreateTagRequest, Tag, UpdateTagRequest};
+use crate::stats;
+
+pub fn tag_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/tags", get(get_tags).post(create_tag))
+        .route("/tags/:id", post(update_tag).delete(delete_tag))
+        .route("/stats/tags", get(get_tag_stats))
+        .with_state(pool)
+}
+
+/// Vérifie qu'une couleur est au format hexadécimal `#rrggbb`
+fn is_valid_color(color: &str) -> bool {
+    color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// --- GESTIONNAIRES (HANDLERS) ---
+
+/// Liste les étiquettes de l'utilisateur
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "tags",
+    responses(
+        (status = 200, description = "Étiquettes de l'utilisateur", body = Vec<Tag>),
+    )
+)]
+pub async fn get_tags(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<Tag>>, StatusCode> {
+    let tags = sqlx::query_as!(
+        Tag,
+        "SELECT id, name, color FROM tags WHERE user_id = $1 ORDER BY name",
+        user_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération étiquettes: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(tags))
+}
+
+/// Crée une étiquette
+#[utoipa::path(
+    post,
+    path = "/tags",
+    tag = "tags",
+    request_body = CreateTagRequest,
+    responses(
+        (status = 201, description = "Étiquette créée", body = Tag),
+        (status = 400, description = "Nom ou couleur invalide"),
+        (status = 409, description = "Nom déjà utilisé"),
+    )
+)]
+pub async fn create_tag(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<CreateTagRequest>,
+) -> Result<(StatusCode, Json<Tag>), StatusCode> {
+    if payload.name.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Some(color) = &payload.color
+        && !is_valid_color(color)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let tag = sqlx::query_as!(
+        Tag,
+        r#"
+        INSERT INTO tags (user_id, name, color)
+        VALUES ($1, $2, COALESCE($3, '#6366f1'))
+        RETURNING id, name, color
+        "#,
+        user_id,
+        payload.name.trim(),
+        payload.color
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| db::conflict_status(e, "création étiquette"))?;
+
+    Ok((StatusCode::CREATED, Json(tag)))
+}
+
+/// Renomme ou recolore une étiquette
+#[utoipa::path(
+    post,
+    path = "/tags/{id}",
+    tag = "tags",
+    params(("id" = i32, Path, description = "Identifiant de l'étiquette")),
+    request_body = UpdateTagRequest,
+    responses(
+        (status = 200, description = "Étiquette modifiée", body = Tag),
+        (status = 400, description = "Nom ou couleur invalide"),
+        (status = 404, description = "Étiquette introuvable"),
+        (status = 409, description = "Nom déjà utilisé"),
+    )
+)]
+pub async fn update_tag(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<UpdateTagRequest>,
+) -> Result<Json<Tag>, StatusCode> {
+    if payload.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if payload.color.as_deref().is_some_and(|c| !is_valid_color(c)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let tag = sqlx::query_as!(
+        Tag,
+        r#"
+        UPDATE tags
+        SET name = COALESCE($1, name),
+            color = COALESCE($2, color)
+        WHERE id = $3 AND user_id = $4
+        RETURNING id, name, color
+        "#,
+        payload.name.as_deref().map(str::trim),
+        payload.color,
+        id,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| db::conflict_status(e, "modification étiquette"))?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(tag))
+}
+
+/// Supprime une étiquette (les tâches associées sont conservées)
+#[utoipa::path(
+    delete,
+    path = "/tags/{id}",
+    tag = "tags",
+    params(("id" = i32, Path, description = "Identifiant de l'étiquette")),
+    responses(
+        (status = 200, description = "Étiquette supprimée"),
+        (status = 404, description = "Étiquette introuvable"),
+    )
+)]
+pub async fn delete_tag(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let result = sqlx::query!(
+        "DELETE FROM tags WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Taux de complétion par étiquette sur les 30 derniers jours
+#[utoipa::path(
+    get,
+    path = "/stats/tags",
+    tag = "stats",
+    responses(
+        (status = 200, description = "Taux de complétion par étiquette", body = Vec<serde_json::Value>),
+    )
+)]
+pub async fn get_tag_stats(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    stats::ensure_recent(&pool, user_id, 30).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        WITH occurrences AS (
+            SELECT tt.tag_id, s.completed
+            FROM task_daily_stats s
+            JOIN tasks t ON t.id = s.task_id
+            JOIN task_tags tt ON tt.task_id = t.id
+            WHERE s.user_id = $1 AND t.deleted = false AND t.active = true
+              AND s.date > current_date - 30
+              AND s.scheduled
+        )
+        SELECT g.id, g.name, g.color,
+               COUNT(o.tag_id)::int as "scheduled!",
+               COUNT(o.tag_id) FILTER (WHERE o.completed)::int as "completed!"
+        FROM tags g
+        LEFT JOIN occurrences o ON o.tag_id = g.id
+        WHERE g.user_id = $1
+        GROUP BY g.id, g.name, g.color
+        ORDER BY g.name
+        "#,
+        user_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur statistiques étiquettes: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result = rows
+        .into_iter()
+        .map(|r| {
+            let success_rate = if r.scheduled > 0 {
+                (r.completed as f64 / r.scheduled as f64 * 100.0).round() as i32
+            } else {
+                0
+            };
+            serde_json::json!({
+                "id": r.id,
+                "name": r.name,
+                "color": r.color,
+                "scheduled": r.scheduled,
+                "completed": r.completed,
+                "success_rate": success_rate
+            })
+        })
+        .collect();
+
+    Ok(Json(result))
+}
@@ -0,0 +1,811 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::audit::{self, AuditEntry};
+use crate::db;
+use crate::models::{
+    AddMemberRequest, CreateTeamRequest, CreateTemplateRequest, UpdateTemplateRequest,
+};
+use crate::stats;
+
+pub fn team_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/teams", get(get_teams).post(create_team))
+        .route("/teams/:id/members", get(get_members).post(add_member))
+        .route("/teams/:id/members/:member_id", delete(remove_member))
+        .route("/teams/:id/templates", get(get_templates).post(create_template))
+        .route(
+            "/teams/:id/templates/:template_id",
+            post(update_template).delete(delete_template),
+        )
+        .route("/teams/:id/dashboard", get(get_team_dashboard))
+        .with_state(pool)
+}
+
+// --- OUTILS ---
+
+/// Renvoie le rôle de l'utilisateur dans l'équipe (404 s'il n'en fait pas partie)
+async fn member_role(pool: &PgPool, team_id: i32, user_id: Uuid) -> Result<String, StatusCode> {
+    sqlx::query_scalar!(
+        "SELECT role FROM team_members WHERE team_id = $1 AND user_id = $2",
+        team_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur vérification membre: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Vérifie que l'utilisateur est administrateur de l'équipe
+async fn require_admin(pool: &PgPool, team_id: i32, user_id: Uuid) -> Result<(), StatusCode> {
+    match member_role(pool, team_id, user_id).await?.as_str() {
+        "admin" => Ok(()),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+/// Copie un modèle d'équipe dans la liste de tâches d'un membre
+/// (`actor_id` est l'utilisateur à l'origine de la copie)
+async fn copy_template_to_user(
+    tx: &mut Transaction<'_, Postgres>,
+    template_id: i32,
+    user_id: Uuid,
+    actor_id: Uuid,
+) -> Result<(), StatusCode> {
+    let template = sqlx::query!(
+        "SELECT title, days, subtasks FROM team_templates WHERE id = $1",
+        template_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération modèle: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let task = sqlx::query!(
+        r#"
+        INSERT INTO tasks (user_id, title, template_id, has_subtasks)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+        user_id,
+        template.title,
+        template_id,
+        !template.subtasks.is_empty()
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur copie modèle: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    sqlx::query!(
+        "INSERT INTO task_days (task_id, day_of_week) SELECT $1, unnest($2::int[])",
+        task.id,
+        &template.days
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur copie jours: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    for (priority, title) in template.subtasks.iter().enumerate() {
+        sqlx::query!(
+            "INSERT INTO subtasks (task_id, title, priority) VALUES ($1, $2, $3)",
+            task.id,
+            title,
+            priority as i32
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
+            eprintln!("Erreur copie sous-tâche: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    let after = audit::task_snapshot(tx, task.id).await?;
+    audit::record(tx, AuditEntry::task(actor_id, task.id, "create").after(after)).await?;
+
+    Ok(())
+}
+
+// --- GESTIONNAIRES (HANDLERS) ---
+
+/// Liste les équipes de l'utilisateur avec son rôle
+#[utoipa::path(
+    get,
+    path = "/teams",
+    tag = "teams",
+    responses(
+        (status = 200, description = "Équipes et rôle de l'utilisateur", body = Vec<serde_json::Value>),
+    )
+)]
+pub async fn get_teams(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let teams = sqlx::query!(
+        r#"
+        SELECT t.id, t.name, m.role,
+               (SELECT COUNT(*) FROM team_members WHERE team_id = t.id) as "members_count!"
+        FROM teams t
+        JOIN team_members m ON m.team_id = t.id
+        WHERE m.user_id = $1
+        ORDER BY t.name
+        "#,
+        user_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération équipes: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result = teams
+        .into_iter()
+        .map(|t| serde_json::json!({
+            "id": t.id,
+            "name": t.name,
+            "role": t.role,
+            "members_count": t.members_count
+        }))
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Crée une équipe, le créateur en devient administrateur
+#[utoipa::path(
+    post,
+    path = "/teams",
+    tag = "teams",
+    request_body = CreateTeamRequest,
+    responses(
+        (status = 201, description = "Équipe créée", body = serde_json::Value),
+        (status = 400, description = "Données invalides"),
+    )
+)]
+pub async fn create_team(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<CreateTeamRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
+    if payload.name.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let team = sqlx::query!(
+        "INSERT INTO teams (name, created_by) VALUES ($1, $2) RETURNING id",
+        payload.name,
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur création équipe: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    sqlx::query!(
+        "INSERT INTO team_members (team_id, user_id, role) VALUES ($1, $2, 'admin')",
+        team.id,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur ajout administrateur: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "id": team.id }))))
+}
+
+/// Liste les membres d'une équipe
+#[utoipa::path(
+    get,
+    path = "/teams/{id}/members",
+    tag = "teams",
+    params(("id" = i32, Path, description = "Identifiant de l'équipe")),
+    responses(
+        (status = 200, description = "Membres de l'équipe", body = Vec<serde_json::Value>),
+        (status = 403, description = "Réservé aux membres (ou administrateurs) de l'équipe"),
+    )
+)]
+pub async fn get_members(
+    Path(team_id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    member_role(&pool, team_id, user_id).await?;
+
+    let members = sqlx::query!(
+        r#"
+        SELECT u.id, u.username, m.role
+        FROM team_members m
+        JOIN users u ON u.id = m.user_id
+        WHERE m.team_id = $1
+        ORDER BY u.username
+        "#,
+        team_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let result = members
+        .into_iter()
+        .map(|m| serde_json::json!({
+            "id": m.id,
+            "username": m.username,
+            "role": m.role
+        }))
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Ajoute un membre (admin seulement) et lui copie les modèles de l'équipe
+#[utoipa::path(
+    post,
+    path = "/teams/{id}/members",
+    tag = "teams",
+    params(("id" = i32, Path, description = "Identifiant de l'équipe")),
+    request_body = AddMemberRequest,
+    responses(
+        (status = 201, description = "Membre ajouté"),
+        (status = 400, description = "Données invalides"),
+        (status = 403, description = "Réservé aux membres (ou administrateurs) de l'équipe"),
+        (status = 404, description = "Utilisateur introuvable"),
+        (status = 409, description = "Déjà membre"),
+    )
+)]
+pub async fn add_member(
+    Path(team_id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<AddMemberRequest>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&pool, team_id, user_id).await?;
+
+    let role = payload.role.unwrap_or_else(|| "member".to_string());
+    if role != "admin" && role != "member" {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let member_id = sqlx::query_scalar!(
+        "SELECT id FROM users WHERE username = $1",
+        payload.username
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    sqlx::query!(
+        "INSERT INTO team_members (team_id, user_id, role) VALUES ($1, $2, $3)",
+        team_id,
+        member_id,
+        role
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| db::conflict_status(e, "ajout membre"))?;
+
+    // Le nouveau membre reçoit toutes les routines de l'équipe
+    let template_ids = sqlx::query_scalar!(
+        "SELECT id FROM team_templates WHERE team_id = $1 ORDER BY id",
+        team_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    for template_id in template_ids {
+        copy_template_to_user(&mut tx, template_id, member_id, user_id).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::CREATED)
+}
+
+/// Retire un membre de l'équipe (admin, ou le membre lui-même)
+#[utoipa::path(
+    delete,
+    path = "/teams/{id}/members/{member_id}",
+    tag = "teams",
+    params(
+        ("id" = i32, Path, description = "Identifiant de l'équipe"),
+        ("member_id" = Uuid, Path, description = "Identifiant de l'utilisateur"),
+    ),
+    responses(
+        (status = 200, description = "Membre retiré"),
+        (status = 403, description = "Réservé aux membres (ou administrateurs) de l'équipe"),
+        (status = 404, description = "Membre introuvable"),
+    )
+)]
+pub async fn remove_member(
+    Path((team_id, member_id)): Path<(i32, Uuid)>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    if member_id != user_id {
+        require_admin(&pool, team_id, user_id).await?;
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Une équipe doit toujours garder au moins un administrateur. Le verrou sur les
+    // administrateurs ordonne les départs simultanés : le second voit le premier parti.
+    let admins = sqlx::query_scalar!(
+        "SELECT user_id FROM team_members WHERE team_id = $1 AND role = 'admin' FOR UPDATE",
+        team_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if admins.iter().all(|&admin| admin == member_id) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let result = sqlx::query!(
+        "DELETE FROM team_members WHERE team_id = $1 AND user_id = $2",
+        team_id,
+        member_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    // Ses copies des modèles deviennent des tâches personnelles : l'équipe ne les
+    // modifie plus et ne les compte plus dans son tableau de bord
+    sqlx::query!(
+        r#"
+        UPDATE tasks SET template_id = NULL
+        WHERE user_id = $2
+          AND template_id IN (SELECT id FROM team_templates WHERE team_id = $1)
+        "#,
+        team_id,
+        member_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Liste les modèles de tâches de l'équipe
+#[utoipa::path(
+    get,
+    path = "/teams/{id}/templates",
+    tag = "teams",
+    params(("id" = i32, Path, description = "Identifiant de l'équipe")),
+    responses(
+        (status = 200, description = "Modèles de l'équipe", body = Vec<serde_json::Value>),
+        (status = 403, description = "Réservé aux membres (ou administrateurs) de l'équipe"),
+    )
+)]
+pub async fn get_templates(
+    Path(team_id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    member_role(&pool, team_id, user_id).await?;
+
+    let templates = sqlx::query!(
+        "SELECT id, title, days, subtasks FROM team_templates WHERE team_id = $1 ORDER BY id",
+        team_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let result = templates
+        .into_iter()
+        .map(|t| serde_json::json!({
+            "id": t.id,
+            "title": t.title,
+            "days": t.days,
+            "subtasks": t.subtasks
+        }))
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Crée un modèle (admin seulement) et le copie chez chaque membre
+#[utoipa::path(
+    post,
+    path = "/teams/{id}/templates",
+    tag = "teams",
+    params(("id" = i32, Path, description = "Identifiant de l'équipe")),
+    request_body = CreateTemplateRequest,
+    responses(
+        (status = 201, description = "Modèle créé et copié chez les membres", body = serde_json::Value),
+        (status = 400, description = "Données invalides"),
+        (status = 403, description = "Réservé aux membres (ou administrateurs) de l'équipe"),
+    )
+)]
+pub async fn create_template(
+    Path(team_id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<CreateTemplateRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
+    require_admin(&pool, team_id, user_id).await?;
+
+    if payload.days.iter().any(|d| !(1..=7).contains(d)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let subtasks: Vec<String> = payload
+        .subtasks
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| !s.trim().is_empty())
+        .collect();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let template = sqlx::query!(
+        r#"
+        INSERT INTO team_templates (team_id, title, days, subtasks)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+        team_id,
+        payload.title,
+        &payload.days,
+        &subtasks
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let member_ids = sqlx::query_scalar!(
+        "SELECT user_id FROM team_members WHERE team_id = $1",
+        team_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    for member_id in member_ids {
+        copy_template_to_user(&mut tx, template.id, member_id, user_id).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "id": template.id }))))
+}
+
+/// Met à jour un modèle (admin seulement).
+/// Le titre et les jours sont répercutés sur les copies liées des membres ;
+/// les sous-tâches ne concernent que les copies futures, pour ne pas
+/// écraser la progression des membres.
+#[utoipa::path(
+    post,
+    path = "/teams/{id}/templates/{template_id}",
+    tag = "teams",
+    params(
+        ("id" = i32, Path, description = "Identifiant de l'équipe"),
+        ("template_id" = i32, Path, description = "Identifiant du modèle"),
+    ),
+    request_body = UpdateTemplateRequest,
+    responses(
+        (status = 200, description = "Modèle modifié"),
+        (status = 400, description = "Données invalides"),
+        (status = 403, description = "Réservé aux membres (ou administrateurs) de l'équipe"),
+        (status = 404, description = "Modèle introuvable"),
+    )
+)]
+pub async fn update_template(
+    Path((team_id, template_id)): Path<(i32, i32)>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<UpdateTemplateRequest>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&pool, team_id, user_id).await?;
+
+    if let Some(days) = &payload.days
+        && days.iter().any(|d| !(1..=7).contains(d))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let template = sqlx::query!(
+        r#"
+        UPDATE team_templates
+        SET title = COALESCE($1, title),
+            days = COALESCE($2, days),
+            subtasks = COALESCE($3, subtasks)
+        WHERE id = $4 AND team_id = $5
+        RETURNING title, days
+        "#,
+        payload.title,
+        payload.days.as_deref(),
+        payload.subtasks.as_deref(),
+        template_id,
+        team_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::BAD_REQUEST)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    // État des copies liées avant répercussion, pour le journal
+    let linked_ids = sqlx::query_scalar!(
+        "SELECT id FROM tasks WHERE template_id = $1 AND deleted = false",
+        template_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut snapshots = Vec::with_capacity(linked_ids.len());
+    for &task_id in &linked_ids {
+        snapshots.push((task_id, audit::task_snapshot(&mut tx, task_id).await?));
+    }
+
+    // Répercuter le titre sur les copies liées
+    sqlx::query!(
+        "UPDATE tasks SET title = $1 WHERE template_id = $2 AND deleted = false",
+        template.title,
+        template_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Répercuter les jours sur les copies liées
+    if payload.days.is_some() {
+        sqlx::query!(
+            r#"
+            DELETE FROM task_days
+            WHERE task_id IN (SELECT id FROM tasks WHERE template_id = $1 AND deleted = false)
+            "#,
+            template_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO task_days (task_id, day_of_week)
+            SELECT t.id, d.day
+            FROM tasks t, unnest($2::int[]) AS d(day)
+            WHERE t.template_id = $1 AND t.deleted = false
+            "#,
+            template_id,
+            &template.days
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    for (task_id, before) in snapshots {
+        let after = audit::task_snapshot(&mut tx, task_id).await?;
+        audit::record(&mut tx, AuditEntry::task(user_id, task_id, "update").before(before).after(after)).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Supprime un modèle (admin seulement), les copies des membres sont conservées
+#[utoipa::path(
+    delete,
+    path = "/teams/{id}/templates/{template_id}",
+    tag = "teams",
+    params(
+        ("id" = i32, Path, description = "Identifiant de l'équipe"),
+        ("template_id" = i32, Path, description = "Identifiant du modèle"),
+    ),
+    responses(
+        (status = 200, description = "Modèle supprimé"),
+        (status = 403, description = "Réservé aux membres (ou administrateurs) de l'équipe"),
+        (status = 404, description = "Modèle introuvable"),
+    )
+)]
+pub async fn delete_template(
+    Path((team_id, template_id)): Path<(i32, i32)>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    require_admin(&pool, team_id, user_id).await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM team_templates WHERE id = $1 AND team_id = $2",
+        template_id,
+        team_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Tableau de bord de l'équipe : taux de complétion sur 30 jours par membre et par modèle
+#[utoipa::path(
+    get,
+    path = "/teams/{id}/dashboard",
+    tag = "teams",
+    params(("id" = i32, Path, description = "Identifiant de l'équipe")),
+    responses(
+        (status = 200, description = "Taux de complétion par membre et par modèle", body = serde_json::Value),
+        (status = 403, description = "Réservé aux membres (ou administrateurs) de l'équipe"),
+    )
+)]
+pub async fn get_team_dashboard(
+    Path(team_id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    member_role(&pool, team_id, user_id).await?;
+    stats::ensure_recent_team(&pool, team_id, 30).await?;
+
+    // 1. Occurrences prévues et complétées par membre, d'après les bilans quotidiens
+    let members = sqlx::query!(
+        r#"
+        WITH occurrences AS (
+            SELECT t.user_id, s.completed
+            FROM team_members m
+            JOIN tasks t ON t.user_id = m.user_id AND t.deleted = false AND t.active = true
+            -- Seules les routines de l'équipe comptent, pas les tâches personnelles
+            JOIN team_templates tt ON tt.id = t.template_id AND tt.team_id = m.team_id
+            JOIN task_daily_stats s ON s.task_id = t.id
+            WHERE m.team_id = $1
+              AND s.date > current_date - 30
+              AND s.scheduled
+        )
+        SELECT u.id, u.username, m.role,
+               COUNT(o.user_id)::int as "scheduled!",
+               COUNT(o.user_id) FILTER (WHERE o.completed)::int as "completed!"
+        FROM team_members m
+        JOIN users u ON u.id = m.user_id
+        LEFT JOIN occurrences o ON o.user_id = m.user_id
+        WHERE m.team_id = $1
+        GROUP BY u.id, u.username, m.role
+        ORDER BY u.username
+        "#,
+        team_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur tableau de bord membres: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // 2. Même calcul restreint aux copies de chaque modèle d'équipe
+    let templates = sqlx::query!(
+        r#"
+        WITH occurrences AS (
+            SELECT t.template_id, s.completed
+            FROM team_templates tt
+            JOIN tasks t ON t.template_id = tt.id AND t.deleted = false AND t.active = true
+            JOIN team_members m ON m.team_id = tt.team_id AND m.user_id = t.user_id
+            JOIN task_daily_stats s ON s.task_id = t.id
+            WHERE tt.team_id = $1
+              AND s.date > current_date - 30
+              AND s.scheduled
+        )
+        SELECT tt.id, tt.title,
+               COUNT(o.template_id)::int as "scheduled!",
+               COUNT(o.template_id) FILTER (WHERE o.completed)::int as "completed!"
+        FROM team_templates tt
+        LEFT JOIN occurrences o ON o.template_id = tt.id
+        WHERE tt.team_id = $1
+        GROUP BY tt.id, tt.title
+        ORDER BY tt.id
+        "#,
+        team_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur tableau de bord modèles: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let rate = |completed: i32, scheduled: i32| {
+        if scheduled > 0 {
+            (completed as f64 / scheduled as f64 * 100.0).round() as i32
+        } else {
+            0
+        }
+    };
+
+    let total_scheduled: i32 = members.iter().map(|m| m.scheduled).sum();
+    let total_completed: i32 = members.iter().map(|m| m.completed).sum();
+
+    Ok(Json(serde_json::json!({
+        "members": members.iter().map(|m| serde_json::json!({
+            "id": m.id,
+            "username": m.username,
+            "role": m.role,
+            "scheduled": m.scheduled,
+            "completed": m.completed,
+            "success_rate": rate(m.completed, m.scheduled)
+        })).collect::<Vec<_>>(),
+        "templates": templates.iter().map(|t| serde_json::json!({
+            "id": t.id,
+            "title": t.title,
+            "scheduled": t.scheduled,
+            "completed": t.completed,
+            "success_rate": rate(t.completed, t.scheduled)
+        })).collect::<Vec<_>>(),
+        "summary": {
+            "members_count": members.len(),
+            "total_scheduled": total_scheduled,
+            "total_completed": total_completed,
+            "success_rate": rate(total_completed, total_scheduled)
+        }
+    })))
+}
@@ -0,0 +1,394 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand::RngCore;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::CreateTriggerRequest;
+use crate::routes::{apply_log_value, apply_toggle};
+
+/// Actions possibles d'une URL de déclenchement
+const ACTIONS: [&str; 4] = ["toggle", "complete", "uncomplete", "increment"];
+
+/// Appels acceptés par URL et par minute
+const RATE_LIMIT: i32 = 10;
+
+/// Gestion des URL de déclenchement d'une tâche (authentifiée)
+pub fn trigger_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route(
+            "/tasks/:id/triggers",
+            get(get_triggers).post(create_trigger),
+        )
+        .route("/tasks/:id/triggers/:trigger_id", delete(delete_trigger))
+        .with_state(pool)
+}
+
+/// Appel d'une URL de déclenchement : le jeton tient lieu d'authentification.
+/// `GET` est accepté pour les tags NFC et boutons qui ne savent qu'ouvrir une URL.
+pub fn public_trigger_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/:token", get(run_trigger).post(run_trigger))
+        .with_state(pool)
+}
+
+/// Erreur d'un déclenchement : un code HTTP simple, ou la limite de débit atteinte
+/// (429, avec le délai avant la fenêtre suivante)
+pub enum TriggerError {
+    Status(StatusCode),
+    RateLimited { retry_after: i32 },
+}
+
+impl From<StatusCode> for TriggerError {
+    fn from(status: StatusCode) -> Self {
+        TriggerError::Status(status)
+    }
+}
+
+impl IntoResponse for TriggerError {
+    fn into_response(self) -> Response {
+        match self {
+            TriggerError::Status(status) => status.into_response(),
+            TriggerError::RateLimited { retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.max(1).to_string())],
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Jeton aléatoire de l'URL (256 bits, base64url)
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// --- GESTIONNAIRES (HANDLERS) ---
+
+/// Liste les URL de déclenchement d'une tâche (sans leur jeton)
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}/triggers",
+    tag = "triggers",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    responses(
+        (status = 200, description = "URL de déclenchement de la tâche", body = Vec<serde_json::Value>),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn get_triggers(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let owned = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM tasks WHERE id = $1 AND user_id = $2 AND deleted = false) AS "exists!""#,
+        id,
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !owned {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let triggers = sqlx::query!(
+        r#"
+        SELECT id, name, action, amount, created_at, last_used_at, use_count
+        FROM task_triggers
+        WHERE task_id = $1
+        ORDER BY id
+        "#,
+        id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération déclencheurs: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result = triggers
+        .into_iter()
+        .map(|t| {
+            serde_json::json!({
+                "id": t.id,
+                "name": t.name,
+                "action": t.action,
+                "amount": t.amount,
+                "created_at": t.created_at,
+                "last_used_at": t.last_used_at,
+                "use_count": t.use_count
+            })
+        })
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Crée une URL de déclenchement. Le jeton n'est renvoyé qu'ici.
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/triggers",
+    tag = "triggers",
+    params(("id" = i32, Path, description = "Identifiant de la tâche")),
+    request_body = CreateTriggerRequest,
+    responses(
+        (status = 201, description = "URL créée, avec son jeton", body = serde_json::Value),
+        (status = 400, description = "Action ou quantité invalide pour cette tâche"),
+        (status = 404, description = "Tâche introuvable"),
+    )
+)]
+pub async fn create_trigger(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<CreateTriggerRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
+    let action = payload.action.trim();
+    if !ACTIONS.contains(&action) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let amount = match (action, payload.amount) {
+        ("increment", amount) => Some(amount.unwrap_or(1.0)),
+        (_, None) => None,
+        (_, Some(_)) => return Err(StatusCode::BAD_REQUEST),
+    };
+    if amount.is_some_and(|a| !a.is_finite() || a == 0.0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let name = payload
+        .name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+
+    let task = sqlx::query!(
+        "SELECT measurement_type FROM tasks WHERE id = $1 AND user_id = $2 AND deleted = false",
+        id,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Seule une tâche chiffrée a une valeur à incrémenter, et elle ne se complète que par sa valeur
+    if (action == "increment") != (task.measurement_type != "boolean") {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let token = generate_token();
+    let trigger = sqlx::query!(
+        r#"
+        INSERT INTO task_triggers (task_id, token_hash, name, action, amount)
+        VALUES ($1, sha256(convert_to($2, 'UTF8')), $3, $4, $5)
+        RETURNING id, created_at
+        "#,
+        id,
+        token,
+        name,
+        action,
+        amount
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur création déclencheur: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "id": trigger.id,
+            "name": name,
+            "action": action,
+            "amount": amount,
+            "created_at": trigger.created_at,
+            "token": token,
+            "path": format!("/api/v1/triggers/{}", token)
+        })),
+    ))
+}
+
+/// Révoque une URL de déclenchement
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}/triggers/{trigger_id}",
+    tag = "triggers",
+    params(
+        ("id" = i32, Path, description = "Identifiant de la tâche"),
+        ("trigger_id" = i32, Path, description = "Identifiant de l'URL"),
+    ),
+    responses(
+        (status = 204, description = "URL révoquée"),
+        (status = 404, description = "URL introuvable"),
+    )
+)]
+pub async fn delete_trigger(
+    Path((id, trigger_id)): Path<(i32, i32)>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM task_triggers tr
+        USING tasks t
+        WHERE tr.id = $1 AND tr.task_id = $2 AND t.id = tr.task_id AND t.user_id = $3
+        "#,
+        trigger_id,
+        id,
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur suppression déclencheur: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Applique l'action d'une URL de déclenchement à l'occurrence du jour, au nom du
+/// propriétaire de la tâche, et renvoie l'état de l'occurrence
+#[utoipa::path(
+    method(get, post),
+    path = "/triggers/{token}",
+    tag = "triggers",
+    params(("token" = String, Path, description = "Jeton de l'URL")),
+    responses(
+        (status = 200, description = "État de l'occurrence du jour", body = serde_json::Value),
+        (status = 400, description = "Action impossible sur cette tâche"),
+        (status = 404, description = "Jeton inconnu"),
+        (status = 409, description = "Tâche archivée"),
+        (status = 429, description = "Trop d'appels, voir `Retry-After`"),
+    ),
+    security(())
+)]
+pub async fn run_trigger(
+    Path(token): Path<String>,
+    State(pool): State<PgPool>,
+) -> Result<Json<serde_json::Value>, TriggerError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Compte l'appel dans la fenêtre d'une minute en cours ; le verrou sur la ligne
+    // ordonne les appels simultanés d'une même URL
+    let trigger = sqlx::query!(
+        r#"
+        UPDATE task_triggers tr SET
+            window_start = CASE WHEN tr.window_start <= now() - interval '1 minute'
+                                THEN now() ELSE tr.window_start END,
+            window_calls = CASE WHEN tr.window_start <= now() - interval '1 minute'
+                                THEN 1 ELSE tr.window_calls + 1 END
+        FROM tasks t
+        JOIN users u ON u.id = t.user_id
+        WHERE tr.token_hash = sha256(convert_to($1, 'UTF8'))
+          AND t.id = tr.task_id AND t.deleted = false AND u.disabled_at IS NULL
+        RETURNING tr.id, tr.task_id, tr.action, tr.amount, tr.window_calls, t.user_id, t.title,
+                  t.active,
+                  ceil(extract(epoch FROM tr.window_start + interval '1 minute' - now()))::int
+                      AS "retry_after!"
+        "#,
+        token
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur déclencheur: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    if trigger.window_calls > RATE_LIMIT {
+        tx.commit()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Err(TriggerError::RateLimited {
+            retry_after: trigger.retry_after,
+        });
+    }
+
+    // Une tâche archivée n'a plus d'occurrences : l'URL reste valide mais ne fait rien
+    if !trigger.active {
+        return Err(StatusCode::CONFLICT.into());
+    }
+
+    let completed = sqlx::query_scalar!(
+        "SELECT completed FROM task_completions WHERE task_id = $1 AND date = current_date",
+        trigger.task_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .unwrap_or(false);
+
+    match (trigger.action.as_str(), trigger.amount) {
+        ("increment", Some(amount)) => {
+            apply_log_value(
+                &mut tx,
+                trigger.user_id,
+                trigger.task_id,
+                None,
+                amount,
+                true,
+                "trigger",
+            )
+            .await?;
+        }
+        // Complète ou décoche seulement si l'état diffère : rappeler l'URL ne change rien
+        ("complete", _) if completed => {}
+        ("uncomplete", _) if !completed => {}
+        _ => apply_toggle(&mut tx, trigger.user_id, trigger.task_id, None, "trigger").await?,
+    }
+
+    sqlx::query!(
+        "UPDATE task_triggers SET last_used_at = now(), use_count = use_count + 1 WHERE id = $1",
+        trigger.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let occurrence = sqlx::query!(
+        r#"
+        SELECT current_date AS "date!", tc.completed AS "completed?", tc.value
+        FROM (SELECT 1) AS one
+        LEFT JOIN task_completions tc ON tc.task_id = $1 AND tc.date = current_date
+        "#,
+        trigger.task_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({
+        "task_id": trigger.task_id,
+        "title": trigger.title,
+        "action": trigger.action,
+        "date": occurrence.date,
+        "completed": occurrence.completed.unwrap_or(false),
+        "value": occurrence.value
+    })))
+}
@@ -0,0 +1,180 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::post,
+};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::audit::{self, AuditEntry};
+use crate::undo::{self, undo_window_minutes};
+
+pub fn undo_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/undo/:token", post(undo_change))
+        .with_state(pool)
+}
+
+/// Annule la modification associée à un jeton, tant que la fenêtre d'annulation est ouverte.
+/// Refuse (409) si l'élément a été modifié depuis, pour ne pas écraser un changement plus récent.
+#[utoipa::path(
+    post,
+    path = "/undo/{token}",
+    tag = "undo",
+    params(("token" = Uuid, Path, description = "Jeton d'annulation")),
+    responses(
+        (status = 200, description = "Modification annulée", body = serde_json::Value),
+        (status = 404, description = "Jeton inconnu"),
+        (status = 409, description = "Élément modifié depuis"),
+        (status = 410, description = "Fenêtre d'annulation expirée"),
+    )
+)]
+pub async fn undo_change(
+    Path(token): Path<Uuid>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let undo_token = sqlx::query!(
+        r#"
+        SELECT tx_id, used_at IS NULL AND created_at > now() - make_interval(mins => $3) as "usable!"
+        FROM undo_tokens
+        WHERE token = $1 AND user_id = $2
+        FOR UPDATE
+        "#,
+        token,
+        user_id,
+        undo_window_minutes()
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération jeton d'annulation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Jeton déjà utilisé ou expiré
+    if !undo_token.usable {
+        return Err(StatusCode::GONE);
+    }
+
+    // Les modifications sont défaites de la plus récente à la plus ancienne
+    let entries = sqlx::query!(
+        r#"
+        SELECT task_id, entity, entity_id, before, after
+        FROM audit_log
+        WHERE tx_id = $1 AND owner_id = $2
+        ORDER BY id DESC
+        "#,
+        undo_token.tx_id,
+        user_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération modifications: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    for entry in &entries {
+        match entry.entity.as_str() {
+            "task" => {
+                let current = audit::task_snapshot(&mut tx, entry.task_id).await?;
+                let (Some(current), Some(before), Some(after)) = (current, &entry.before, &entry.after)
+                else {
+                    return Err(StatusCode::CONFLICT);
+                };
+                if !undo::matches(&current, after) {
+                    return Err(StatusCode::CONFLICT);
+                }
+
+                undo::restore_task(&mut tx, entry.task_id, before).await?;
+
+                let restored = audit::task_snapshot(&mut tx, entry.task_id).await?;
+                audit::record(
+                    &mut tx,
+                    AuditEntry::task(user_id, entry.task_id, "undo")
+                        .before(Some(current))
+                        .after(restored),
+                )
+                .await?;
+            }
+            "completion" => {
+                let date = entry
+                    .entity_id
+                    .split_once('@')
+                    .and_then(|(_, date)| date.parse::<NaiveDate>().ok())
+                    .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+                let current = audit::completion_snapshot(&mut tx, entry.task_id, date).await?;
+                let unchanged = match (&current, &entry.after) {
+                    (Some(current), Some(after)) => undo::matches(current, after),
+                    (None, None) => true,
+                    _ => false,
+                };
+                if !unchanged {
+                    return Err(StatusCode::CONFLICT);
+                }
+
+                undo::restore_completion(&mut tx, entry.task_id, date, entry.before.as_ref()).await?;
+
+                let restored = audit::completion_snapshot(&mut tx, entry.task_id, date).await?;
+                audit::record(
+                    &mut tx,
+                    AuditEntry::completion(user_id, entry.task_id, date, "undo")
+                        .before(current)
+                        .after(restored),
+                )
+                .await?;
+            }
+            "subtask" => {
+                let subtask_id = entry
+                    .entity_id
+                    .parse::<i32>()
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+                // Seules les bascules de sous-tâches d'une tâche sont annulables : la
+                // sous-tâche existait avant et existe toujours, dans l'état laissé
+                let current = audit::subtask_snapshot(&mut tx, subtask_id).await?;
+                let (Some(current), Some(before), Some(after)) = (current, &entry.before, &entry.after)
+                else {
+                    return Err(StatusCode::CONFLICT);
+                };
+                if !undo::matches(&current, after) {
+                    return Err(StatusCode::CONFLICT);
+                }
+
+                undo::restore_subtask(&mut tx, subtask_id, before).await?;
+
+                let restored = audit::subtask_snapshot(&mut tx, subtask_id).await?;
+                audit::record(
+                    &mut tx,
+                    AuditEntry::subtask(user_id, entry.task_id, subtask_id, "undo")
+                        .before(Some(current))
+                        .after(restored),
+                )
+                .await?;
+            }
+            // Seules les modifications de tâches, de sous-tâches et de complétions émettent des jetons
+            _ => return Err(StatusCode::CONFLICT),
+        }
+    }
+
+    sqlx::query!("UPDATE undo_tokens SET used_at = now() WHERE token = $1", token)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({ "undone": entries.len() })))
+}
@@ -0,0 +1,170 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get},
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::CreateVacationRequest;
+
+pub fn vacation_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/vacations", get(get_vacations).post(create_vacation))
+        .route("/vacations/:id", delete(delete_vacation))
+        .with_state(pool)
+}
+
+// --- GESTIONNAIRES (HANDLERS) ---
+
+/// Liste les périodes de vacances de l'utilisateur
+#[utoipa::path(
+    get,
+    path = "/vacations",
+    tag = "vacations",
+    responses(
+        (status = 200, description = "Périodes de vacances", body = Vec<serde_json::Value>),
+    )
+)]
+pub async fn get_vacations(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let vacations = sqlx::query!(
+        r#"
+        SELECT v.id, v.start_date, v.end_date, v.reason, v.all_tasks,
+               COALESCE(array_agg(vt.task_id) FILTER (WHERE vt.task_id IS NOT NULL), '{}') as "task_ids!"
+        FROM vacations v
+        LEFT JOIN vacation_tasks vt ON vt.vacation_id = v.id
+        WHERE v.user_id = $1
+        GROUP BY v.id
+        ORDER BY v.start_date DESC
+        "#,
+        user_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération vacances: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result = vacations
+        .into_iter()
+        .map(|v| serde_json::json!({
+            "id": v.id,
+            "start_date": v.start_date,
+            "end_date": v.end_date,
+            "reason": v.reason,
+            "all_tasks": v.all_tasks,
+            "task_ids": v.task_ids
+        }))
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Met en pause toutes les tâches (ou une sélection) entre deux dates
+#[utoipa::path(
+    post,
+    path = "/vacations",
+    tag = "vacations",
+    request_body = CreateVacationRequest,
+    responses(
+        (status = 201, description = "Période créée", body = serde_json::Value),
+        (status = 400, description = "Données invalides"),
+    )
+)]
+pub async fn create_vacation(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<CreateVacationRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
+    if payload.end_date < payload.start_date {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let vacation = sqlx::query!(
+        r#"
+        INSERT INTO vacations (user_id, start_date, end_date, reason, all_tasks)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+        user_id,
+        payload.start_date,
+        payload.end_date,
+        payload.reason,
+        payload.task_ids.is_none()
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur création vacances: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if let Some(task_ids) = &payload.task_ids {
+        // Seules les tâches de l'utilisateur peuvent être mises en pause
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO vacation_tasks (vacation_id, task_id)
+            SELECT $1, id FROM tasks WHERE id = ANY($2) AND user_id = $3
+            "#,
+            vacation.id,
+            task_ids,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        // Une sélection sans tâche valide ne mettrait rien en pause
+        if inserted.rows_affected() == 0 {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "id": vacation.id }))))
+}
+
+/// Supprime une période de vacances
+#[utoipa::path(
+    delete,
+    path = "/vacations/{id}",
+    tag = "vacations",
+    params(("id" = i32, Path, description = "Identifiant de la période")),
+    responses(
+        (status = 200, description = "Période supprimée"),
+        (status = 404, description = "Période introuvable"),
+    )
+)]
+pub async fn delete_vacation(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let result = sqlx::query!(
+        "DELETE FROM vacations WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,364 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{CreateWebhookRequest, UpdateWebhookRequest};
+use crate::webhooks::{self, EVENTS};
+
+pub fn webhook_routes(pool: PgPool) -> Router {
+    Router::new()
+        .route("/webhooks", get(get_webhooks).post(create_webhook))
+        .route("/webhooks/:id", post(update_webhook).delete(delete_webhook))
+        .route("/webhooks/:id/deliveries", get(get_deliveries))
+        .route(
+            "/webhooks/:id/deliveries/:delivery_id/redeliver",
+            post(redeliver),
+        )
+        .with_state(pool)
+}
+
+/// Filtre d'événements trié et sans doublon, `Err` si un événement est inconnu
+fn parse_events(events: Vec<String>) -> Result<Vec<String>, StatusCode> {
+    let mut events: Vec<String> = events.into_iter().map(|e| e.trim().to_string()).collect();
+    if events.iter().any(|e| !EVENTS.contains(&e.as_str())) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    events.sort();
+    events.dedup();
+    Ok(events)
+}
+
+// --- GESTIONNAIRES (HANDLERS) ---
+
+/// Liste les webhooks de l'utilisateur (sans leur secret)
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Webhooks de l'utilisateur", body = Vec<serde_json::Value>),
+    )
+)]
+pub async fn get_webhooks(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let webhooks = sqlx::query!(
+        r#"
+        SELECT w.id, w.url, w.events, w.active, w.created_at,
+               COUNT(d.id) FILTER (WHERE d.status = 'pending') AS "pending!",
+               COUNT(d.id) FILTER (WHERE d.status = 'failed') AS "failed!"
+        FROM webhooks w
+        LEFT JOIN webhook_deliveries d ON d.webhook_id = w.id
+        WHERE w.user_id = $1
+        GROUP BY w.id
+        ORDER BY w.id
+        "#,
+        user_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération webhooks: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result = webhooks
+        .into_iter()
+        .map(|w| {
+            serde_json::json!({
+                "id": w.id,
+                "url": w.url,
+                "events": w.events,
+                "active": w.active,
+                "created_at": w.created_at,
+                "deliveries": { "pending": w.pending, "failed": w.failed }
+            })
+        })
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Crée un webhook. Le secret n'est renvoyé qu'ici.
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook créé, avec son secret", body = serde_json::Value),
+        (status = 400, description = "URL invalide ou locale, ou événement inconnu"),
+    )
+)]
+pub async fn create_webhook(
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
+    let url = payload.url.trim();
+    if !webhooks::is_allowed_url(url) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let events = parse_events(payload.events.unwrap_or_default())?;
+    let secret = match payload.secret.map(|s| s.trim().to_string()) {
+        Some(secret) if secret.is_empty() => return Err(StatusCode::BAD_REQUEST),
+        Some(secret) => secret,
+        None => webhooks::generate_secret(),
+    };
+
+    let webhook = sqlx::query!(
+        r#"
+        INSERT INTO webhooks (user_id, url, secret, events)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, active, created_at
+        "#,
+        user_id,
+        url,
+        secret,
+        &events
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur création webhook: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "id": webhook.id,
+            "url": url,
+            "events": events,
+            "active": webhook.active,
+            "created_at": webhook.created_at,
+            "secret": secret
+        })),
+    ))
+}
+
+/// Modifie l'URL, le secret, le filtre d'événements ou l'activation d'un webhook
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = i32, Path, description = "Identifiant du webhook")),
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook modifié", body = serde_json::Value),
+        (status = 400, description = "URL invalide ou locale, ou événement inconnu"),
+        (status = 404, description = "Webhook introuvable"),
+    )
+)]
+pub async fn update_webhook(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+    Json(payload): Json<UpdateWebhookRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let url = payload.url.map(|u| u.trim().to_string());
+    if url.as_deref().is_some_and(|u| !webhooks::is_allowed_url(u)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let secret = payload.secret.map(|s| s.trim().to_string());
+    if secret.as_deref().is_some_and(str::is_empty) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let events = payload.events.map(parse_events).transpose()?;
+
+    let webhook = sqlx::query!(
+        r#"
+        UPDATE webhooks SET
+            url = COALESCE($3, url),
+            secret = COALESCE($4, secret),
+            events = COALESCE($5, events),
+            active = COALESCE($6, active)
+        WHERE id = $1 AND user_id = $2
+        RETURNING id, url, events, active, created_at
+        "#,
+        id,
+        user_id,
+        url,
+        secret,
+        events.as_deref(),
+        payload.active
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur modification webhook: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(serde_json::json!({
+        "id": webhook.id,
+        "url": webhook.url,
+        "events": webhook.events,
+        "active": webhook.active,
+        "created_at": webhook.created_at
+    })))
+}
+
+/// Supprime un webhook et son historique d'envois
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = i32, Path, description = "Identifiant du webhook")),
+    responses(
+        (status = 204, description = "Webhook supprimé"),
+        (status = 404, description = "Webhook introuvable"),
+    )
+)]
+pub async fn delete_webhook(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    let result = sqlx::query!(
+        "DELETE FROM webhooks WHERE id = $1 AND user_id = $2",
+        id,
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur suppression webhook: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Journal des 50 derniers envois d'un webhook, avec chaque essai
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = i32, Path, description = "Identifiant du webhook")),
+    responses(
+        (status = 200, description = "Dernières livraisons", body = Vec<serde_json::Value>),
+        (status = 404, description = "Webhook introuvable"),
+    )
+)]
+pub async fn get_deliveries(
+    Path(id): Path<i32>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<Json<Vec<serde_json::Value>>, StatusCode> {
+    let owned = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM webhooks WHERE id = $1 AND user_id = $2) AS "exists!""#,
+        id,
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !owned {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let deliveries = sqlx::query!(
+        r#"
+        SELECT d.id, d.event, d.payload, d.status, d.attempts, d.next_attempt_at,
+               d.created_at, d.delivered_at,
+               COALESCE((
+                   SELECT json_agg(json_build_object(
+                       'attempted_at', a.attempted_at,
+                       'status_code', a.status_code,
+                       'error', a.error,
+                       'duration_ms', a.duration_ms
+                   ) ORDER BY a.id)
+                   FROM webhook_delivery_attempts a WHERE a.delivery_id = d.id
+               ), '[]') AS "attempt_log!: serde_json::Value"
+        FROM webhook_deliveries d
+        WHERE d.webhook_id = $1
+        ORDER BY d.id DESC
+        LIMIT 50
+        "#,
+        id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur récupération envois webhook: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result = deliveries
+        .into_iter()
+        .map(|d| {
+            serde_json::json!({
+                "id": d.id,
+                "event": d.event,
+                "status": d.status,
+                "attempts": d.attempts,
+                // Prochain essai, tant que l'envoi est en attente
+                "next_attempt_at": (d.status == "pending").then_some(d.next_attempt_at),
+                "created_at": d.created_at,
+                "delivered_at": d.delivered_at,
+                "payload": d.payload,
+                "attempt_log": d.attempt_log
+            })
+        })
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Remet un envoi en file pour un envoi immédiat, avec un nouveau jeu d'essais
+/// (le journal des essais précédents est conservé)
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}/deliveries/{delivery_id}/redeliver",
+    tag = "webhooks",
+    params(
+        ("id" = i32, Path, description = "Identifiant du webhook"),
+        ("delivery_id" = i64, Path, description = "Identifiant de la livraison"),
+    ),
+    responses(
+        (status = 202, description = "Nouvelle livraison planifiée", body = serde_json::Value),
+        (status = 404, description = "Livraison introuvable"),
+    )
+)]
+pub async fn redeliver(
+    Path((id, delivery_id)): Path<(i32, i64)>,
+    State(pool): State<PgPool>,
+    Extension(user_id): Extension<Uuid>,
+) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
+    let delivery = sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries d
+        SET status = 'pending', attempts = 0, next_attempt_at = now(), delivered_at = NULL
+        FROM webhooks w
+        WHERE d.id = $1 AND d.webhook_id = $2 AND w.id = d.webhook_id AND w.user_id = $3
+        RETURNING d.id, d.status
+        "#,
+        delivery_id,
+        id,
+        user_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur renvoi webhook: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({ "id": delivery.id, "status": delivery.status })),
+    ))
+}
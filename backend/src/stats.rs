@@ -0,0 +1,100 @@
+use axum::http::StatusCode;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Calcule les bilans quotidiens manquants de l'utilisateur sur les `days` derniers jours,
+/// avant que les statistiques ne les lisent
+pub async fn ensure_recent(pool: &PgPool, user_id: Uuid, days: i32) -> Result<(), StatusCode> {
+    sqlx::query!(
+        "SELECT ensure_daily_stats($1, current_date - ($2::int - 1), current_date)",
+        user_id,
+        days
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur calcul bilans quotidiens: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
+
+/// Même chose pour chaque membre d'une équipe
+pub async fn ensure_recent_team(pool: &PgPool, team_id: i32, days: i32) -> Result<(), StatusCode> {
+    sqlx::query!(
+        r#"
+        SELECT ensure_daily_stats(m.user_id, current_date - ($2::int - 1), current_date)
+        FROM team_members m
+        WHERE m.team_id = $1
+        "#,
+        team_id,
+        days
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur calcul bilans quotidiens de l'équipe: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
+
+/// Jours recalculés par le passage périodique, aujourd'hui compris
+pub const REBUILD_DAYS: i32 = 7;
+
+/// Recalcule les `days` derniers jours de tous les utilisateurs depuis les données brutes :
+/// rattrape le changement de jour, les journées manquées (serveur arrêté) et tout écart
+/// des bilans tenus par les déclencheurs. Les jours passés gardent l'état d'archivage
+/// et les sous-tâches enregistrés ce jour-là.
+pub async fn rebuild_recent(pool: &PgPool, days: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        "SELECT refresh_daily_stats(id, NULL, current_date - ($1::int - 1), current_date) FROM users",
+        days
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Reconstruit l'historique des bilans depuis les données brutes, pour un utilisateur ou tous.
+/// Sans date de début, l'historique part de la création de la première tâche.
+/// Les jours passés sont jugés avec le planning de l'époque ; ceux déjà calculés gardent
+/// l'état d'archivage et les sous-tâches enregistrés, les autres prennent l'état actuel.
+pub async fn backfill(
+    pool: &PgPool,
+    user_id: Option<Uuid>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<u64, sqlx::Error> {
+    let users = sqlx::query_scalar!(
+        "SELECT id FROM users WHERE $1::uuid IS NULL OR id = $1 ORDER BY username",
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Un utilisateur par transaction, pour ne pas tout verrouiller d'un coup
+    for id in &users {
+        sqlx::query!(
+            r#"
+            SELECT refresh_daily_stats(
+                $1,
+                NULL,
+                COALESCE($2, stats_start_date($1), current_date),
+                COALESCE($3, current_date)
+            )
+            "#,
+            id,
+            from,
+            to
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(users.len() as u64)
+}
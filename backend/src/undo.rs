@@ -0,0 +1,252 @@
+use axum::http::StatusCode;
+use chrono::NaiveDate;
+use sqlx::{PgConnection, types::JsonValue};
+use std::env;
+use uuid::Uuid;
+
+/// Durée pendant laquelle une modification peut être annulée (10 minutes par défaut)
+pub fn undo_window_minutes() -> i32 {
+    env::var("UNDO_WINDOW_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|minutes: &i32| *minutes > 0)
+        .unwrap_or(10)
+}
+
+/// Émet un jeton d'annulation couvrant toutes les entrées du journal écrites
+/// par la transaction en cours (None si elle n'a rien modifié)
+pub async fn issue(conn: &mut PgConnection, user_id: Uuid) -> Result<Option<Uuid>, StatusCode> {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO undo_tokens (user_id, tx_id)
+        SELECT $1, txid_current()
+        WHERE EXISTS(SELECT 1 FROM audit_log WHERE tx_id = txid_current())
+        RETURNING token
+        "#,
+        user_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur émission jeton d'annulation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Vrai si `current` contient toutes les valeurs de `expected`
+/// (les instantanés partiels, comme `{"active": false}`, ne comparent que leurs clés)
+pub fn matches(current: &JsonValue, expected: &JsonValue) -> bool {
+    match (current, expected) {
+        (JsonValue::Object(current), JsonValue::Object(expected)) => expected
+            .iter()
+            .all(|(key, value)| current.get(key).is_some_and(|c| matches(c, value))),
+        _ => current == expected,
+    }
+}
+
+/// Remet une tâche dans l'état décrit par un instantané du journal (complet ou partiel).
+/// Les sous-tâches retrouvent leurs identifiants et leur état de complétion.
+pub async fn restore_task(
+    conn: &mut PgConnection,
+    task_id: i32,
+    snapshot: &JsonValue,
+) -> Result<(), StatusCode> {
+    let err = |e: sqlx::Error| {
+        eprintln!("Erreur restauration tâche: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE tasks SET
+            title = COALESCE($2::jsonb->>'title', title),
+            active = COALESCE(($2->>'active')::boolean, active),
+            deleted = COALESCE(($2->>'deleted')::boolean, deleted),
+            deleted_at = CASE
+                WHEN NOT $2 ? 'deleted' THEN deleted_at
+                WHEN ($2->>'deleted')::boolean THEN COALESCE(deleted_at, now())
+            END,
+            measurement_type = COALESCE($2->>'measurement_type', measurement_type),
+            target = CASE WHEN $2 ? 'measurement_type' THEN ($2->>'target')::float8 ELSE target END,
+            unit = CASE WHEN $2 ? 'measurement_type' THEN $2->>'unit' ELSE unit END
+        WHERE id = $1
+        "#,
+        task_id,
+        snapshot
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(err)?;
+
+    if let Some(days) = snapshot.get("days") {
+        sqlx::query!("DELETE FROM task_days WHERE task_id = $1", task_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(err)?;
+        sqlx::query!(
+            r#"
+            INSERT INTO task_days (task_id, day_of_week)
+            SELECT $1, d::int FROM jsonb_array_elements_text($2::jsonb) AS d
+            "#,
+            task_id,
+            days
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(err)?;
+    }
+
+    if let Some(tags) = snapshot.get("tags") {
+        sqlx::query!("DELETE FROM task_tags WHERE task_id = $1", task_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(err)?;
+        // Les étiquettes supprimées entre-temps ne sont pas recréées
+        sqlx::query!(
+            r#"
+            INSERT INTO task_tags (task_id, tag_id)
+            SELECT $1, g.id FROM tags g
+            WHERE g.id IN (SELECT t::int FROM jsonb_array_elements_text($2::jsonb) AS t)
+              AND g.user_id = (SELECT user_id FROM tasks WHERE id = $1)
+            "#,
+            task_id,
+            tags
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(err)?;
+    }
+
+    if let Some(subtasks) = snapshot.get("subtasks") {
+        // Les sous-tâches restées en place sont modifiées (leur version avance si leur état
+        // change), les autres supprimées ou recréées : aucune version déjà vue ne revient
+        // avec un autre contenu, ce qui tromperait `If-Match`
+        sqlx::query!(
+            r#"
+            DELETE FROM subtasks
+            WHERE task_id = $1
+              AND id NOT IN (SELECT (s->>'id')::int FROM jsonb_array_elements($2::jsonb) AS s)
+            "#,
+            task_id,
+            subtasks
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(err)?;
+        sqlx::query!(
+            r#"
+            UPDATE subtasks st
+            SET title = s.title, completed = s.completed, priority = s.priority
+            FROM jsonb_to_recordset($2::jsonb) AS s(id int, title text, completed boolean, priority int)
+            WHERE st.id = s.id AND st.task_id = $1
+            "#,
+            task_id,
+            subtasks
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(err)?;
+        // Chaque modification d'une sous-tâche avance aussi la version de sa tâche : celle-ci
+        // dépasse toutes les versions qu'a pu avoir une sous-tâche supprimée depuis
+        sqlx::query!(
+            r#"
+            INSERT INTO subtasks (id, task_id, title, completed, priority, version)
+            SELECT s.id, $1, s.title, s.completed, s.priority,
+                   (SELECT version FROM tasks WHERE id = $1)
+            FROM jsonb_to_recordset($2::jsonb) AS s(id int, title text, completed boolean, priority int)
+            WHERE NOT EXISTS (SELECT 1 FROM subtasks st WHERE st.id = s.id)
+            "#,
+            task_id,
+            subtasks
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(err)?;
+        sqlx::query!(
+            "UPDATE tasks SET has_subtasks = jsonb_array_length($2::jsonb) > 0 WHERE id = $1",
+            task_id,
+            subtasks
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(err)?;
+    }
+
+    Ok(())
+}
+
+/// Remet la complétion d'une tâche à une date dans l'état d'un instantané du journal
+/// (None : l'occurrence n'existait pas)
+pub async fn restore_completion(
+    conn: &mut PgConnection,
+    task_id: i32,
+    date: NaiveDate,
+    snapshot: Option<&JsonValue>,
+) -> Result<(), StatusCode> {
+    let result = match snapshot {
+        None => {
+            sqlx::query!(
+                "DELETE FROM task_completions WHERE task_id = $1 AND date = $2",
+                task_id,
+                date
+            )
+            .execute(conn)
+            .await
+        }
+        Some(snapshot) => {
+            sqlx::query!(
+                r#"
+                INSERT INTO task_completions
+                SELECT * FROM jsonb_populate_record(
+                    NULL::task_completions,
+                    $2::jsonb || jsonb_build_object('task_id', $1::int)
+                )
+                ON CONFLICT (task_id, date) DO UPDATE SET
+                    completed = EXCLUDED.completed,
+                    priority = EXCLUDED.priority,
+                    value = EXCLUDED.value,
+                    note = EXCLUDED.note,
+                    rating = EXCLUDED.rating,
+                    skipped = EXCLUDED.skipped,
+                    skip_reason = EXCLUDED.skip_reason
+                "#,
+                task_id,
+                snapshot
+            )
+            .execute(conn)
+            .await
+        }
+    };
+
+    result.map(|_| ()).map_err(|e| {
+        eprintln!("Erreur restauration complétion: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Remet une sous-tâche dans l'état décrit par un instantané du journal
+pub async fn restore_subtask(
+    conn: &mut PgConnection,
+    subtask_id: i32,
+    snapshot: &JsonValue,
+) -> Result<(), StatusCode> {
+    sqlx::query!(
+        r#"
+        UPDATE subtasks SET
+            title = COALESCE($2::jsonb->>'title', title),
+            completed = COALESCE(($2->>'completed')::boolean, completed),
+            priority = COALESCE(($2->>'priority')::int, priority)
+        WHERE id = $1
+        "#,
+        subtask_id,
+        snapshot
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        eprintln!("Erreur restauration sous-tâche: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
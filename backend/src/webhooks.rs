@@ -0,0 +1,357 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use sqlx::PgPool;
+use sqlx::types::JsonValue;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Événements auxquels un webhook peut s'abonner
+pub const EVENTS: [&str; 5] = [
+    "task.created",
+    "task.updated",
+    "task.deleted",
+    "completion.toggled",
+    "daily.summary",
+];
+
+/// Nombre d'essais avant d'abandonner un envoi
+pub const MAX_ATTEMPTS: i32 = 8;
+
+/// Envois traités par passage du worker
+const BATCH_SIZE: i64 = 20;
+
+/// Attente avant le prochain essai : 30 s, doublée à chaque échec (environ 2 h au total)
+pub fn backoff(attempts: i32) -> Duration {
+    Duration::from_secs(30 << (attempts.clamp(1, MAX_ATTEMPTS) - 1))
+}
+
+/// Secret aléatoire proposé quand l'utilisateur n'en fournit pas
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Signature `sha256=<hex>` de `<timestamp>.<corps>`, envoyée dans `X-Webhook-Signature`.
+/// Le destinataire la recalcule avec le secret et rejette les horodatages trop anciens.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepte toute clé");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", to_hex(&mac.finalize().into_bytes()))
+}
+
+/// Met en file le bilan de la veille pour les webhooks abonnés à `daily.summary`
+/// (un par jour, à partir du lendemain de leur création)
+pub async fn enqueue_daily_summaries(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let pending = sqlx::query!(
+        r#"
+        SELECT DISTINCT w.user_id
+        FROM webhooks w
+        JOIN users u ON u.id = w.user_id
+        WHERE w.active AND u.disabled_at IS NULL
+          AND (cardinality(w.events) = 0 OR 'daily.summary' = ANY(w.events))
+          AND w.created_at::date < current_date
+          AND NOT EXISTS (
+              SELECT 1 FROM webhook_deliveries d
+              WHERE d.webhook_id = w.id AND d.event = 'daily.summary'
+                AND d.summary_date = current_date - 1
+          )
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    // Bilans calculés avant la lecture : une même requête ne verrait pas les lignes ajoutées
+    let user_ids: Vec<_> = pending.into_iter().map(|r| r.user_id).collect();
+    sqlx::query!(
+        "SELECT ensure_daily_stats(id, current_date - 1, current_date - 1) FROM unnest($1::uuid[]) AS id",
+        &user_ids
+    )
+    .execute(pool)
+    .await?;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO webhook_deliveries (webhook_id, event, payload, summary_date)
+        SELECT w.id, 'daily.summary', jsonb_build_object(
+                   'event', 'daily.summary',
+                   'occurred_at', now(),
+                   'date', s.date,
+                   'scheduled', s.scheduled_tasks,
+                   'done', s.done_tasks,
+                   'completed', s.completed_tasks,
+                   'success_rate', CASE WHEN s.scheduled_tasks > 0
+                       THEN round(s.done_tasks * 100.0 / s.scheduled_tasks) ELSE 0 END
+               ),
+               s.date
+        FROM webhooks w
+        JOIN user_daily_stats s ON s.user_id = w.user_id AND s.date = current_date - 1
+        WHERE w.user_id = ANY($1)
+          AND w.active
+          AND (cardinality(w.events) = 0 OR 'daily.summary' = ANY(w.events))
+          AND w.created_at::date < current_date
+        ON CONFLICT (webhook_id, summary_date) WHERE event = 'daily.summary' DO NOTHING
+        "#,
+        &user_ids
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Adresse qu'un webhook peut viser : ni boucle locale, ni réseau privé, ni lien local
+/// (dont le service de métadonnées 169.254.169.254), ni adresse non routable
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // Plage partagée des opérateurs (100.64.0.0/10)
+                || (a == 100 && b & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Adresses locales uniques (fc00::/7) et de lien (fe80::/10)
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// URL http(s) qui ne désigne pas explicitement la machine ou le réseau local.
+/// Un nom d'hôte est vérifié à nouveau à chaque envoi, une fois résolu.
+pub fn is_allowed_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => is_public_ip(ip),
+        Err(_) => {
+            let host = host.trim_end_matches('.');
+            host != "localhost" && !host.ends_with(".localhost")
+        }
+    }
+}
+
+/// Résolution DNS des envois, qui écarte les adresses non publiques : un nom qui pointe
+/// (ou se met à pointer) vers le réseau local n'est jamais contacté
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} : aucune adresse publique", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Client HTTP des envois vers des URL choisies par les utilisateurs (webhooks,
+/// notifications). Les redirections ne sont pas suivies : elles pourraient mener
+/// vers une adresse interne.
+pub struct Sender {
+    client: reqwest::Client,
+    private_targets: bool,
+}
+
+impl Sender {
+    /// Envois vers des adresses publiques uniquement
+    pub fn public() -> Self {
+        Self::build(false)
+    }
+
+    /// Envois autorisés vers la machine et le réseau local (serveurs de test)
+    pub fn local() -> Self {
+        Self::build(true)
+    }
+
+    fn build(private_targets: bool) -> Self {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none());
+        if !private_targets {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        Self {
+            client: builder.build().expect("Client HTTP des envois"),
+            private_targets,
+        }
+    }
+
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Les adresses IP écrites dans l'URL ne passent pas par la résolution DNS :
+    /// elles sont vérifiées ici avant chaque envoi
+    pub fn can_reach(&self, url: &str) -> bool {
+        self.private_targets || is_allowed_url(url)
+    }
+}
+
+/// Envoi réservé par ce worker
+struct Delivery {
+    id: i64,
+    event: String,
+    payload: JsonValue,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+/// Réserve les envois dus, compte l'essai et les met de côté le temps de l'envoi :
+/// une autre instance ne les reprend pas, un arrêt en cours d'envoi les relance plus tard
+async fn claim_due(pool: &PgPool) -> Result<Vec<Delivery>, sqlx::Error> {
+    sqlx::query_as!(
+        Delivery,
+        r#"
+        UPDATE webhook_deliveries d
+        SET attempts = d.attempts + 1,
+            next_attempt_at = now() + interval '5 minutes'
+        FROM (
+            SELECT d.id
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.id = d.webhook_id
+            WHERE d.status = 'pending' AND d.next_attempt_at <= now() AND w.active
+            ORDER BY d.next_attempt_at
+            LIMIT $1
+            FOR UPDATE OF d SKIP LOCKED
+        ) due, webhooks w
+        WHERE d.id = due.id AND w.id = d.webhook_id
+        RETURNING d.id, d.event, d.payload, d.attempts, w.url, w.secret
+        "#,
+        BATCH_SIZE
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Envoie la requête signée, renvoie le code HTTP ou l'erreur
+async fn post(sender: &Sender, delivery: &Delivery) -> (Option<u16>, Option<String>) {
+    if !sender.can_reach(&delivery.url) {
+        return (None, Some("adresse non publique".to_string()));
+    }
+    let body = delivery.payload.to_string().into_bytes();
+    let timestamp = Utc::now().timestamp();
+
+    let result = sender
+        .client
+        .post(&delivery.url)
+        .header("Content-Type", "application/json")
+        .header("User-Agent", "task-manager-webhooks")
+        .header("X-Webhook-Event", &delivery.event)
+        .header("X-Webhook-Delivery", delivery.id)
+        .header("X-Webhook-Timestamp", timestamp)
+        .header(
+            "X-Webhook-Signature",
+            sign(&delivery.secret, timestamp, &body),
+        )
+        .body(body)
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => (
+            Some(response.status().as_u16()),
+            Some(format!("HTTP {}", response.status())),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Envoie un webhook et enregistre l'essai : réussi, à refaire plus tard ou abandonné
+async fn deliver(
+    pool: &PgPool,
+    sender: &Sender,
+    delivery: &Delivery,
+) -> Result<(), sqlx::Error> {
+    let started = Instant::now();
+    let (status_code, error) = post(sender, delivery).await;
+    let duration_ms = started.elapsed().as_millis() as i32;
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO webhook_delivery_attempts (delivery_id, status_code, error, duration_ms)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        delivery.id,
+        status_code.map(i32::from),
+        error,
+        duration_ms
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let retry_in = backoff(delivery.attempts).as_secs_f64();
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = CASE WHEN $2 THEN 'delivered' WHEN attempts >= $3 THEN 'failed' ELSE 'pending' END,
+            delivered_at = CASE WHEN $2 THEN now() END,
+            next_attempt_at = now() + make_interval(secs => $4)
+        WHERE id = $1
+        "#,
+        delivery.id,
+        error.is_none(),
+        MAX_ATTEMPTS,
+        retry_in
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+/// Met en file les bilans quotidiens puis envoie les webhooks dus, renvoie le nombre d'essais
+pub async fn send_due(pool: &PgPool, sender: &Sender) -> Result<usize, sqlx::Error> {
+    enqueue_daily_summaries(pool).await?;
+
+    let due = claim_due(pool).await?;
+    for delivery in &due {
+        deliver(pool, sender, delivery).await?;
+    }
+
+    Ok(due.len())
+}
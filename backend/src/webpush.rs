@@ -0,0 +1,246 @@
+use aes_gcm::Aes128Gcm;
+use aes_gcm::aead::{Aead, KeyInit, generic_array::GenericArray};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use hkdf::Hkdf;
+use p256::PublicKey;
+use p256::ecdh::EphemeralSecret;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use reqwest::StatusCode;
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::env;
+
+use crate::notifier::{Notification, Notifier, NotifyError, PushSubscription, Recipient};
+use crate::webhooks::Sender;
+
+/// Durée de conservation d'un message par le service push s'il ne peut pas le remettre
+const PUSH_TTL_SECONDS: u32 = 12 * 60 * 60;
+
+/// Taille des blocs chiffrés : le contenu tient dans un seul bloc
+const RECORD_SIZE: u32 = 4096;
+
+/// Clé VAPID du serveur (RFC 8292), qui identifie l'expéditeur auprès des services push
+pub struct VapidKey {
+    signing_key: SigningKey,
+    public_key: String,
+}
+
+impl VapidKey {
+    /// Clé privée P-256 brute en base64url, comme la génèrent les outils Web Push
+    pub fn from_base64(private_key: &str) -> Result<Self, NotifyError> {
+        let bytes = decode_base64(private_key)?;
+        let signing_key = SigningKey::from_slice(&bytes)?;
+        let public_key = URL_SAFE_NO_PAD.encode(
+            signing_key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes(),
+        );
+
+        Ok(Self {
+            signing_key,
+            public_key,
+        })
+    }
+
+    /// `VAPID_PRIVATE_KEY`, ou `None` si Web Push n'est pas configuré
+    pub fn from_env() -> Option<Result<Self, NotifyError>> {
+        let key = env::var("VAPID_PRIVATE_KEY")
+            .ok()
+            .filter(|k| !k.is_empty())?;
+        Some(Self::from_base64(&key))
+    }
+
+    /// Clé publique à passer à `pushManager.subscribe` (`applicationServerKey`)
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// En-tête `Authorization` : jeton ES256 valable 12 heures pour l'origine du service push
+    pub fn authorization(&self, endpoint: &str, subject: &str) -> Result<String, NotifyError> {
+        let audience = reqwest::Url::parse(endpoint)?
+            .origin()
+            .ascii_serialization();
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(
+            serde_json::json!({
+                "aud": audience,
+                "exp": Utc::now().timestamp() + 12 * 60 * 60,
+                "sub": subject
+            })
+            .to_string(),
+        );
+        let unsigned = format!("{}.{}", header, claims);
+        let signature: Signature = self.signing_key.sign(unsigned.as_bytes());
+
+        Ok(format!(
+            "vapid t={}.{}, k={}",
+            unsigned,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+            self.public_key
+        ))
+    }
+}
+
+/// Chiffre le contenu pour un abonnement (RFC 8291, encodage `aes128gcm` de la RFC 8188) :
+/// sel, taille de bloc, clé publique éphémère, puis le bloc chiffré
+pub fn encrypt(p256dh: &str, auth: &str, payload: &[u8]) -> Result<Vec<u8>, NotifyError> {
+    let ua_public_bytes = decode_base64(p256dh)?;
+    let ua_public = PublicKey::from_sec1_bytes(&ua_public_bytes)?;
+    let auth_secret = decode_base64(auth)?;
+
+    let as_secret = EphemeralSecret::random(&mut OsRng);
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let shared = as_secret.diffie_hellman(&ua_public);
+
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(&ua_public_bytes);
+    key_info.extend_from_slice(as_public.as_bytes());
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&auth_secret), shared.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| "dérivation de clé")?;
+
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let hkdf = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let mut cek = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .and_then(|_| hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce))
+        .map_err(|_| "dérivation de clé")?;
+
+    // Délimiteur du dernier (et seul) bloc
+    let mut plaintext = payload.to_vec();
+    plaintext.push(2);
+    let ciphertext = Aes128Gcm::new(GenericArray::from_slice(&cek))
+        .encrypt(GenericArray::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| "chiffrement")?;
+
+    let mut body = Vec::with_capacity(86 + ciphertext.len());
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.len() as u8);
+    body.extend_from_slice(as_public.as_bytes());
+    body.extend_from_slice(&ciphertext);
+
+    Ok(body)
+}
+
+/// Base64url avec ou sans remplissage
+fn decode_base64(value: &str) -> Result<Vec<u8>, NotifyError> {
+    Ok(URL_SAFE_NO_PAD.decode(value.trim().trim_end_matches('='))?)
+}
+
+/// Envoie la notification chiffrée à chaque navigateur abonné.
+/// Un abonnement que le service push déclare expiré (404, 410) est supprimé.
+pub struct WebPushNotifier {
+    pool: PgPool,
+    sender: Sender,
+    key: VapidKey,
+    subject: String,
+}
+
+impl WebPushNotifier {
+    /// `subject` : contact de l'expéditeur pour les services push (`mailto:` ou URL)
+    pub fn new(pool: PgPool, key: VapidKey, subject: String) -> Self {
+        Self {
+            pool,
+            sender: Sender::public(),
+            key,
+            subject,
+        }
+    }
+
+    /// Envois autorisés vers la machine et le réseau local (serveurs de test)
+    pub fn local(pool: PgPool, key: VapidKey, subject: String) -> Self {
+        Self {
+            pool,
+            sender: Sender::local(),
+            key,
+            subject,
+        }
+    }
+
+    /// `VAPID_PRIVATE_KEY` et `VAPID_SUBJECT` (`mailto:admin@localhost` par défaut)
+    pub fn from_env(pool: PgPool) -> Option<Result<Self, NotifyError>> {
+        let subject =
+            env::var("VAPID_SUBJECT").unwrap_or_else(|_| "mailto:admin@localhost".to_string());
+        Some(VapidKey::from_env()?.map(|key| Self::new(pool, key, subject)))
+    }
+
+    async fn push(
+        &self,
+        subscription: &PushSubscription,
+        payload: &[u8],
+    ) -> Result<(), NotifyError> {
+        // Point d'accès fourni par le navigateur : mêmes restrictions que les webhooks
+        if !self.sender.can_reach(&subscription.endpoint) {
+            return Err("adresse non publique".into());
+        }
+        let body = encrypt(&subscription.p256dh, &subscription.auth, payload)?;
+        let response = self
+            .sender
+            .client()
+            .post(&subscription.endpoint)
+            .header(
+                "Authorization",
+                self.key
+                    .authorization(&subscription.endpoint, &self.subject)?,
+            )
+            .header("TTL", PUSH_TTL_SECONDS)
+            .header("Content-Encoding", "aes128gcm")
+            .header("Content-Type", "application/octet-stream")
+            .body(body)
+            .send()
+            .await?;
+
+        if matches!(response.status(), StatusCode::NOT_FOUND | StatusCode::GONE) {
+            sqlx::query!(
+                "DELETE FROM push_subscriptions WHERE id = $1",
+                subscription.id
+            )
+            .execute(&self.pool)
+            .await?;
+            return Ok(());
+        }
+        response.error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for WebPushNotifier {
+    fn name(&self) -> &'static str {
+        "webpush"
+    }
+
+    async fn send(
+        &self,
+        recipient: &Recipient,
+        notification: &Notification,
+    ) -> Result<(), NotifyError> {
+        let payload = serde_json::to_vec(notification)?;
+
+        // Un navigateur injoignable n'empêche pas l'envoi aux autres
+        let mut last_error = None;
+        for subscription in &recipient.push_subscriptions {
+            if let Err(e) = self.push(subscription, &payload).await {
+                last_error = Some(e);
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use sqlx::PgPool;
+use task_manager::admin::{create_user, export_user, find_user, reset_password, set_disabled};
+use task_manager::api;
+use task_manager::auth::verify_password;
+use tokio::sync::broadcast;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, token};
+
+/// Code HTTP de `GET /api/v1/tasks` avec ce jeton
+async fn list_status(pool: &PgPool, token: &str) -> StatusCode {
+    api::router(pool.clone(), broadcast::channel(16).0)
+        .oneshot(
+            Request::get("/api/v1/tasks")
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+#[sqlx::test]
+async fn create_find_and_reset_password(pool: PgPool) {
+    apply_migrations(&pool).await;
+
+    let id = create_user(&pool, "alice", "motdepasse")
+        .await
+        .unwrap()
+        .unwrap();
+    // Nom déjà pris
+    assert_eq!(create_user(&pool, "alice", "autre").await.unwrap(), None);
+
+    assert_eq!(find_user(&pool, "alice").await.unwrap(), Some(id));
+    assert_eq!(find_user(&pool, &id.to_string()).await.unwrap(), Some(id));
+    assert_eq!(find_user(&pool, "bob").await.unwrap(), None);
+
+    reset_password(&pool, id, "nouveau-secret").await.unwrap();
+    let user = sqlx::query!(
+        "SELECT password_hash, tokens_valid_after FROM users WHERE id = $1",
+        id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(verify_password("nouveau-secret", &user.password_hash));
+    assert!(!verify_password("motdepasse", &user.password_hash));
+    // Les jetons émis avant la réinitialisation sont révoqués
+    assert!(user.tokens_valid_after.is_some());
+}
+
+#[sqlx::test]
+async fn disable_and_enable(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let id = create_user(&pool, "alice", "motdepasse")
+        .await
+        .unwrap()
+        .unwrap();
+
+    set_disabled(&pool, id, true).await.unwrap();
+    let user = sqlx::query!(
+        "SELECT disabled_at, tokens_valid_after FROM users WHERE id = $1",
+        id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(user.disabled_at.is_some());
+    assert!(user.tokens_valid_after.is_some());
+
+    set_disabled(&pool, id, false).await.unwrap();
+    let disabled_at = sqlx::query_scalar!("SELECT disabled_at FROM users WHERE id = $1", id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(disabled_at, None);
+}
+
+#[sqlx::test]
+async fn export_contains_user_data(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let id = create_user(&pool, "alice", "motdepasse")
+        .await
+        .unwrap()
+        .unwrap();
+    let other = create_user(&pool, "bob", "motdepasse")
+        .await
+        .unwrap()
+        .unwrap();
+    let task_id = create_task(&pool, id, "Sport", &[1, 3, 5]).await;
+    create_task(&pool, other, "Lecture", &[2]).await;
+
+    sqlx::query!(
+        "INSERT INTO task_completions (task_id, date) VALUES ($1, CURRENT_DATE)",
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let export = export_user(&pool, id).await.unwrap();
+    assert_eq!(export["user"]["username"], "alice");
+
+    let tasks = export["tasks"].as_array().unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0]["title"], "Sport");
+    assert_eq!(tasks[0]["days"], serde_json::json!([1, 3, 5]));
+
+    let completions = export["completions"].as_array().unwrap();
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0]["task_id"], task_id);
+}
+
+#[sqlx::test]
+async fn reset_and_disable_revoke_tokens_of_the_same_second(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let id: Uuid = create_user(&pool, "alice", "motdepasse")
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Émis juste avant la réinitialisation, le plus souvent pendant la même seconde
+    let before = token(id);
+    assert_eq!(list_status(&pool, &before).await, StatusCode::OK);
+    reset_password(&pool, id, "nouveau-secret").await.unwrap();
+    assert_eq!(list_status(&pool, &before).await, StatusCode::UNAUTHORIZED);
+
+    // Un jeton émis une fois la seconde de révocation passée est accepté
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let after = token(id);
+    assert_eq!(list_status(&pool, &after).await, StatusCode::OK);
+
+    set_disabled(&pool, id, true).await.unwrap();
+    assert_eq!(list_status(&pool, &after).await, StatusCode::UNAUTHORIZED);
+    set_disabled(&pool, id, false).await.unwrap();
+    // La réactivation ne rend pas valides les jetons révoqués
+    assert_eq!(list_status(&pool, &after).await, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,97 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use sqlx::PgPool;
+use task_manager::api;
+use tokio::sync::broadcast;
+use tower::ServiceExt;
+
+mod common;
+use common::apply_migrations;
+
+async fn send(pool: &PgPool, request: Request<Body>) -> Response {
+    api::router(pool.clone(), broadcast::channel(16).0)
+        .oneshot(request)
+        .await
+        .unwrap()
+}
+
+fn login(path: &str) -> Request<Body> {
+    Request::post(path)
+        .header("content-type", "application/json")
+        .body(Body::from(
+            r#"{"username":"personne","password":"motdepasse"}"#,
+        ))
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn v1_is_not_deprecated(pool: PgPool) {
+    apply_migrations(&pool).await;
+
+    let response = send(&pool, login("/api/v1/auth/login")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().get("deprecation").is_none());
+
+    let response = send(
+        &pool,
+        Request::get("/api/v1/tasks").body(Body::empty()).unwrap(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().get("sunset").is_none());
+}
+
+#[sqlx::test]
+async fn legacy_prefix_is_a_deprecated_alias(pool: PgPool) {
+    apply_migrations(&pool).await;
+
+    // Même comportement que la v1, en-têtes de dépréciation en plus
+    let response = send(&pool, login("/api/auth/login")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(
+        response.headers()["deprecation"]
+            .to_str()
+            .unwrap()
+            .starts_with('@')
+    );
+    assert!(response.headers().contains_key("sunset"));
+    assert_eq!(
+        response.headers()["link"],
+        r#"</api/v1/auth/login>; rel="successor-version""#
+    );
+
+    // Y compris quand l'authentification échoue
+    let response = send(
+        &pool,
+        Request::get("/api/tasks/3").body(Body::empty()).unwrap(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()["link"],
+        r#"</api/v1/tasks/3>; rel="successor-version""#
+    );
+}
+
+#[sqlx::test]
+async fn docs_are_still_served_under_the_legacy_prefix(pool: PgPool) {
+    apply_migrations(&pool).await;
+
+    for (legacy, current) in [
+        ("/api/openapi.json", "/api/v1/openapi.json"),
+        ("/api/docs/", "/api/v1/docs/"),
+    ] {
+        let response = send(&pool, Request::get(current).body(Body::empty()).unwrap()).await;
+        assert_eq!(response.status(), StatusCode::OK, "{}", current);
+        assert!(response.headers().get("deprecation").is_none());
+
+        let response = send(&pool, Request::get(legacy).body(Body::empty()).unwrap()).await;
+        assert_eq!(response.status(), StatusCode::OK, "{}", legacy);
+        assert!(response.headers().contains_key("sunset"));
+        assert_eq!(
+            response.headers()["link"],
+            format!(r#"<{}>; rel="successor-version""#, current).as_str()
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+
+mod common;
+use common::{apply_migrations, create_task, create_user, send};
+
+#[sqlx::test]
+async fn history_records_actor_and_snapshots(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3, 4, 5, 6, 7]).await;
+    let uri = format!("/tasks/{}", task_id);
+
+    send(
+        &pool,
+        user_id,
+        "POST",
+        &uri,
+        &[("If-Match", "*")],
+        Some(json!({ "title": "Course" })),
+    )
+    .await;
+    send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("{}/toggle", uri),
+        &[],
+        None,
+    )
+    .await;
+    send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("{}/subtasks", uri),
+        &[("If-Match", "*")],
+        Some(json!({ "title": "Étirements" })),
+    )
+    .await;
+
+    let history = send(
+        &pool,
+        user_id,
+        "GET",
+        &format!("{}/history", uri),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(history.status, StatusCode::OK);
+    assert_eq!(history.body["total"], 3);
+    let entries = history.body["entries"].as_array().unwrap();
+
+    // Du plus récent au plus ancien
+    assert_eq!(entries[0]["entity"], "subtask");
+    assert_eq!(entries[0]["action"], "create");
+    assert_eq!(entries[0]["before"], serde_json::Value::Null);
+    assert_eq!(entries[0]["after"]["title"], "Étirements");
+
+    assert_eq!(entries[1]["entity"], "completion");
+    assert_eq!(entries[1]["after"]["completed"], true);
+
+    assert_eq!(entries[2]["entity"], "task");
+    assert_eq!(entries[2]["action"], "update");
+    assert_eq!(entries[2]["before"]["title"], "Sport");
+    assert_eq!(entries[2]["after"]["title"], "Course");
+    assert_eq!(entries[2]["actor_id"], user_id.to_string());
+    assert_eq!(entries[2]["actor_username"], user_id.to_string());
+
+    // Pagination, y compris des pages hors de portée
+    let (pool, base) = (&pool, &uri);
+    let page = move |query: &str| {
+        let uri = format!("{}/history?{}", base, query);
+        async move { send(pool, user_id, "GET", &uri, &[], None).await }
+    };
+    let second = page("page=2&per_page=2").await;
+    assert_eq!(second.body["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(second.body["entries"][0]["entity"], "task");
+    let beyond = page("page=1000&per_page=2").await;
+    assert!(beyond.body["entries"].as_array().unwrap().is_empty());
+    for query in [
+        "page=0",
+        "per_page=201",
+        &format!("page={}&per_page=2", i64::MAX),
+    ] {
+        assert_eq!(
+            page(query).await.status,
+            StatusCode::BAD_REQUEST,
+            "{}",
+            query
+        );
+    }
+
+    // L'historique d'une tâche n'est visible que de son propriétaire
+    let other = create_user(pool).await;
+    let history = send(pool, other, "GET", &format!("{}/history", uri), &[], None).await;
+    assert_eq!(history.status, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn entries_share_the_transaction_id_of_their_request(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let every_day = [1, 2, 3, 4, 5, 6, 7];
+    let first = create_task(&pool, user_id, "Lecture", &every_day).await;
+    let second = create_task(&pool, user_id, "Sport", &every_day).await;
+
+    // Deux opérations d'un lot, chacune dans son point de sauvegarde
+    let batch = send(
+        &pool,
+        user_id,
+        "POST",
+        "/batch",
+        &[],
+        Some(json!({
+            "operations": [
+                { "op": "toggle", "task_id": first },
+                { "op": "toggle", "task_id": second },
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(batch.status, StatusCode::OK);
+    send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("/tasks/{}/toggle", first),
+        &[],
+        None,
+    )
+    .await;
+
+    let tx_ids = sqlx::query_scalar!("SELECT tx_id FROM audit_log ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(tx_ids.len(), 3);
+    assert_eq!(tx_ids[0], tx_ids[1]);
+    assert_ne!(tx_ids[1], tx_ids[2]);
+
+    // Le jeton du lot couvre ses deux entrées, pas celle de la requête suivante
+    let token = batch.body["undo_token"].as_str().unwrap();
+    let undo_token_tx = sqlx::query_scalar!(
+        "SELECT tx_id FROM undo_tokens WHERE token = $1::text::uuid",
+        token
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(undo_token_tx, tx_ids[0]);
+
+    // La complétion de `first` a changé depuis le lot : l'annulation est refusée en bloc
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("/undo/{}", token),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::CONFLICT);
+    let completed = sqlx::query_scalar!(
+        r#"SELECT count(*) AS "count!" FROM task_completions WHERE completed AND date = current_date"#
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(completed, 1);
+}
+
+#[sqlx::test]
+async fn requests_without_changes_issue_no_undo_token(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3]).await;
+
+    // Mettre à la corbeille une tâche qui y est déjà ne change rien
+    let uri = format!("/tasks/{}", task_id);
+    let first = send(&pool, user_id, "DELETE", &uri, &[("If-Match", "*")], None).await;
+    assert!(first.body["undo_token"].is_string());
+    let second = send(&pool, user_id, "DELETE", &uri, &[("If-Match", "*")], None).await;
+    assert_eq!(second.status, StatusCode::OK);
+    assert_eq!(second.body["undo_token"], serde_json::Value::Null);
+
+    let entries = sqlx::query_scalar!(
+        r#"SELECT count(*) AS "count!" FROM audit_log WHERE task_id = $1"#,
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(entries, 1);
+}
@@ -0,0 +1,178 @@
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+use common::{Reply, apply_migrations, create_task, create_user, send};
+
+async fn batch(pool: &PgPool, user_id: Uuid, body: Value) -> Reply {
+    send(pool, user_id, "POST", "/batch", &[], Some(body)).await
+}
+
+fn statuses(reply: &Reply) -> Vec<u64> {
+    reply.body["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["status"].as_u64().unwrap())
+        .collect()
+}
+
+async fn task_state(pool: &PgPool, task_id: i32) -> (String, bool, bool, i32) {
+    let task = sqlx::query!(
+        "SELECT title, active, deleted_at IS NOT NULL AS \"deleted!\", version FROM tasks WHERE id = $1",
+        task_id
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    (task.title, task.active, task.deleted, task.version)
+}
+
+async fn count_tasks(pool: &PgPool, user_id: Uuid) -> i64 {
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM tasks WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn atomic_batches_roll_back_on_the_first_failure(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let read = create_task(&pool, user_id, "Read", &[1]).await;
+    let write = create_task(&pool, user_id, "Write", &[2]).await;
+    let before = (
+        task_state(&pool, read).await,
+        task_state(&pool, write).await,
+    );
+
+    // Mode atomique par défaut
+    let reply = batch(
+        &pool,
+        user_id,
+        json!({
+            "operations": [
+                { "op": "create", "title": "Run", "days": [3] },
+                { "op": "update", "task_id": read, "version": before.0.3, "title": "Relire" },
+                { "op": "delete", "task_id": write, "version": before.1.3 + 5 },
+                { "op": "archive", "task_id": write, "archived": true, "version": before.1.3 },
+                { "op": "toggle", "task_id": read },
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(reply.body["committed"], false);
+    assert_eq!(reply.body["undo_token"], Value::Null);
+    assert_eq!(statuses(&reply), vec![201, 200, 412, 424, 424]);
+    let stale = &reply.body["results"][2];
+    assert_eq!(stale["version"], before.1.3);
+    assert_eq!(stale["current"]["title"], "Write");
+
+    // Rien n'est resté des opérations réussies
+    assert_eq!(count_tasks(&pool, user_id).await, 2);
+    assert_eq!(
+        (
+            task_state(&pool, read).await,
+            task_state(&pool, write).await
+        ),
+        before
+    );
+    let completions = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM task_completions WHERE task_id = $1"#,
+        read
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(completions, 0);
+
+    // Le même lot sans l'opération périmée passe en entier
+    let reply = batch(
+        &pool,
+        user_id,
+        json!({
+            "atomic": true,
+            "operations": [
+                { "op": "update", "task_id": read, "version": before.0.3, "title": "Relire" },
+                { "op": "archive", "task_id": write, "archived": true, "version": before.1.3 },
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(reply.body["committed"], true);
+    assert_eq!(statuses(&reply), vec![200, 200]);
+    assert!(reply.body["undo_token"].is_string());
+    assert_eq!(task_state(&pool, read).await.0, "Relire");
+    assert!(!task_state(&pool, write).await.1);
+}
+
+#[sqlx::test]
+async fn best_effort_batches_keep_the_successful_operations(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let other = create_user(&pool).await;
+    let read = create_task(&pool, user_id, "Read", &[1]).await;
+    let write = create_task(&pool, user_id, "Write", &[2]).await;
+    let run = create_task(&pool, user_id, "Run", &[3]).await;
+    let swim = create_task(&pool, other, "Swim", &[4]).await;
+    let version = task_state(&pool, read).await.3;
+
+    let reply = batch(
+        &pool,
+        user_id,
+        json!({
+            "atomic": false,
+            "operations": [
+                { "op": "update", "task_id": read, "version": version, "title": "Relire" },
+                { "op": "delete", "task_id": write },
+                { "op": "toggle", "task_id": swim },
+                {
+                    "op": "update", "task_id": run, "version": version, "title": "Courir",
+                    "subtasks": [{ "id": 0, "title": "Disparue", "version": 1 }]
+                },
+                { "op": "create", "title": "Cook", "days": [5] },
+                { "op": "delete", "task_id": run, "version": version },
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(reply.body["committed"], true);
+    assert_eq!(statuses(&reply), vec![200, 428, 404, 409, 201, 200]);
+    // Une création ne se défait pas : pas de jeton d'annulation
+    assert_eq!(reply.body["undo_token"], Value::Null);
+
+    assert_eq!(task_state(&pool, read).await.0, "Relire");
+    assert!(!task_state(&pool, write).await.2);
+    assert!(task_state(&pool, run).await.2);
+    assert_eq!(task_state(&pool, swim).await.0, "Swim");
+    let created = reply.body["results"][4]["task_id"].as_i64().unwrap() as i32;
+    assert_eq!(task_state(&pool, created).await.0, "Cook");
+    // L'opération en échec n'a rien laissé derrière elle
+    assert_eq!(task_state(&pool, run).await.0, "Run");
+}
+
+#[sqlx::test]
+async fn oversized_batches_are_refused(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1]).await;
+
+    let operations = vec![json!({ "op": "toggle", "task_id": task_id }); 201];
+    let reply = batch(&pool, user_id, json!({ "operations": operations })).await;
+    assert_eq!(reply.status, StatusCode::PAYLOAD_TOO_LARGE);
+    let completions = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM task_completions WHERE task_id = $1"#,
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(completions, 0);
+}
@@ -0,0 +1,133 @@
+#![allow(dead_code)]
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Request, StatusCode};
+use sqlx::PgPool;
+use task_manager::api;
+use tokio::sync::broadcast;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+/// Helper pour appliquer les migrations avant chaque test
+pub async fn apply_migrations(pool: &PgPool) {
+    sqlx::migrate!("./migrations") // chemin **absolu** relatif au Cargo.toml
+        .run(pool)
+        .await
+        .unwrap();
+}
+
+/// Helper pour créer l'utilisateur propriétaire des tâches de test
+pub async fn create_user(pool: &PgPool) -> Uuid {
+    let user_id = Uuid::new_v4();
+    sqlx::query!(
+        "INSERT INTO users (id, username, password_hash) VALUES ($1, $2, 'hash')",
+        user_id,
+        user_id.to_string()
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    user_id
+}
+
+/// Helper pour créer une tâche prévue les jours donnés
+pub async fn create_task(pool: &PgPool, user_id: Uuid, title: &str, days: &[i32]) -> i32 {
+    let rec = sqlx::query!(
+        "INSERT INTO tasks (user_id, title) VALUES ($1, $2) RETURNING id",
+        user_id,
+        title
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+
+    for day in days {
+        sqlx::query!(
+            "INSERT INTO task_days (task_id, day_of_week) VALUES ($1, $2)",
+            rec.id,
+            day
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    rec.id
+}
+
+/// Helper pour faire comme si la tâche et son planning existaient depuis `days` jours
+pub async fn backdate_task(pool: &PgPool, task_id: i32, days: i32) {
+    sqlx::query!(
+        "UPDATE tasks SET created_at = created_at - make_interval(days => $2) WHERE id = $1",
+        task_id,
+        days
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        "UPDATE task_schedules SET effective_from = effective_from - $2::int WHERE task_id = $1",
+        task_id,
+        days
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+/// Jeton de session de l'utilisateur, signé avec un secret de test
+pub fn token(user_id: Uuid) -> String {
+    // SAFETY: tous les tests posent la même valeur
+    unsafe { std::env::set_var("JWT_SECRET", "secret-de-test") };
+    task_manager::auth::create_jwt(user_id)
+}
+
+/// Réponse de l'API : code HTTP, en-têtes et corps JSON (`Null` si vide)
+pub struct Reply {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: serde_json::Value,
+}
+
+/// Envoie une requête à l'API v1 (`uri` relatif à `/api/v1`) au nom de l'utilisateur,
+/// avec des en-têtes supplémentaires et un corps JSON éventuels
+pub async fn send(
+    pool: &PgPool,
+    user_id: Uuid,
+    method: &str,
+    uri: &str,
+    headers: &[(&str, &str)],
+    body: Option<serde_json::Value>,
+) -> Reply {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(format!("/api/v1{}", uri))
+        .header("Authorization", format!("Bearer {}", token(user_id)));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap();
+
+    let response = api::router(pool.clone(), broadcast::channel(16).0)
+        .oneshot(request)
+        .await
+        .unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    Reply {
+        status,
+        headers,
+        body,
+    }
+}
@@ -0,0 +1,243 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use task_manager::models::{CompletionNoteRequest, LogValueRequest, SetCompletionRequest};
+use task_manager::routes::{log_task_value, set_completion, set_completion_note};
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, create_user, send};
+
+/// Date du jour décalée de `days` jours, selon l'horloge de la base
+async fn db_date(pool: &PgPool, days: i32) -> NaiveDate {
+    sqlx::query_scalar!(r#"SELECT current_date + $1::int AS "date!""#, days)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn complete(
+    pool: &PgPool,
+    user_id: Uuid,
+    task_id: i32,
+    date: Option<NaiveDate>,
+) -> Result<serde_json::Value, StatusCode> {
+    set_completion(
+        Path(task_id),
+        State(pool.clone()),
+        Extension(user_id),
+        Json(SetCompletionRequest {
+            date,
+            completed: true,
+        }),
+    )
+    .await
+    .map(|Json(body)| body)
+}
+
+#[sqlx::test]
+async fn future_dates_follow_the_database_clock(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+    let today = db_date(&pool, 0).await;
+    let tomorrow = db_date(&pool, 1).await;
+
+    // Sans date : le jour de la base, comme les déclencheurs et les statistiques
+    let body = complete(&pool, user_id, task_id, None).await.unwrap();
+    assert_eq!(body["date"], serde_json::json!(today));
+    assert!(complete(&pool, user_id, task_id, Some(today)).await.is_ok());
+    assert_eq!(
+        complete(&pool, user_id, task_id, Some(tomorrow))
+            .await
+            .unwrap_err(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let note = set_completion_note(
+        Path(task_id),
+        State(pool.clone()),
+        Extension(user_id),
+        Json(CompletionNoteRequest {
+            date: Some(tomorrow),
+            note: Some("plus tard".to_string()),
+            rating: None,
+        }),
+    )
+    .await;
+    assert_eq!(note.unwrap_err(), StatusCode::BAD_REQUEST);
+
+    sqlx::query!(
+        "UPDATE tasks SET measurement_type = 'count', target = 10 WHERE id = $1",
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let logged = log_task_value(
+        Path(task_id),
+        State(pool.clone()),
+        Extension(user_id),
+        Json(LogValueRequest {
+            value: 3.0,
+            date: Some(tomorrow),
+            increment: None,
+        }),
+    )
+    .await;
+    assert_eq!(logged.unwrap_err(), StatusCode::BAD_REQUEST);
+
+    let future_rows = sqlx::query_scalar!(
+        r#"SELECT count(*) AS "count!" FROM task_completions WHERE date > current_date"#
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(future_rows, 0);
+}
+
+#[sqlx::test]
+async fn put_routes_can_be_repeated(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+
+    // Cocher deux fois laisse l'occurrence cochée, contrairement à `toggle`
+    let completion_uri = format!("/tasks/{}/completion", task_id);
+    let put_completion = |completed: bool| {
+        send(
+            &pool,
+            user_id,
+            "PUT",
+            &completion_uri,
+            &[],
+            Some(serde_json::json!({ "completed": completed })),
+        )
+    };
+    for _ in 0..2 {
+        let reply = put_completion(true).await;
+        assert_eq!(reply.status, StatusCode::OK);
+        assert_eq!(reply.body["completed"], true);
+    }
+    let completions = sqlx::query_scalar!(
+        r#"SELECT count(*) AS "count!" FROM task_completions WHERE task_id = $1"#,
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(completions, 1);
+    for _ in 0..2 {
+        assert_eq!(put_completion(false).await.body["completed"], false);
+    }
+
+    // Archiver une tâche déjà archivée ne change ni sa version ni son historique
+    let archived_uri = format!("/tasks/{}/archived", task_id);
+    let put_archived = || {
+        send(
+            &pool,
+            user_id,
+            "PUT",
+            &archived_uri,
+            &[],
+            Some(serde_json::json!({ "archived": true })),
+        )
+    };
+    let first = put_archived().await;
+    assert_eq!(first.status, StatusCode::OK);
+    assert_eq!(first.body["active"], false);
+    assert!(first.body["undo_token"].is_string());
+    let again = put_archived().await;
+    assert_eq!(again.status, StatusCode::OK);
+    assert_eq!(again.body["active"], false);
+    assert_eq!(again.body["version"], first.body["version"]);
+    assert_eq!(again.headers["etag"], first.headers["etag"]);
+    assert!(again.body["undo_token"].is_null());
+
+    // Une version périmée est refusée
+    let stale = send(
+        &pool,
+        user_id,
+        "PUT",
+        &format!("/tasks/{}/archived", task_id),
+        &[("If-Match", "\"1\"")],
+        Some(serde_json::json!({ "archived": false })),
+    )
+    .await;
+    assert_eq!(stale.status, StatusCode::PRECONDITION_FAILED);
+}
+
+#[sqlx::test]
+async fn measured_tasks_are_completed_by_their_value(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Pompes", &[1, 2, 3, 4, 5, 6, 7]).await;
+    sqlx::query!(
+        "UPDATE tasks SET measurement_type = 'count', target = 10 WHERE id = $1",
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let uri = format!("/tasks/{}", task_id);
+
+    // Cocher sans valeur laisserait l'occurrence et les statistiques de mesure en désaccord
+    assert_eq!(
+        complete(&pool, user_id, task_id, None).await.unwrap_err(),
+        StatusCode::BAD_REQUEST
+    );
+    let toggled = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("{}/toggle", uri),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(toggled.status, StatusCode::BAD_REQUEST);
+    let batch = send(
+        &pool,
+        user_id,
+        "POST",
+        "/batch",
+        &[],
+        Some(serde_json::json!({ "operations": [{ "op": "toggle", "task_id": task_id }] })),
+    )
+    .await;
+    assert_eq!(batch.body["results"][0]["status"], 400);
+    let trigger = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("{}/triggers", uri),
+        &[],
+        Some(serde_json::json!({ "action": "complete" })),
+    )
+    .await;
+    assert_eq!(trigger.status, StatusCode::BAD_REQUEST);
+    let completions = sqlx::query_scalar!(
+        r#"SELECT count(*) AS "count!" FROM task_completions WHERE task_id = $1"#,
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(completions, 0);
+
+    // La valeur complète l'occurrence dès qu'elle atteint l'objectif
+    let logged = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("{}/log", uri),
+        &[],
+        Some(serde_json::json!({ "value": 10 })),
+    )
+    .await;
+    assert_eq!(logged.body["completed"], true);
+}
@@ -1,20 +1,16 @@
-use chrono::{Datelike, Local};
 use sqlx::PgPool;
 
-/// Helper pour appliquer les migrations avant chaque test
-async fn apply_migrations(pool: &PgPool) {
-    sqlx::migrate!("./migrations") // chemin **absolu** relatif au Cargo.toml
-        .run(pool)
-        .await
-        .unwrap();
-}
+mod common;
+use common::{apply_migrations, backdate_task, create_task, create_user};
 
 #[sqlx::test]
 async fn create_task_works(pool: PgPool) {
     apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
 
     let rec = sqlx::query!(
-        "INSERT INTO tasks (title) VALUES ($1) RETURNING id",
+        "INSERT INTO tasks (user_id, title) VALUES ($1, $2) RETURNING id",
+        user_id,
         "Test task"
     )
     .fetch_one(&pool)
@@ -27,9 +23,11 @@ async fn create_task_works(pool: PgPool) {
 #[sqlx::test]
 async fn task_days_are_inserted(pool: PgPool) {
     apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
 
     let rec = sqlx::query!(
-        "INSERT INTO tasks (title) VALUES ($1) RETURNING id",
+        "INSERT INTO tasks (user_id, title) VALUES ($1, $2) RETURNING id",
+        user_id,
         "Task with days"
     )
     .fetch_one(&pool)
@@ -38,7 +36,7 @@ async fn task_days_are_inserted(pool: PgPool) {
 
     let task_id = rec.id;
 
-    for day in [0, 1, 2] {
+    for day in [1, 2, 3] {
         sqlx::query!(
             "INSERT INTO task_days (task_id, day_of_week) VALUES ($1, $2)",
             task_id,
@@ -62,9 +60,11 @@ async fn task_days_are_inserted(pool: PgPool) {
 #[sqlx::test]
 async fn soft_delete_hides_task(pool: PgPool) {
     apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
 
     let rec = sqlx::query!(
-        "INSERT INTO tasks (title) VALUES ($1) RETURNING id",
+        "INSERT INTO tasks (user_id, title) VALUES ($1, $2) RETURNING id",
+        user_id,
         "Soft delete task"
     )
     .fetch_one(&pool)
@@ -91,11 +91,17 @@ async fn soft_delete_hides_task(pool: PgPool) {
 #[sqlx::test]
 async fn today_tasks_only_matching_day(pool: PgPool) {
     apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
 
-    let today = Local::now().weekday().num_days_from_monday() as i32;
+    // Jour ISO (1 = lundi) selon la base, comme `get_today_tasks`
+    let today = sqlx::query_scalar!(r#"SELECT extract(isodow FROM current_date)::int AS "day!""#)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
 
     let rec = sqlx::query!(
-        "INSERT INTO tasks (title) VALUES ($1) RETURNING id",
+        "INSERT INTO tasks (user_id, title) VALUES ($1, $2) RETURNING id",
+        user_id,
         "Today task"
     )
     .fetch_one(&pool)
@@ -128,3 +134,286 @@ async fn today_tasks_only_matching_day(pool: PgPool) {
 
     assert_eq!(rows.len(), 1);
 }
+
+#[sqlx::test]
+async fn measured_task_requires_target(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+
+    let missing_target = sqlx::query!(
+        "INSERT INTO tasks (user_id, title, measurement_type) VALUES ($1, $2, 'count')",
+        user_id,
+        "Drink water"
+    )
+    .execute(&pool)
+    .await;
+    assert!(missing_target.is_err());
+
+    let boolean_with_target = sqlx::query!(
+        "INSERT INTO tasks (user_id, title, target) VALUES ($1, $2, 8)",
+        user_id,
+        "Drink water"
+    )
+    .execute(&pool)
+    .await;
+    assert!(boolean_with_target.is_err());
+
+    let measured = sqlx::query!(
+        "INSERT INTO tasks (user_id, title, measurement_type, target, unit) VALUES ($1, $2, 'count', 8, 'glasses')",
+        user_id,
+        "Drink water"
+    )
+    .execute(&pool)
+    .await;
+    assert!(measured.is_ok());
+}
+
+#[sqlx::test]
+async fn skipped_and_vacation_days_are_excused(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Run", &[1, 2, 3, 4, 5, 6, 7]).await;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO task_completions (task_id, date, completed, skipped, skip_reason)
+        VALUES ($1, DATE '2026-01-05', false, true, 'sick')
+        "#,
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    sqlx::query!(
+        "INSERT INTO vacations (user_id, start_date, end_date) VALUES ($1, DATE '2026-02-01', DATE '2026-02-07')",
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let excused = sqlx::query!(
+        r#"
+        SELECT is_excused($1, DATE '2026-01-05') as "skipped!",
+               is_excused($1, DATE '2026-02-03') as "vacation!",
+               is_excused($1, DATE '2026-03-01') as "regular!"
+        "#,
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    assert!(excused.skipped);
+    assert!(excused.vacation);
+    assert!(!excused.regular);
+}
+
+#[sqlx::test]
+async fn audit_log_is_append_only(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Audited", &[1]).await;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (actor_id, owner_id, task_id, entity, entity_id, action, after)
+        VALUES ($1, $1, $2, 'task', $2::int::text, 'create', audit_task_snapshot($2))
+        "#,
+        user_id,
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let update = sqlx::query!("UPDATE audit_log SET action = 'tampered'")
+        .execute(&pool)
+        .await;
+    assert!(update.is_err());
+
+    let delete = sqlx::query!("DELETE FROM audit_log").execute(&pool).await;
+    assert!(delete.is_err());
+
+    // L'historique survit à la suppression définitive de la tâche
+    sqlx::query!("DELETE FROM tasks WHERE id = $1", task_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let snapshot = sqlx::query_scalar!(
+        "SELECT after FROM audit_log WHERE task_id = $1",
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap()
+    .expect("snapshot should be recorded");
+
+    assert_eq!(snapshot["title"], "Audited");
+    assert_eq!(snapshot["days"], serde_json::json!([1]));
+}
+
+#[sqlx::test]
+async fn subtask_version_bumps_only_on_change(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Versioned", &[1]).await;
+
+    let subtask_id = sqlx::query_scalar!(
+        "INSERT INTO subtasks (task_id, title) VALUES ($1, 'step') RETURNING id",
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let version_after = |sql: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query(sql).bind(subtask_id).execute(&pool).await.unwrap();
+            sqlx::query_scalar!("SELECT version FROM subtasks WHERE id = $1", subtask_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+
+    assert_eq!(version_after("UPDATE subtasks SET title = 'step' WHERE id = $1").await, 1);
+    assert_eq!(version_after("UPDATE subtasks SET completed = true WHERE id = $1").await, 2);
+    assert_eq!(version_after("UPDATE subtasks SET title = 'renamed' WHERE id = $1").await, 3);
+}
+
+#[sqlx::test]
+async fn task_version_follows_days_and_subtasks(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Versioned", &[1]).await;
+
+    let version = || async {
+        sqlx::query_scalar!("SELECT version FROM tasks WHERE id = $1", task_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+
+    let initial = version().await;
+
+    sqlx::query!("INSERT INTO task_days (task_id, day_of_week) VALUES ($1, 2)", task_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let after_days = version().await;
+    assert!(after_days > initial);
+
+    sqlx::query!("INSERT INTO subtasks (task_id, title) VALUES ($1, 'step')", task_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let after_subtask = version().await;
+    assert!(after_subtask > after_days);
+
+    // Une mise à jour sans changement ne modifie pas la version
+    sqlx::query!("UPDATE tasks SET title = title WHERE id = $1", task_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(version().await, after_subtask);
+}
+
+#[sqlx::test]
+async fn daily_stats_follow_completions(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+    let other_id = create_task(&pool, user_id, "Write", &[1, 2, 3, 4, 5, 6, 7]).await;
+    backdate_task(&pool, task_id, 7).await;
+    backdate_task(&pool, other_id, 7).await;
+
+    sqlx::query!(
+        "SELECT ensure_daily_stats($1, current_date - 6, current_date)",
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let day = || async {
+        sqlx::query!(
+            r#"
+            SELECT scheduled_tasks, done_tasks
+            FROM user_daily_stats
+            WHERE user_id = $1 AND date = current_date - 2
+            "#,
+            user_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+
+    let before = day().await;
+    assert_eq!((before.scheduled_tasks, before.done_tasks), (2, 0));
+
+    // Une complétion met à jour le bilan de son jour par déclencheur
+    sqlx::query!(
+        "INSERT INTO task_completions (task_id, date, completed) VALUES ($1, current_date - 2, true)",
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let completed = day().await;
+    assert_eq!((completed.scheduled_tasks, completed.done_tasks), (2, 1));
+
+    // Une occurrence sautée n'est plus prévue
+    sqlx::query!(
+        r#"
+        UPDATE task_completions SET completed = false, skipped = true
+        WHERE task_id = $1 AND date = current_date - 2
+        "#,
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let skipped = day().await;
+    assert_eq!((skipped.scheduled_tasks, skipped.done_tasks), (1, 0));
+}
+
+#[sqlx::test]
+async fn past_days_use_the_schedule_in_effect(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Run", &[1, 2, 3, 4, 5, 6, 7]).await;
+    backdate_task(&pool, task_id, 10).await;
+
+    // Plus aucun jour prévu à partir d'aujourd'hui, comme après une modification
+    sqlx::query!("DELETE FROM task_days WHERE task_id = $1", task_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    sqlx::query!(
+        "SELECT ensure_daily_stats($1, current_date - 19, current_date)",
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // Prévue les 10 jours depuis sa création, ni avant, ni aujourd'hui
+    let scheduled = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM task_daily_stats
+        WHERE task_id = $1 AND scheduled
+        "#,
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(scheduled, 10);
+}
@@ -0,0 +1,249 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use sqlx::PgPool;
+use task_manager::models::{AddMemberRequest, CreateTeamRequest, UpdateTemplateRequest};
+use task_manager::routes_teams::{add_member, create_team, remove_member, update_template};
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, create_user};
+
+/// Crée une équipe administrée par `admin` et renvoie son id
+async fn team(pool: &PgPool, admin: Uuid) -> i32 {
+    let (_, Json(created)) = create_team(
+        State(pool.clone()),
+        Extension(admin),
+        Json(CreateTeamRequest {
+            name: "Backend".to_string(),
+        }),
+    )
+    .await
+    .unwrap();
+    created["id"].as_i64().unwrap() as i32
+}
+
+async fn add(pool: &PgPool, team_id: i32, admin: Uuid, member: Uuid, role: &str) -> StatusCode {
+    match add_member(
+        Path(team_id),
+        State(pool.clone()),
+        Extension(admin),
+        Json(AddMemberRequest {
+            username: member.to_string(),
+            role: Some(role.to_string()),
+        }),
+    )
+    .await
+    {
+        Ok(status) => status,
+        Err(status) => status,
+    }
+}
+
+async fn remove(pool: &PgPool, team_id: i32, actor: Uuid, member: Uuid) -> StatusCode {
+    match remove_member(
+        Path((team_id, member)),
+        State(pool.clone()),
+        Extension(actor),
+    )
+    .await
+    {
+        Ok(status) => status,
+        Err(status) => status,
+    }
+}
+
+#[sqlx::test]
+async fn team_member_role_is_checked(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+
+    let team = sqlx::query!(
+        "INSERT INTO teams (name, created_by) VALUES ($1, $2) RETURNING id",
+        "Backend",
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let result = sqlx::query!(
+        "INSERT INTO team_members (team_id, user_id, role) VALUES ($1, $2, 'owner')",
+        team.id,
+        user_id
+    )
+    .execute(&pool)
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[sqlx::test]
+async fn deleting_template_keeps_member_copies(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+
+    let team = sqlx::query!(
+        "INSERT INTO teams (name, created_by) VALUES ($1, $2) RETURNING id",
+        "Backend",
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let template = sqlx::query!(
+        "INSERT INTO team_templates (team_id, title, days) VALUES ($1, $2, $3) RETURNING id",
+        team.id,
+        "Stand-up prep",
+        &[1, 2, 3, 4, 5][..]
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let task_id = create_task(&pool, user_id, "Stand-up prep", &[1, 2, 3, 4, 5]).await;
+    sqlx::query!(
+        "UPDATE tasks SET template_id = $1 WHERE id = $2",
+        template.id,
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    sqlx::query!("DELETE FROM team_templates WHERE id = $1", template.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let task = sqlx::query!("SELECT template_id FROM tasks WHERE id = $1", task_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(task.template_id, None);
+}
+
+#[sqlx::test]
+async fn duplicate_member_is_a_conflict(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let admin = create_user(&pool).await;
+    let member = create_user(&pool).await;
+    let team_id = team(&pool, admin).await;
+
+    assert_eq!(
+        add(&pool, team_id, admin, member, "member").await,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        add(&pool, team_id, admin, member, "member").await,
+        StatusCode::CONFLICT
+    );
+    assert_eq!(
+        add(&pool, team_id, admin, member, "owner").await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[sqlx::test]
+async fn last_admin_cannot_leave(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let admin = create_user(&pool).await;
+    let member = create_user(&pool).await;
+    let team_id = team(&pool, admin).await;
+    add(&pool, team_id, admin, member, "member").await;
+
+    assert_eq!(
+        remove(&pool, team_id, admin, admin).await,
+        StatusCode::CONFLICT
+    );
+    assert_eq!(remove(&pool, team_id, member, member).await, StatusCode::OK);
+    assert_eq!(
+        remove(&pool, team_id, admin, member).await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[sqlx::test]
+async fn simultaneous_admin_departures_keep_one_admin(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let first = create_user(&pool).await;
+    let second = create_user(&pool).await;
+    let team_id = team(&pool, first).await;
+    add(&pool, team_id, first, second, "admin").await;
+
+    // Chacun retire l'autre en même temps : un seul départ passe
+    let (a, b) = tokio::join!(
+        remove(&pool, team_id, first, second),
+        remove(&pool, team_id, second, first)
+    );
+    let succeeded = [a, b].iter().filter(|&&s| s == StatusCode::OK).count();
+    assert_eq!(succeeded, 1, "{} / {}", a, b);
+
+    let admins = sqlx::query_scalar!(
+        r#"SELECT count(*) AS "count!" FROM team_members WHERE team_id = $1 AND role = 'admin'"#,
+        team_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(admins, 1);
+}
+
+#[sqlx::test]
+async fn former_members_keep_their_copies_unchanged(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let admin = create_user(&pool).await;
+    let member = create_user(&pool).await;
+    let team_id = team(&pool, admin).await;
+    let template_id = sqlx::query_scalar!(
+        "INSERT INTO team_templates (team_id, title, days) VALUES ($1, 'Revue', '{1}') RETURNING id",
+        team_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    // L'arrivée dans l'équipe copie le modèle, le départ rompt le lien
+    assert_eq!(
+        add(&pool, team_id, admin, member, "member").await,
+        StatusCode::CREATED
+    );
+    let copy = sqlx::query_scalar!(
+        "SELECT id FROM tasks WHERE user_id = $1 AND template_id = $2",
+        member,
+        template_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(remove(&pool, team_id, admin, member).await, StatusCode::OK);
+
+    update_template(
+        Path((team_id, template_id)),
+        State(pool.clone()),
+        Extension(admin),
+        Json(UpdateTemplateRequest {
+            title: Some("Rétrospective".to_string()),
+            days: Some(vec![5]),
+            subtasks: None,
+        }),
+    )
+    .await
+    .unwrap();
+
+    let task = sqlx::query!("SELECT title, template_id FROM tasks WHERE id = $1", copy)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(task.title, "Revue");
+    assert_eq!(task.template_id, None);
+    let days = sqlx::query_scalar!("SELECT day_of_week FROM task_days WHERE task_id = $1", copy)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(days, vec![1]);
+}
@@ -0,0 +1,204 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+use task_manager::api;
+use task_manager::events::{CHANNEL, TaskEvent};
+use tokio::sync::broadcast;
+use tokio_stream::StreamExt;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, create_user, send, token};
+
+/// Prochaine notification du canal, ou `None` si rien n'arrive
+async fn next_event(listener: &mut PgListener) -> Option<TaskEvent> {
+    let notification = tokio::time::timeout(Duration::from_millis(500), listener.recv())
+        .await
+        .ok()?
+        .unwrap();
+    Some(serde_json::from_str(notification.payload()).unwrap())
+}
+
+fn event(user_id: Uuid, entity: &str, task_id: i32) -> TaskEvent {
+    TaskEvent {
+        user_id,
+        entity: entity.to_string(),
+        action: "update".to_string(),
+        task_id,
+        entity_id: task_id.to_string(),
+    }
+}
+
+/// Flux `GET /events` de l'utilisateur, lu événement par événement
+struct EventStream {
+    body: axum::body::BodyDataStream,
+    buffer: String,
+}
+
+impl EventStream {
+    async fn open(pool: &PgPool, events: &broadcast::Sender<TaskEvent>, user_id: Uuid) -> Self {
+        let response = api::router(pool.clone(), events.clone())
+            .oneshot(
+                Request::get("/api/v1/events")
+                    .header("Authorization", format!("Bearer {}", token(user_id)))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        Self {
+            body: response.into_body().into_data_stream(),
+            buffer: String::new(),
+        }
+    }
+
+    /// Type et données du prochain événement (les commentaires de maintien sont ignorés)
+    async fn next(&mut self) -> (String, String) {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let frame: String = self.buffer.drain(..end + 2).collect();
+                let field = |name: &str| {
+                    frame
+                        .lines()
+                        .find_map(|line| line.strip_prefix(name))
+                        .map(str::to_string)
+                };
+                if let (Some(kind), Some(data)) = (field("event: "), field("data: ")) {
+                    return (kind, data);
+                }
+                continue;
+            }
+            let chunk = tokio::time::timeout(Duration::from_secs(1), self.body.next())
+                .await
+                .expect("aucun événement")
+                .unwrap()
+                .unwrap();
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+}
+
+#[sqlx::test]
+async fn changes_are_published_once_committed(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+    let mut listener = PgListener::connect_with(&pool).await.unwrap();
+    listener.listen(CHANNEL).await.unwrap();
+
+    // Tâche, sous-tâche et complétion, chacune avec son type d'élément
+    send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("/tasks/{}", task_id),
+        &[("If-Match", "*")],
+        Some(json!({ "title": "Relire" })),
+    )
+    .await;
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("/tasks/{}/subtasks", task_id),
+        &[("If-Match", "*")],
+        Some(json!({ "title": "Chapitre" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("/tasks/{}/toggle", task_id),
+        &[],
+        None,
+    )
+    .await;
+
+    let mut published = Vec::new();
+    while let Some(event) = next_event(&mut listener).await {
+        assert_eq!(event.user_id, user_id);
+        assert_eq!(event.task_id, task_id);
+        published.push((event.entity, event.entity_id));
+    }
+    let subtask_id = reply.body["subtask_id"].to_string();
+    let today = sqlx::query_scalar!(r#"SELECT current_date AS "today!""#)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        published,
+        [
+            ("task".to_string(), task_id.to_string()),
+            ("subtask".to_string(), subtask_id.clone()),
+            // La bascule coche aussi la sous-tâche
+            ("subtask".to_string(), subtask_id),
+            ("completion".to_string(), format!("{}@{}", task_id, today)),
+        ]
+    );
+
+    // Lot annulé : aucune notification
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        "/batch",
+        &[],
+        Some(json!({
+            "operations": [
+                { "op": "toggle", "task_id": task_id },
+                { "op": "delete", "task_id": task_id },
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(reply.body["committed"], false);
+    assert!(next_event(&mut listener).await.is_none());
+}
+
+#[sqlx::test]
+async fn streams_only_carry_the_users_events(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let (events, _) = broadcast::channel(16);
+    let user_id = create_user(&pool).await;
+    let mut stream = EventStream::open(&pool, &events, user_id).await;
+
+    events.send(event(Uuid::new_v4(), "task", 1)).unwrap();
+    events.send(event(user_id, "completion", 2)).unwrap();
+    events.send(event(user_id, "subtask", 3)).unwrap();
+
+    let (kind, data) = stream.next().await;
+    assert_eq!(kind, "completion");
+    let received: TaskEvent = serde_json::from_str(&data).unwrap();
+    assert_eq!((received.user_id, received.task_id), (user_id, 2));
+    assert_eq!(stream.next().await.0, "subtask");
+}
+
+#[sqlx::test]
+async fn lagging_streams_are_told_to_resync(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let (events, _) = broadcast::channel(2);
+    let user_id = create_user(&pool).await;
+    let mut stream = EventStream::open(&pool, &events, user_id).await;
+
+    // Plus d'événements que le canal n'en garde avant la lecture du flux
+    for task_id in 1..=5 {
+        events.send(event(user_id, "task", task_id)).unwrap();
+    }
+
+    assert_eq!(
+        stream.next().await,
+        ("resync".to_string(), "{}".to_string())
+    );
+    // Puis les derniers événements conservés
+    let (kind, data) = stream.next().await;
+    assert_eq!(kind, "task");
+    assert_eq!(serde_json::from_str::<TaskEvent>(&data).unwrap().task_id, 4);
+}
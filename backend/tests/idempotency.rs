@@ -0,0 +1,253 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::middleware::from_fn_with_state;
+use axum::routing::post;
+use axum::{Extension, Router};
+use serde_json::json;
+use sqlx::PgPool;
+use std::time::Duration;
+use task_manager::idempotency::idempotency;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+use common::{Reply, apply_migrations, create_task, create_user, send};
+
+/// Crée une URL de déclenchement avec cette clé d'idempotence
+async fn create_trigger(pool: &PgPool, user_id: Uuid, task_id: i32, key: &str) -> Reply {
+    send(
+        pool,
+        user_id,
+        "POST",
+        &format!("/tasks/{}/triggers", task_id),
+        &[("Idempotency-Key", key)],
+        Some(json!({ "action": "toggle" })),
+    )
+    .await
+}
+
+async fn count_triggers(pool: &PgPool) -> i64 {
+    sqlx::query_scalar!(r#"SELECT count(*) AS "count!" FROM task_triggers"#)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn count_keys(pool: &PgPool) -> i64 {
+    sqlx::query_scalar!(r#"SELECT count(*) AS "count!" FROM idempotency_keys"#)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn repeated_requests_replay_the_original_response(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1]).await;
+
+    let first = create_trigger(&pool, user_id, task_id, "cle-1").await;
+    assert_eq!(first.status, StatusCode::CREATED);
+    assert!(first.headers.get("idempotent-replayed").is_none());
+
+    // Même jeton renvoyé, sans seconde URL créée
+    let again = create_trigger(&pool, user_id, task_id, "cle-1").await;
+    assert_eq!(again.status, StatusCode::CREATED);
+    assert_eq!(again.headers["idempotent-replayed"], "true");
+    assert_eq!(again.headers["content-type"], "application/json");
+    assert_eq!(again.body, first.body);
+    assert_eq!(count_triggers(&pool).await, 1);
+
+    // Les clés sont propres à chaque utilisateur
+    let other = create_user(&pool).await;
+    let other_task = create_task(&pool, other, "Lecture", &[1]).await;
+    let reply = create_trigger(&pool, other, other_task, "cle-1").await;
+    assert!(reply.headers.get("idempotent-replayed").is_none());
+    assert_eq!(count_triggers(&pool).await, 2);
+
+    // Clé invalide
+    let long_key = "x".repeat(256);
+    let reply = create_trigger(&pool, user_id, task_id, &long_key).await;
+    assert_eq!(reply.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn key_reused_for_another_request_is_rejected(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1]).await;
+    let other_task = create_task(&pool, user_id, "Lecture", &[1]).await;
+
+    create_trigger(&pool, user_id, task_id, "cle-1").await;
+
+    // Autre corps
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("/tasks/{}/triggers", task_id),
+        &[("Idempotency-Key", "cle-1")],
+        Some(json!({ "action": "complete" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Autre chemin
+    let reply = create_trigger(&pool, user_id, other_task, "cle-1").await;
+    assert_eq!(reply.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(count_triggers(&pool).await, 1);
+}
+
+#[sqlx::test]
+async fn claim_in_progress_conflicts_until_its_lease_ends(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1]).await;
+
+    // Réponse jamais enregistrée : la requête d'origine paraît toujours en cours
+    create_trigger(&pool, user_id, task_id, "cle-1").await;
+    sqlx::query!("UPDATE idempotency_keys SET status = NULL, headers = NULL, body = NULL")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let reply = create_trigger(&pool, user_id, task_id, "cle-1").await;
+    assert_eq!(reply.status, StatusCode::CONFLICT);
+
+    // Passé le bail, seule la même requête peut reprendre la clé
+    sqlx::query!("UPDATE idempotency_keys SET claimed_at = claimed_at - interval '2 minutes'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("/tasks/{}/triggers", task_id),
+        &[("Idempotency-Key", "cle-1")],
+        Some(json!({ "action": "complete" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let retried = create_trigger(&pool, user_id, task_id, "cle-1").await;
+    assert_eq!(retried.status, StatusCode::CREATED);
+    assert!(retried.headers.get("idempotent-replayed").is_none());
+    assert_eq!(count_triggers(&pool).await, 2);
+
+    let replayed = create_trigger(&pool, user_id, task_id, "cle-1").await;
+    assert_eq!(replayed.headers["idempotent-replayed"], "true");
+    assert_eq!(replayed.body, retried.body);
+}
+
+#[sqlx::test]
+async fn interrupted_requests_release_their_key(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+
+    let app = Router::new()
+        .route("/lent", post(std::future::pending::<StatusCode>))
+        .route(
+            "/panique",
+            post(|| async {
+                panic!("gestionnaire interrompu");
+                #[allow(unreachable_code)]
+                StatusCode::OK
+            }),
+        )
+        .layer(from_fn_with_state(pool.clone(), idempotency))
+        .layer(Extension(user_id));
+    let request = |uri: &str| {
+        Request::post(uri)
+            .header("Idempotency-Key", uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // Client parti pendant l'exécution
+    let cancelled = tokio::time::timeout(
+        Duration::from_millis(500),
+        app.clone().oneshot(request("/lent")),
+    );
+    assert!(cancelled.await.is_err());
+    // Panique du gestionnaire
+    let panicked = tokio::spawn(app.oneshot(request("/panique"))).await;
+    assert!(panicked.is_err());
+
+    // La libération se fait en tâche de fond
+    for _ in 0..50 {
+        if count_keys(&pool).await == 0 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("clés non libérées");
+}
+
+#[sqlx::test]
+async fn trigger_urls_accept_idempotency_keys(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1]).await;
+    let created = create_trigger(&pool, user_id, task_id, "creation").await;
+    let trigger_id = created.body["id"].as_i64().unwrap();
+    let token = created.body["token"].as_str().unwrap();
+
+    // Un second `toggle` rejoué ne décoche pas l'occurrence
+    let uri = format!("/triggers/{}", token);
+    let call = || {
+        send(
+            &pool,
+            Uuid::nil(),
+            "POST",
+            &uri,
+            &[("Idempotency-Key", "bouton")],
+            None,
+        )
+    };
+    let first = call().await;
+    assert_eq!(first.status, StatusCode::OK);
+    assert_eq!(first.body["completed"], true);
+    let again = call().await;
+    assert_eq!(again.headers["idempotent-replayed"], "true");
+    assert_eq!(again.body, first.body);
+
+    // Clé rattachée au propriétaire, sans conserver le jeton
+    let stored = sqlx::query!("SELECT user_id, path FROM idempotency_keys WHERE key = 'bouton'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored.user_id, user_id);
+    assert_eq!(stored.path, format!("/triggers/{}", trigger_id));
+}
+
+#[sqlx::test]
+async fn auth_routes_ignore_idempotency_keys(pool: PgPool) {
+    apply_migrations(&pool).await;
+
+    let register = || {
+        send(
+            &pool,
+            Uuid::nil(),
+            "POST",
+            "/auth/register",
+            &[("Idempotency-Key", "inscription")],
+            Some(json!({ "username": "alice", "password": "motdepasse-solide" })),
+        )
+    };
+    assert_eq!(register().await.status, StatusCode::CREATED);
+    let again = register().await;
+    assert_eq!(again.status, StatusCode::CONFLICT);
+    assert!(again.headers.get("idempotent-replayed").is_none());
+
+    let login = send(
+        &pool,
+        Uuid::nil(),
+        "POST",
+        "/auth/login",
+        &[("Idempotency-Key", "connexion")],
+        Some(json!({ "username": "alice", "password": "motdepasse-solide" })),
+    )
+    .await;
+    assert_eq!(login.status, StatusCode::OK);
+    assert_eq!(count_keys(&pool).await, 0);
+}
@@ -0,0 +1,209 @@
+use axum::http::StatusCode;
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, backdate_task, create_task, create_user, send};
+
+const ALL_DAYS: [i32; 7] = [1, 2, 3, 4, 5, 6, 7];
+
+async fn list(pool: &PgPool, user_id: Uuid, query: &str) -> (StatusCode, Value) {
+    let reply = send(
+        pool,
+        user_id,
+        "GET",
+        &format!("/tasks/all?{}", query),
+        &[],
+        None,
+    )
+    .await;
+    (reply.status, reply.body)
+}
+
+fn titles(page: &Value) -> Vec<String> {
+    page["tasks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+/// Parcourt toutes les pages et renvoie les titres dans l'ordre reçu
+async fn walk(pool: &PgPool, user_id: Uuid, query: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let query = match &cursor {
+            Some(c) => format!("{}&cursor={}", query, c),
+            None => query.to_string(),
+        };
+        let (status, page) = list(pool, user_id, &query).await;
+        assert_eq!(status, StatusCode::OK);
+        seen.extend(titles(&page));
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => return seen,
+        }
+    }
+}
+
+#[sqlx::test]
+async fn cursor_pages_cover_every_task_once(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    for title in [
+        "delta", "Alpha", "charlie", "Bravo", "alpha", "echo", "Foxtrot",
+    ] {
+        create_task(&pool, user_id, title, &[1]).await;
+    }
+
+    // Titres sans tenir compte de la casse, à égalité par ordre de création
+    let expected = [
+        "Alpha", "alpha", "Bravo", "charlie", "delta", "echo", "Foxtrot",
+    ];
+    assert_eq!(walk(&pool, user_id, "sort=title&limit=3").await, expected);
+    let mut reversed = expected.to_vec();
+    reversed.reverse();
+    assert_eq!(
+        walk(&pool, user_id, "sort=title&order=desc&limit=2").await,
+        reversed
+    );
+    // Par défaut : ordre de création
+    assert_eq!(
+        walk(&pool, user_id, "limit=4").await,
+        [
+            "delta", "Alpha", "charlie", "Bravo", "alpha", "echo", "Foxtrot"
+        ]
+    );
+
+    // Une page pleine, le total des tâches filtrées, puis la dernière page sans curseur
+    let (_, first) = list(&pool, user_id, "sort=title&limit=3").await;
+    assert_eq!(titles(&first), ["Alpha", "alpha", "Bravo"]);
+    assert_eq!(first["total"], 7);
+    let cursor = first["next_cursor"].as_str().unwrap().to_string();
+
+    // Les changements faits avant le curseur ne décalent pas la suite
+    create_task(&pool, user_id, "Aardvark", &[1]).await;
+    let (_, second) = list(
+        &pool,
+        user_id,
+        &format!("sort=title&limit=3&cursor={}", cursor),
+    )
+    .await;
+    assert_eq!(titles(&second), ["charlie", "delta", "echo"]);
+    assert_eq!(second["total"], 8);
+
+    let (_, filtered) = list(&pool, user_id, "sort=title&q=ALP&limit=1").await;
+    assert_eq!(titles(&filtered), ["Alpha"]);
+    assert_eq!(filtered["total"], 2);
+    let cursor = filtered["next_cursor"].as_str().unwrap().to_string();
+    let (_, last) = list(
+        &pool,
+        user_id,
+        &format!("sort=title&q=ALP&limit=1&cursor={}", cursor),
+    )
+    .await;
+    assert_eq!(titles(&last), ["alpha"]);
+    assert_eq!(last["next_cursor"], Value::Null);
+}
+
+#[sqlx::test]
+async fn tasks_sort_by_priority_and_completion_rate(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let half = create_task(&pool, user_id, "Half", &ALL_DAYS).await;
+    let full = create_task(&pool, user_id, "Full", &ALL_DAYS).await;
+    create_task(&pool, user_id, "Never", &[]).await;
+    backdate_task(&pool, half, 9).await;
+    backdate_task(&pool, full, 9).await;
+
+    // 10 jours prévus : 5 faits pour « Half », tous pour « Full »
+    sqlx::query!(
+        r#"
+        INSERT INTO task_completions (task_id, date, completed)
+        SELECT $1::int, current_date - n, true FROM generate_series(0, 9) AS n
+        UNION ALL
+        SELECT $2::int, current_date - n, true FROM generate_series(1, 5) AS n
+        "#,
+        full,
+        half
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let (_, page) = list(&pool, user_id, "sort=completion_rate").await;
+    assert_eq!(titles(&page), ["Never", "Half", "Full"]);
+    let rates: Vec<_> = page["tasks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["completion_rate"].clone())
+        .collect();
+    assert_eq!(rates, [Value::Null, 50.0.into(), 100.0.into()]);
+    assert_eq!(
+        walk(&pool, user_id, "sort=completion_rate&order=desc&limit=1").await,
+        ["Full", "Half", "Never"]
+    );
+
+    // Priorité du jour, 0 sans occurrence aujourd'hui
+    sqlx::query!(
+        "UPDATE task_completions SET priority = 5 WHERE task_id = $1 AND date = current_date",
+        full
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO task_completions (task_id, date, completed, priority) VALUES ($1, current_date, false, 2)",
+        half
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        walk(&pool, user_id, "sort=priority&order=desc&limit=2").await,
+        ["Full", "Half", "Never"]
+    );
+}
+
+#[sqlx::test]
+async fn invalid_cursors_and_parameters_are_refused(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    for title in ["Read", "Write", "Run"] {
+        create_task(&pool, user_id, title, &[1]).await;
+    }
+    let (_, page) = list(&pool, user_id, "sort=title&limit=1").await;
+    let cursor = page["next_cursor"].as_str().unwrap().to_string();
+
+    for query in [
+        "cursor=zz".to_string(),
+        "cursor=abc".to_string(),
+        "cursor=7b7d".to_string(),
+        // Curseur d'une autre liste
+        format!("sort=created_at&limit=1&cursor={}", cursor),
+        format!("sort=title&order=desc&limit=1&cursor={}", cursor),
+        "sort=due_date".to_string(),
+        "order=up".to_string(),
+        "days=0,8".to_string(),
+        "status=deleted".to_string(),
+    ] {
+        let (status, _) = list(&pool, user_id, &query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+
+    // Limite ramenée dans les bornes
+    let (_, page) = list(&pool, user_id, "limit=0").await;
+    assert_eq!(titles(&page).len(), 1);
+    let (status, page) = list(
+        &pool,
+        user_id,
+        &format!("sort=title&limit=1&cursor={}", cursor),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(titles(&page), ["Run"]);
+}
@@ -0,0 +1,384 @@
+use aes_gcm::Aes128Gcm;
+use aes_gcm::aead::{Aead, KeyInit, generic_array::GenericArray};
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use hkdf::Hkdf;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use sha2::Sha256;
+use sqlx::PgPool;
+use task_manager::notifier::{
+    Notification, Notifier, PushSubscription, Recipient, SmtpConfig, SmtpNotifier, WebhookNotifier,
+};
+use task_manager::reminders;
+use task_manager::webpush::{VapidKey, WebPushNotifier};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, create_user, send};
+
+fn notification() -> Notification {
+    Notification {
+        id: 1,
+        user_id: Uuid::new_v4(),
+        kind: "reminder".to_string(),
+        task_id: Some(7),
+        date: Utc::now().date_naive(),
+        title: "Rappel : Lire".to_string(),
+        body: "Pas encore faite aujourd'hui".to_string(),
+        created_at: Utc::now(),
+    }
+}
+
+/// Serveur HTTP local qui transmet les en-têtes et le corps de chaque POST reçu
+async fn stub_endpoint() -> (String, mpsc::UnboundedReceiver<(HeaderMap, Bytes)>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let app = Router::new()
+        .route(
+            "/*path",
+            post(
+                |State(sender): State<mpsc::UnboundedSender<(HeaderMap, Bytes)>>,
+                 headers: HeaderMap,
+                 body: Bytes| async move {
+                    sender.send((headers, body)).unwrap();
+                    "ok"
+                },
+            ),
+        )
+        .with_state(sender);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (url, receiver)
+}
+
+/// Serveur SMTP minimal qui accepte un message et transmet son contenu
+async fn mock_smtp() -> (u16, mpsc::UnboundedReceiver<String>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = socket.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 mock ESMTP\r\n").await.unwrap();
+
+        let mut data: Option<String> = None;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if let Some(message) = data.as_mut() {
+                if line == "." {
+                    sender.send(data.take().unwrap()).unwrap();
+                    writer.write_all(b"250 OK\r\n").await.unwrap();
+                } else {
+                    message.push_str(&line);
+                    message.push('\n');
+                }
+                continue;
+            }
+            let reply: &[u8] = match line.get(..4).unwrap_or("").to_ascii_uppercase().as_str() {
+                "EHLO" | "HELO" => b"250 mock\r\n",
+                "DATA" => {
+                    data = Some(String::new());
+                    b"354 go ahead\r\n"
+                }
+                "QUIT" => {
+                    writer.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                }
+                _ => b"250 OK\r\n",
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+    });
+
+    (port, receiver)
+}
+
+#[sqlx::test]
+async fn due_reminders_are_sent_once_outside_quiet_hours(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+    let done_id = create_task(&pool, user_id, "Write", &[1, 2, 3, 4, 5, 6, 7]).await;
+
+    // Rappels à minuit, créés la veille : dus dès maintenant
+    sqlx::query!(
+        r#"
+        INSERT INTO task_reminders (task_id, remind_at, created_at)
+        SELECT unnest($1::int[]), '00:00', now() - interval '1 day'
+        "#,
+        &[task_id, done_id]
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO task_completions (task_id, date, completed) VALUES ($1, current_date, true)",
+        done_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // Heures calmes sur toute la journée : rien ne part
+    sqlx::query!(
+        r#"
+        INSERT INTO notification_settings (user_id, quiet_start, quiet_end)
+        VALUES ($1, '00:00', '23:59:59.999999')
+        "#,
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert!(
+        reminders::claim_due_reminders(&pool)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    sqlx::query!(
+        "UPDATE notification_settings SET quiet_start = NULL, quiet_end = NULL WHERE user_id = $1",
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // Seule la tâche non complétée reçoit son rappel, une seule fois
+    let due = reminders::claim_due_reminders(&pool).await.unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].task_id, Some(task_id));
+    assert!(
+        reminders::claim_due_reminders(&pool)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[sqlx::test]
+async fn webhook_and_push_reach_stub_endpoints(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let (url, mut received) = stub_endpoint().await;
+    let notification = notification();
+
+    // Navigateur abonné : clé de réception et secret d'authentification
+    let ua_secret = SecretKey::random(&mut rand::rngs::OsRng);
+    let ua_public = ua_secret.public_key().to_encoded_point(false);
+    let auth_secret = [7u8; 16];
+    let recipient = Recipient {
+        webhook_url: Some(format!("{}/hook", url)),
+        push_subscriptions: vec![PushSubscription {
+            id: 1,
+            endpoint: format!("{}/push/abc", url),
+            p256dh: URL_SAFE_NO_PAD.encode(ua_public.as_bytes()),
+            auth: URL_SAFE_NO_PAD.encode(auth_secret),
+        }],
+        ..Default::default()
+    };
+
+    WebhookNotifier::local()
+        .send(&recipient, &notification)
+        .await
+        .unwrap();
+    let (_, body) = received.recv().await.unwrap();
+    let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payload["title"], "Rappel : Lire");
+    assert_eq!(payload["task_id"], 7);
+
+    let vapid = VapidKey::from_base64(&URL_SAFE_NO_PAD.encode([1u8; 32])).unwrap();
+    let vapid_public = vapid.public_key().to_string();
+    WebPushNotifier::local(pool, vapid, "mailto:test@example.com".to_string())
+        .send(&recipient, &notification)
+        .await
+        .unwrap();
+    let (headers, body) = received.recv().await.unwrap();
+    assert_eq!(headers["content-encoding"], "aes128gcm");
+
+    // Jeton VAPID signé par la clé annoncée, pour l'origine du service push
+    let authorization = headers["authorization"].to_str().unwrap();
+    let (token, key) = authorization
+        .strip_prefix("vapid t=")
+        .and_then(|rest| rest.split_once(", k="))
+        .unwrap();
+    assert_eq!(key, vapid_public);
+    let (unsigned, signature) = token.rsplit_once('.').unwrap();
+    let verifying_key =
+        VerifyingKey::from_sec1_bytes(&URL_SAFE_NO_PAD.decode(key).unwrap()).unwrap();
+    let signature = Signature::from_slice(&URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap();
+    verifying_key
+        .verify(unsigned.as_bytes(), &signature)
+        .unwrap();
+    let claims: serde_json::Value = serde_json::from_slice(
+        &URL_SAFE_NO_PAD
+            .decode(unsigned.split('.').nth(1).unwrap())
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(claims["aud"], url);
+
+    // Déchiffrement côté navigateur (RFC 8291)
+    let (salt, rest) = body.split_at(16);
+    let key_length = rest[4] as usize;
+    let (as_public, ciphertext) = rest[5..].split_at(key_length);
+    let as_public = PublicKey::from_sec1_bytes(as_public).unwrap();
+    let shared = p256::ecdh::diffie_hellman(ua_secret.to_nonzero_scalar(), as_public.as_affine());
+
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(ua_public.as_bytes());
+    key_info.extend_from_slice(as_public.to_encoded_point(false).as_bytes());
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&auth_secret), shared.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .unwrap();
+    let hkdf = Hkdf::<Sha256>::new(Some(salt), &ikm);
+    let mut cek = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .unwrap();
+    hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .unwrap();
+    let plaintext = Aes128Gcm::new(GenericArray::from_slice(&cek))
+        .decrypt(GenericArray::from_slice(&nonce), ciphertext)
+        .unwrap();
+
+    assert_eq!(plaintext.last(), Some(&2));
+    let payload: serde_json::Value =
+        serde_json::from_slice(&plaintext[..plaintext.len() - 1]).unwrap();
+    assert_eq!(payload["body"], "Pas encore faite aujourd'hui");
+}
+
+#[tokio::test]
+async fn email_reaches_mock_smtp_server() {
+    let (port, mut received) = mock_smtp().await;
+    let smtp = SmtpNotifier::new(SmtpConfig {
+        host: "127.0.0.1".to_string(),
+        port: Some(port),
+        tls: "none".to_string(),
+        username: None,
+        password: None,
+        from: "Task Manager <noreply@example.com>".to_string(),
+    })
+    .unwrap();
+    let recipient = Recipient {
+        username: "alice".to_string(),
+        email: Some("alice@example.com".to_string()),
+        ..Default::default()
+    };
+
+    smtp.send(&recipient, &notification()).await.unwrap();
+
+    let message = received.recv().await.unwrap();
+    assert!(message.contains("To: alice <alice@example.com>"));
+    assert!(message.contains("Subject: Rappel : Lire"));
+}
+
+#[sqlx::test]
+async fn notifications_never_reach_internal_addresses(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let (url, mut received) = stub_endpoint().await;
+    let recipient = Recipient {
+        webhook_url: Some(format!("{}/hook", url)),
+        push_subscriptions: vec![PushSubscription {
+            id: 1,
+            endpoint: format!("{}/push/abc", url),
+            p256dh: URL_SAFE_NO_PAD.encode(
+                SecretKey::random(&mut rand::rngs::OsRng)
+                    .public_key()
+                    .to_encoded_point(false)
+                    .as_bytes(),
+            ),
+            auth: URL_SAFE_NO_PAD.encode([7u8; 16]),
+        }],
+        ..Default::default()
+    };
+
+    // Le serveur de test écoute sur 127.0.0.1 : hors d'atteinte des envois réels
+    let error = WebhookNotifier::new()
+        .send(&recipient, &notification())
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "adresse non publique");
+    let vapid = VapidKey::from_base64(&URL_SAFE_NO_PAD.encode([1u8; 32])).unwrap();
+    assert!(
+        WebPushNotifier::new(pool, vapid, "mailto:test@example.com".to_string())
+            .send(&recipient, &notification())
+            .await
+            .is_err()
+    );
+    assert!(received.try_recv().is_err());
+}
+
+#[sqlx::test]
+async fn notification_targets_must_be_public(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+
+    let settings = |url: &str| {
+        send(
+            &pool,
+            user_id,
+            "PUT",
+            "/notifications/settings",
+            &[],
+            Some(serde_json::json!({ "webhook_url": url })),
+        )
+    };
+    for url in [
+        "http://localhost:8080/hook",
+        "http://127.0.0.1/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://[::1]/hook",
+        "ftp://example.com/hook",
+    ] {
+        assert_eq!(
+            settings(url).await.status,
+            StatusCode::BAD_REQUEST,
+            "{}",
+            url
+        );
+    }
+    assert_eq!(
+        settings("https://hooks.example.com/notify").await.status,
+        StatusCode::NO_CONTENT
+    );
+
+    let subscribe = |endpoint: &str| {
+        send(
+            &pool,
+            user_id,
+            "POST",
+            "/notifications/push/subscriptions",
+            &[],
+            Some(serde_json::json!({
+                "endpoint": endpoint,
+                "keys": { "p256dh": "cle", "auth": "secret" }
+            })),
+        )
+    };
+    assert_eq!(
+        subscribe("http://10.0.0.5/push").await.status,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        subscribe("https://push.example.com/abc").await.status,
+        StatusCode::CREATED
+    );
+}
@@ -0,0 +1,161 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use std::collections::BTreeSet;
+use task_manager::openapi::{ApiDoc, DOCS_PATH, SPEC_PATH, docs_routes};
+use tower::ServiceExt;
+use utoipa::OpenApi;
+
+const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+/// Préfixe ajouté par `api::v1` aux routeurs montés à part
+fn prefix(router: &str) -> &'static str {
+    match router {
+        "auth_routes" => "/auth",
+        "public_trigger_routes" => "/triggers",
+        _ => "",
+    }
+}
+
+/// Routes déclarées par les `.route(...)` des modules `routes*.rs`, relatives à `/api`
+/// et au format OpenAPI (`/tasks/{id}`)
+fn declared_routes() -> BTreeSet<(String, String)> {
+    let mut declared = BTreeSet::new();
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if !name.starts_with("routes") || !name.ends_with(".rs") {
+            continue;
+        }
+        let source = std::fs::read_to_string(&path).unwrap();
+
+        let mut router = "";
+        for chunk in source.split(".route(") {
+            if chunk.trim_start().starts_with('"') {
+                declared.extend(parse_route(chunk, prefix(router)));
+            }
+            // Les routes suivantes appartiennent à la dernière fonction `pub fn` du morceau
+            if let Some(pos) = chunk.rfind("pub fn ") {
+                router = chunk[pos + 7..].split('(').next().unwrap();
+            }
+        }
+    }
+
+    declared
+}
+
+/// Méthodes et chemin d'un appel `.route("/tasks/:id", get(...).post(...))`, dont `chunk`
+/// est le texte qui suit `.route(`
+fn parse_route(chunk: &str, prefix: &str) -> Vec<(String, String)> {
+    let mut depth = 1;
+    let end = chunk
+        .char_indices()
+        .find(|&(_, c)| {
+            depth += match c {
+                '(' => 1,
+                ')' => -1,
+                _ => 0,
+            };
+            depth == 0
+        })
+        .map(|(end, _)| end)
+        .unwrap();
+    let call = &chunk[..end];
+
+    let route = call
+        .split('"')
+        .nth(1)
+        .unwrap()
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(param) => format!("{{{}}}", param),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    METHODS
+        .iter()
+        .filter(|method| {
+            // `get(` mais pas `get_tags(`
+            call.match_indices(&format!("{}(", method))
+                .any(|(pos, _)| !call[..pos].ends_with(|c: char| c.is_alphanumeric() || c == '_'))
+        })
+        .map(|method| (method.to_string(), format!("{}{}", prefix, route)))
+        .collect()
+}
+
+/// Routes décrites par la spécification
+fn documented_routes() -> BTreeSet<(String, String)> {
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    let mut documented = BTreeSet::new();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for method in METHODS {
+            if item.get(method).is_some() {
+                documented.insert((method.to_string(), path.clone()));
+            }
+        }
+    }
+    documented
+}
+
+#[test]
+fn every_route_is_documented() {
+    let declared = declared_routes();
+    let documented = documented_routes();
+    assert!(declared.len() > 50, "routes non trouvées : {:?}", declared);
+
+    let missing: Vec<_> = declared.difference(&documented).collect();
+    assert!(
+        missing.is_empty(),
+        "routes absentes de la spécification OpenAPI : {:?}",
+        missing
+    );
+    let unknown: Vec<_> = documented.difference(&declared).collect();
+    assert!(
+        unknown.is_empty(),
+        "routes documentées mais inexistantes : {:?}",
+        unknown
+    );
+}
+
+#[test]
+fn public_routes_need_no_token() {
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    assert_eq!(spec["security"][0]["bearer"], serde_json::json!([]));
+    assert_eq!(
+        spec["paths"]["/auth/login"]["post"]["security"],
+        serde_json::json!([{}])
+    );
+    assert_eq!(
+        spec["paths"]["/tasks"]["get"].get("security"),
+        None,
+        "les routes authentifiées héritent du schéma global"
+    );
+}
+
+#[tokio::test]
+async fn spec_and_docs_are_served() {
+    let response = docs_routes()
+        .oneshot(Request::get(SPEC_PATH).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let spec: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(spec["components"]["schemas"]["CreateTaskRequest"].is_object());
+
+    let response = docs_routes()
+        .oneshot(
+            Request::get(format!("{}/", DOCS_PATH))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,251 @@
+use axum::http::StatusCode;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use task_manager::stats;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, backdate_task, create_task, create_user, send};
+
+const ALL_DAYS: [i32; 7] = [1, 2, 3, 4, 5, 6, 7];
+
+/// Exécute une requête de préparation
+async fn run(pool: &PgPool, sql: &str, user_id: Uuid) {
+    sqlx::query(sql).bind(user_id).execute(pool).await.unwrap();
+}
+
+/// Totaux quotidiens enregistrés de l'utilisateur sur les 10 derniers jours
+async fn stored_days(pool: &PgPool, user_id: Uuid) -> Vec<(NaiveDate, i32, i32, i32)> {
+    sqlx::query_as(
+        r#"
+        SELECT date, scheduled_tasks, done_tasks, completed_tasks
+        FROM user_daily_stats
+        WHERE user_id = $1 AND date > current_date - 10
+        ORDER BY date
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn rollups_match_a_recompute_from_raw_data(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let read = create_task(&pool, user_id, "Read", &ALL_DAYS).await;
+    let write = create_task(&pool, user_id, "Write", &ALL_DAYS).await;
+    backdate_task(&pool, read, 10).await;
+    backdate_task(&pool, write, 10).await;
+
+    // Journées déjà enregistrées par le passage horaire
+    run(
+        &pool,
+        "SELECT ensure_daily_stats($1, current_date - 9, current_date)",
+        user_id,
+    )
+    .await;
+
+    // Modifications suivies par les déclencheurs
+    for sql in [
+        "INSERT INTO task_completions (task_id, date, completed)
+         SELECT id, current_date - 3, true FROM tasks WHERE user_id = $1",
+        "INSERT INTO task_completions (task_id, date, completed, skipped)
+         SELECT id, current_date - 4, false, true FROM tasks WHERE user_id = $1 AND title = 'Read'",
+        "INSERT INTO task_completions (task_id, date, completed)
+         SELECT id, current_date - 5, true FROM tasks WHERE user_id = $1 AND title = 'Write'",
+        "DELETE FROM task_completions
+         WHERE date = current_date - 3 AND task_id IN (SELECT id FROM tasks WHERE user_id = $1 AND title = 'Write')",
+        "INSERT INTO vacations (user_id, start_date, end_date) VALUES ($1, current_date - 2, current_date - 1)",
+        "INSERT INTO subtasks (task_id, title, completed)
+         SELECT id, 'Chapitre', true FROM tasks WHERE user_id = $1 AND title = 'Write'",
+        "INSERT INTO task_completions (task_id, date, completed)
+         SELECT id, current_date, true FROM tasks WHERE user_id = $1 AND title = 'Read'",
+    ] {
+        run(&pool, sql, user_id).await;
+    }
+
+    // Bilans des tâches : identiques au recalcul
+    let differences: i64 = sqlx::query_scalar(
+        r#"
+        WITH stored AS (
+            SELECT * FROM task_daily_stats WHERE user_id = $1
+        ), computed AS (
+            SELECT * FROM compute_task_daily_stats($1, NULL, current_date - 9, current_date)
+        )
+        SELECT COUNT(*) FROM (
+            (SELECT * FROM stored EXCEPT SELECT * FROM computed)
+            UNION ALL
+            (SELECT * FROM computed EXCEPT SELECT * FROM stored)
+        ) d
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(differences, 0);
+
+    // Totaux de l'utilisateur : la somme du recalcul
+    let recomputed: Vec<(NaiveDate, i32, i32, i32)> = sqlx::query_as(
+        r#"
+        SELECT d.day::date,
+               COUNT(s.task_id) FILTER (WHERE s.scheduled)::int,
+               COUNT(s.task_id) FILTER (WHERE s.scheduled AND s.completed)::int,
+               COUNT(s.task_id) FILTER (WHERE s.completed)::int
+        FROM generate_series(current_date - 9, current_date, interval '1 day') AS d(day)
+        LEFT JOIN compute_task_daily_stats($1, NULL, current_date - 9, current_date) s
+            ON s.date = d.day::date
+        GROUP BY d.day
+        ORDER BY d.day
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let stored = stored_days(&pool, user_id).await;
+    assert_eq!(stored, recomputed);
+
+    // Les cas couverts apparaissent bien : complétion, saut, vacances
+    let counts: Vec<_> = stored.iter().map(|&(_, s, d, c)| (s, d, c)).collect();
+    assert_eq!(counts[6], (2, 1, 1)); // J-3
+    assert_eq!(counts[5], (1, 0, 0)); // J-4 : « Read » sautée
+    assert_eq!(counts[7], (0, 0, 0)); // J-2 : vacances
+    assert_eq!(counts[9], (2, 1, 1)); // aujourd'hui
+}
+
+#[sqlx::test]
+async fn rebuild_fixes_drift_and_keeps_recorded_state(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let read = create_task(&pool, user_id, "Read", &ALL_DAYS).await;
+    let write = create_task(&pool, user_id, "Write", &ALL_DAYS).await;
+    backdate_task(&pool, read, 5).await;
+    backdate_task(&pool, write, 5).await;
+    run(
+        &pool,
+        "SELECT ensure_daily_stats($1, current_date - 5, current_date)",
+        user_id,
+    )
+    .await;
+
+    // « Read » était archivée à J-2, « Write » a reçu une sous-tâche aujourd'hui
+    sqlx::query!(
+        "UPDATE task_daily_stats SET active = false, scheduled = false WHERE task_id = $1 AND date = current_date - 2",
+        read
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO subtasks (task_id, title) VALUES ($1, 'Relire')",
+        write
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // Écarts : totaux faussés, complétion manquée, journée perdue
+    sqlx::query!(
+        "UPDATE user_daily_stats SET done_tasks = 99 WHERE user_id = $1 AND date = current_date - 1",
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO task_completions (task_id, date, completed) VALUES ($1, current_date - 3, true)",
+        read
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "UPDATE task_daily_stats SET completed = false WHERE task_id = $1 AND date = current_date - 3",
+        read
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "DELETE FROM user_daily_stats WHERE user_id = $1 AND date = current_date - 4",
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    stats::rebuild_recent(&pool, 7).await.unwrap();
+
+    let counts: Vec<_> = stored_days(&pool, user_id)
+        .await
+        .into_iter()
+        .map(|(_, s, d, c)| (s, d, c))
+        .collect();
+    assert_eq!(
+        counts,
+        vec![
+            (0, 0, 0), // J-6, avant la création des tâches
+            (2, 0, 0), // J-5
+            (2, 0, 0), // J-4, totaux perdus puis recalculés
+            (2, 1, 1), // J-3, complétion rattrapée
+            (1, 0, 0), // J-2, « Read » toujours archivée ce jour-là
+            (2, 0, 0), // J-1, totaux corrigés
+            (2, 0, 0),
+        ]
+    );
+
+    // La sous-tâche ajoutée aujourd'hui ne modifie pas les jours passés
+    let subtasks: Vec<(NaiveDate, i32)> = sqlx::query_as(
+        "SELECT date, subtasks_total FROM task_daily_stats WHERE task_id = $1 ORDER BY date",
+    )
+    .bind(write)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let totals: Vec<i32> = subtasks.into_iter().map(|(_, total)| total).collect();
+    assert_eq!(totals, vec![0, 0, 0, 0, 0, 1]);
+}
+
+#[sqlx::test]
+async fn totals_cover_the_whole_history(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &ALL_DAYS).await;
+    backdate_task(&pool, task_id, 60).await;
+    sqlx::query!(
+        "INSERT INTO task_completions (task_id, date, completed) VALUES ($1, current_date - 45, true)",
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "DELETE FROM user_daily_stats WHERE user_id = $1 AND date < current_date - 6",
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let summary = || async {
+        let reply = send(&pool, user_id, "GET", "/stats?days=7", &[], None).await;
+        assert_eq!(reply.status, StatusCode::OK);
+        reply.body["summary"].clone()
+    };
+    // Seule la période lue a été calculée
+    assert_eq!(summary().await["total_scheduled_days"], 7);
+
+    // Rattrapage fait par la migration 023
+    run(
+        &pool,
+        "SELECT ensure_daily_stats(id, stats_start_date(id), current_date) FROM users WHERE id = $1",
+        user_id,
+    )
+    .await;
+    let summary = summary().await;
+    assert_eq!(summary["total_scheduled_days"], 61);
+    assert_eq!(summary["total_completed_ever"], 1);
+}
@@ -0,0 +1,201 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+use common::{Reply, apply_migrations, create_task, create_user, send};
+
+async fn version(pool: &PgPool, task_id: i32) -> String {
+    let version = sqlx::query_scalar!("SELECT version FROM tasks WHERE id = $1", task_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    format!("\"{}\"", version)
+}
+
+async fn add_subtask(pool: &PgPool, user_id: Uuid, task_id: i32, if_match: Option<&str>) -> Reply {
+    let headers: Vec<_> = if_match.map(|v| ("If-Match", v)).into_iter().collect();
+    send(
+        pool,
+        user_id,
+        "POST",
+        &format!("/tasks/{}/subtasks", task_id),
+        &headers,
+        Some(json!({ "title": "Étirements" })),
+    )
+    .await
+}
+
+#[sqlx::test]
+async fn creating_a_subtask_checks_the_task_version(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3]).await;
+
+    assert_eq!(
+        add_subtask(&pool, user_id, task_id, None).await.status,
+        StatusCode::PRECONDITION_REQUIRED
+    );
+
+    // Deux appareils partent de la même version : le second reçoit 412 et la tâche à jour
+    let seen = version(&pool, task_id).await;
+    let first = add_subtask(&pool, user_id, task_id, Some(&seen)).await;
+    assert_eq!(first.status, StatusCode::OK);
+    assert_eq!(
+        format!("\"{}\"", first.body["version"]),
+        version(&pool, task_id).await
+    );
+
+    let second = add_subtask(&pool, user_id, task_id, Some(&seen)).await;
+    assert_eq!(second.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(
+        second.headers["etag"],
+        version(&pool, task_id).await.as_str()
+    );
+    assert_eq!(second.body["subtasks"].as_array().unwrap().len(), 1);
+
+    let current = version(&pool, task_id).await;
+    let retried = add_subtask(&pool, user_id, task_id, Some(&current)).await;
+    assert_eq!(retried.status, StatusCode::OK);
+    assert_eq!(
+        add_subtask(&pool, user_id, task_id, Some("*")).await.status,
+        StatusCode::OK
+    );
+
+    let other = create_user(&pool).await;
+    assert_eq!(
+        add_subtask(&pool, other, task_id, Some("*")).await.status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[sqlx::test]
+async fn simultaneous_subtask_creations_on_one_version(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3]).await;
+    let seen = version(&pool, task_id).await;
+
+    // La tâche est verrouillée pendant la vérification : une seule création passe
+    let (a, b) = tokio::join!(
+        add_subtask(&pool, user_id, task_id, Some(&seen)),
+        add_subtask(&pool, user_id, task_id, Some(&seen))
+    );
+    let mut statuses = [a.status, b.status];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::PRECONDITION_FAILED]);
+
+    let count = sqlx::query_scalar!(
+        r#"SELECT count(*) AS "count!" FROM subtasks WHERE task_id = $1"#,
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[sqlx::test]
+async fn restore_and_purge_check_the_task_version(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3]).await;
+    let uri = format!("/tasks/{}", task_id);
+
+    let seen = version(&pool, task_id).await;
+    let reply = send(&pool, user_id, "DELETE", &uri, &[("If-Match", &seen)], None).await;
+    assert_eq!(reply.status, StatusCode::OK);
+
+    // La version vue avant la mise à la corbeille est périmée
+    let restore = format!("{}/restore", uri);
+    let purge = format!("{}/permanent", uri);
+    for (method, uri) in [("POST", &restore), ("DELETE", &purge)] {
+        let reply = send(&pool, user_id, method, uri, &[], None).await;
+        assert_eq!(reply.status, StatusCode::PRECONDITION_REQUIRED);
+        let reply = send(&pool, user_id, method, uri, &[("If-Match", &seen)], None).await;
+        assert_eq!(reply.status, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(reply.body["deleted"], true);
+    }
+
+    // La corbeille donne la version à envoyer
+    let trash = send(&pool, user_id, "GET", "/tasks/trash", &[], None)
+        .await
+        .body;
+    let trashed = format!("\"{}\"", trash[0]["version"]);
+    assert_eq!(trashed, version(&pool, task_id).await);
+
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        &restore,
+        &[("If-Match", &trashed)],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    // Restaurée entre-temps : la purge ne trouve plus la tâche dans la corbeille
+    let current = version(&pool, task_id).await;
+    let reply = send(
+        &pool,
+        user_id,
+        "DELETE",
+        &purge,
+        &[("If-Match", &current)],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::NOT_FOUND);
+
+    let reply = send(&pool, user_id, "DELETE", &uri, &[("If-Match", "*")], None).await;
+    assert_eq!(reply.status, StatusCode::OK);
+    let current = version(&pool, task_id).await;
+    let reply = send(
+        &pool,
+        user_id,
+        "DELETE",
+        &purge,
+        &[("If-Match", &current)],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn batch_subtask_creation_needs_the_task_version(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3]).await;
+    let current: i32 = version(&pool, task_id)
+        .await
+        .trim_matches('"')
+        .parse()
+        .unwrap();
+
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        "/batch",
+        &[],
+        Some(json!({
+            "atomic": false,
+            "operations": [
+                { "op": "create_subtask", "task_id": task_id, "title": "Sans version" },
+                { "op": "create_subtask", "task_id": task_id, "title": "Périmée", "version": current - 1 },
+                { "op": "create_subtask", "task_id": task_id, "title": "À jour", "version": current },
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    let statuses: Vec<_> = reply.body["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["status"].as_u64().unwrap())
+        .collect();
+    assert_eq!(statuses, vec![428, 412, 201]);
+}
@@ -0,0 +1,294 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, create_user, send};
+
+/// Envoie un lot de mutations et renvoie le résultat de chacune, par identifiant
+async fn push(pool: &PgPool, user_id: Uuid, mutations: Vec<Value>) -> Vec<Value> {
+    let reply = send(
+        pool,
+        user_id,
+        "POST",
+        "/sync",
+        &[],
+        Some(json!({ "mutations": mutations })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    reply.body["results"].as_array().unwrap().clone()
+}
+
+fn mutation(at: DateTime<Utc>, change: Value) -> Value {
+    let mut mutation = json!({ "id": Uuid::new_v4(), "at": at });
+    mutation
+        .as_object_mut()
+        .unwrap()
+        .extend(change.as_object().unwrap().clone());
+    mutation
+}
+
+fn statuses(results: &[Value]) -> Vec<&str> {
+    results
+        .iter()
+        .map(|r| r["status"].as_str().unwrap())
+        .collect()
+}
+
+async fn title(pool: &PgPool, task_id: i32) -> String {
+    sqlx::query_scalar!("SELECT title FROM tasks WHERE id = $1", task_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn last_writer_wins_on_tasks_subtasks_and_completions(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+    let subtask_id = sqlx::query_scalar!(
+        "INSERT INTO subtasks (task_id, title) VALUES ($1, 'Chapitre') RETURNING id",
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let date = (Utc::now() - Duration::days(1)).date_naive();
+
+    // Les trois éléments modifiés par le même client
+    let changes = |title: &str, completed: bool| {
+        vec![
+            json!({ "type": "update_task", "task_id": task_id, "title": title }),
+            json!({
+                "type": "update_subtask", "task_id": task_id, "subtask_id": subtask_id,
+                "completed": completed
+            }),
+            json!({
+                "type": "set_completion", "task_id": task_id, "date": date,
+                "completed": completed, "note": title
+            }),
+        ]
+    };
+    let batch = |at: DateTime<Utc>, title: &str, completed: bool| {
+        changes(title, completed)
+            .into_iter()
+            .map(|change| mutation(at, change))
+            .collect::<Vec<_>>()
+    };
+    let state = || async {
+        let subtask =
+            sqlx::query_scalar!("SELECT completed FROM subtasks WHERE id = $1", subtask_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let completion = sqlx::query!(
+            "SELECT completed, note FROM task_completions WHERE task_id = $1 AND date = $2",
+            task_id,
+            date
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        (
+            title(&pool, task_id).await,
+            subtask,
+            completion.completed,
+            completion.note,
+        )
+    };
+
+    let at = Utc::now() - Duration::minutes(10);
+    let results = push(&pool, user_id, batch(at, "Premier", true)).await;
+    assert_eq!(statuses(&results), ["applied"; 3]);
+    let first = state().await;
+    assert_eq!(
+        first,
+        (
+            "Premier".to_string(),
+            true,
+            true,
+            Some("Premier".to_string())
+        )
+    );
+
+    // Un autre appareil, resté hors ligne, avait modifié les mêmes éléments plus tôt
+    let results = push(
+        &pool,
+        user_id,
+        batch(at - Duration::minutes(10), "Ancien", false),
+    )
+    .await;
+    assert_eq!(statuses(&results), ["conflict"; 3]);
+    assert_eq!(state().await, first);
+
+    // À égalité, le serveur l'emporte
+    let results = push(&pool, user_id, batch(at, "Égalité", false)).await;
+    assert_eq!(statuses(&results), ["conflict"; 3]);
+    assert_eq!(state().await, first);
+
+    // Plus récent : appliqué
+    let results = push(
+        &pool,
+        user_id,
+        batch(at + Duration::minutes(5), "Récent", false),
+    )
+    .await;
+    assert_eq!(statuses(&results), ["applied"; 3]);
+    assert_eq!(
+        state().await,
+        (
+            "Récent".to_string(),
+            false,
+            false,
+            Some("Récent".to_string())
+        )
+    );
+}
+
+#[sqlx::test]
+async fn future_timestamps_count_as_now(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1]).await;
+
+    // Horloge en avance d'un jour : la modification est datée de sa réception
+    let change = |title: &str| json!({ "type": "update_task", "task_id": task_id, "title": title });
+    let results = push(
+        &pool,
+        user_id,
+        vec![mutation(Utc::now() + Duration::days(1), change("Futur"))],
+    )
+    .await;
+    assert_eq!(statuses(&results), ["applied"]);
+
+    // Une modification faite ensuite, avec une horloge juste, n'est pas écartée
+    let results = push(
+        &pool,
+        user_id,
+        vec![mutation(Utc::now(), change("Maintenant"))],
+    )
+    .await;
+    assert_eq!(statuses(&results), ["applied"]);
+    assert_eq!(title(&pool, task_id).await, "Maintenant");
+}
+
+#[sqlx::test]
+async fn batches_are_ordered_isolated_and_replayable(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1]).await;
+    let other_task = create_task(&pool, create_user(&pool).await, "Swim", &[1]).await;
+    let at = Utc::now() - Duration::minutes(10);
+
+    // Envoyées dans le désordre : appliquées dans l'ordre de leur date
+    let second = mutation(
+        at + Duration::minutes(2),
+        json!({ "type": "update_task", "task_id": task_id, "title": "Deux" }),
+    );
+    let first = mutation(
+        at + Duration::minutes(1),
+        json!({ "type": "update_task", "task_id": task_id, "title": "Un" }),
+    );
+    // Refusées sans bloquer les autres
+    let empty_title = mutation(
+        at,
+        json!({ "type": "update_task", "task_id": task_id, "title": " " }),
+    );
+    let not_mine = mutation(
+        at,
+        json!({ "type": "update_task", "task_id": other_task, "title": "Piscine" }),
+    );
+    let created = mutation(
+        at + Duration::minutes(3),
+        json!({ "type": "create_task", "title": "Write", "days": [2, 2, 4] }),
+    );
+    let batch = vec![
+        second.clone(),
+        first.clone(),
+        empty_title.clone(),
+        not_mine.clone(),
+        created.clone(),
+    ];
+
+    let results = push(&pool, user_id, batch.clone()).await;
+    let result = |m: &Value| results.iter().find(|r| r["id"] == m["id"]).unwrap().clone();
+    assert_eq!(result(&first)["status"], "applied");
+    assert_eq!(result(&second)["status"], "applied");
+    assert_eq!(result(&empty_title)["error"], 400);
+    assert_eq!(result(&not_mine)["error"], 404);
+    let created_id = result(&created)["task_id"].as_i64().unwrap() as i32;
+    assert_eq!(title(&pool, task_id).await, "Deux");
+    assert_eq!(title(&pool, other_task).await, "Swim");
+    let days = sqlx::query_scalar!(
+        "SELECT day_of_week FROM task_days WHERE task_id = $1 ORDER BY day_of_week",
+        created_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(days, vec![2, 4]);
+
+    // Lot renvoyé après une coupure : mêmes résultats, rien n'est rejoué
+    let history = || async {
+        sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM audit_log"#)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    let before = history().await;
+    let replayed = push(&pool, user_id, batch).await;
+    assert_eq!(replayed, results);
+    assert_eq!(history().await, before);
+    let tasks = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM tasks WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(tasks, 2);
+}
+
+#[sqlx::test]
+async fn pull_returns_changes_since_the_cursor(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1]).await;
+    let untouched = create_task(&pool, user_id, "Write", &[2]).await;
+
+    let reply = send(&pool, user_id, "GET", "/sync", &[], None).await;
+    assert_eq!(reply.body["tasks"].as_array().unwrap().len(), 2);
+    let cursor = reply.body["cursor"].as_str().unwrap().to_string();
+
+    push(
+        &pool,
+        user_id,
+        vec![mutation(
+            Utc::now(),
+            json!({ "type": "update_task", "task_id": task_id, "title": "Relire" }),
+        )],
+    )
+    .await;
+
+    let reply = send(
+        &pool,
+        user_id,
+        "GET",
+        &format!("/sync?since={}", cursor),
+        &[],
+        None,
+    )
+    .await;
+    let tasks = reply.body["tasks"].as_array().unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0]["id"], task_id);
+    assert_eq!(tasks[0]["title"], "Relire");
+    assert_ne!(tasks[0]["id"], untouched);
+
+    let reply = send(&pool, user_id, "GET", "/sync?since=abc", &[], None).await;
+    assert_eq!(reply.status, StatusCode::BAD_REQUEST);
+}
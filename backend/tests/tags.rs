@@ -0,0 +1,232 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_user, send};
+
+const EVERY_DAY: [i32; 7] = [1, 2, 3, 4, 5, 6, 7];
+
+async fn create_tag(pool: &PgPool, user_id: Uuid, name: &str) -> i64 {
+    let reply = send(
+        pool,
+        user_id,
+        "POST",
+        "/tags",
+        &[],
+        Some(json!({ "name": name })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::CREATED);
+    reply.body["id"].as_i64().unwrap()
+}
+
+/// Crée une tâche quotidienne avec ces étiquettes
+async fn create_tagged_task(pool: &PgPool, user_id: Uuid, title: &str, tags: &[i64]) {
+    let reply = send(
+        pool,
+        user_id,
+        "POST",
+        "/tasks",
+        &[],
+        Some(json!({ "title": title, "days": EVERY_DAY, "tags": tags })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::CREATED);
+}
+
+/// Titres des tâches d'une liste, dans l'ordre
+fn titles(tasks: &serde_json::Value) -> Vec<&str> {
+    tasks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["title"].as_str().unwrap())
+        .collect()
+}
+
+#[sqlx::test]
+async fn tag_names_are_unique_per_user(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let alice = create_user(&pool).await;
+    let bob = create_user(&pool).await;
+
+    let work = create_tag(&pool, alice, "Travail").await;
+    let home = create_tag(&pool, alice, "Maison").await;
+    // Le même nom est libre pour un autre utilisateur
+    create_tag(&pool, bob, "Travail").await;
+
+    let reply = send(
+        &pool,
+        alice,
+        "POST",
+        "/tags",
+        &[],
+        Some(json!({ "name": "Travail" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::CONFLICT);
+    let reply = send(
+        &pool,
+        alice,
+        "POST",
+        "/tags",
+        &[],
+        Some(json!({ "name": "Sport", "color": "rouge" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::BAD_REQUEST);
+
+    // Renommer vers un nom pris : 409 ; vers un nom libre : l'étiquette suit
+    let uri = format!("/tags/{}", home);
+    let reply = send(
+        &pool,
+        alice,
+        "POST",
+        &uri,
+        &[],
+        Some(json!({ "name": "Travail" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::CONFLICT);
+    let reply = send(
+        &pool,
+        alice,
+        "POST",
+        &uri,
+        &[],
+        Some(json!({ "name": " Foyer ", "color": "#00aa00" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(reply.body["name"], "Foyer");
+    assert_eq!(reply.body["color"], "#00aa00");
+
+    // Étiquette d'un autre utilisateur : introuvable
+    let uri = format!("/tags/{}", work);
+    let reply = send(
+        &pool,
+        bob,
+        "POST",
+        &uri,
+        &[],
+        Some(json!({ "name": "À moi" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        send(&pool, bob, "DELETE", &uri, &[], None).await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    let reply = send(&pool, alice, "GET", "/tags", &[], None).await;
+    let names: Vec<_> = reply
+        .body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].clone())
+        .collect();
+    assert_eq!(names, vec![json!("Foyer"), json!("Travail")]);
+}
+
+#[sqlx::test]
+async fn task_lists_filter_by_tag(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let alice = create_user(&pool).await;
+    let bob = create_user(&pool).await;
+    let work = create_tag(&pool, alice, "Travail").await;
+    let home = create_tag(&pool, alice, "Maison").await;
+    let foreign = create_tag(&pool, bob, "Secret").await;
+
+    create_tagged_task(&pool, alice, "Rapport", &[work]).await;
+    create_tagged_task(&pool, alice, "Ménage", &[home]).await;
+    // Les étiquettes d'un autre utilisateur sont ignorées
+    create_tagged_task(&pool, alice, "Courses", &[home, foreign]).await;
+    create_tagged_task(&pool, alice, "Lecture", &[]).await;
+
+    let reply = send(
+        &pool,
+        alice,
+        "GET",
+        &format!("/tasks?tags={}", home),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(titles(&reply.body), vec!["Ménage", "Courses"]);
+    let courses = &reply.body[1]["tags"];
+    assert_eq!(courses.as_array().unwrap().len(), 1);
+    assert_eq!(courses[0]["name"], "Maison");
+
+    // Plusieurs étiquettes : au moins une
+    let uri = format!("/tasks/all?tags={},{}", work, home);
+    let reply = send(&pool, alice, "GET", &uri, &[], None).await;
+    assert_eq!(
+        titles(&reply.body["tasks"]),
+        vec!["Rapport", "Ménage", "Courses"]
+    );
+    assert_eq!(reply.body["total"], 3);
+
+    let reply = send(&pool, alice, "GET", "/tasks?tags=travail", &[], None).await;
+    assert_eq!(reply.status, StatusCode::BAD_REQUEST);
+
+    // Supprimer une étiquette garde les tâches
+    let reply = send(
+        &pool,
+        alice,
+        "DELETE",
+        &format!("/tags/{}", work),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    let reply = send(&pool, alice, "GET", "/tasks", &[], None).await;
+    assert_eq!(titles(&reply.body).len(), 4);
+    let reply = send(
+        &pool,
+        alice,
+        "GET",
+        &format!("/tasks?tags={}", work),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(reply.body, json!([]));
+}
+
+#[sqlx::test]
+async fn tag_stats_count_scheduled_occurrences(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let alice = create_user(&pool).await;
+    let work = create_tag(&pool, alice, "Travail").await;
+    create_tag(&pool, alice, "Vide").await;
+    create_tagged_task(&pool, alice, "Rapport", &[work]).await;
+    create_tagged_task(&pool, alice, "Mails", &[work]).await;
+
+    let tasks = send(&pool, alice, "GET", "/tasks", &[], None).await.body;
+    let id = tasks[0]["id"].as_i64().unwrap();
+    let reply = send(
+        &pool,
+        alice,
+        "PUT",
+        &format!("/tasks/{}/completion", id),
+        &[],
+        Some(json!({ "completed": true })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+
+    let stats = send(&pool, alice, "GET", "/stats/tags", &[], None)
+        .await
+        .body;
+    assert_eq!(stats[0]["name"], "Travail");
+    assert_eq!(stats[0]["scheduled"], 2);
+    assert_eq!(stats[0]["completed"], 1);
+    assert_eq!(stats[0]["success_rate"], 50);
+    assert_eq!(stats[1]["name"], "Vide");
+    assert_eq!(stats[1]["scheduled"], 0);
+}
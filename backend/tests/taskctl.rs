@@ -0,0 +1,199 @@
+use serde_json::Value;
+use sqlx::PgPool;
+use std::path::PathBuf;
+use std::process::Output;
+use task_manager::admin;
+use task_manager::api;
+use tokio::net::TcpListener;
+use tokio::process::Command;
+use tokio::sync::broadcast;
+
+mod common;
+use common::{apply_migrations, token};
+
+/// `taskctl` avec son propre fichier de configuration, pointé sur ce serveur
+struct Cli {
+    server: String,
+    config: PathBuf,
+}
+
+impl Cli {
+    fn new(server: &str) -> Self {
+        let config = std::env::temp_dir()
+            .join(format!("taskctl-{}", uuid::Uuid::new_v4()))
+            .join("config.json");
+        Self {
+            server: server.to_string(),
+            config,
+        }
+    }
+
+    async fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_taskctl"))
+            .args(args)
+            .env("TASKCTL_CONFIG", &self.config)
+            .env("TASKCTL_SERVER", &self.server)
+            .env("TASKCTL_PASSWORD", "motdepasse-solide")
+            .output()
+            .await
+            .unwrap()
+    }
+}
+
+impl Drop for Cli {
+    fn drop(&mut self) {
+        if let Some(dir) = self.config.parent() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// Sert l'API sur un port libre, le temps du test
+async fn start_server(pool: &PgPool) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let app = api::router(pool.clone(), broadcast::channel(16).0);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{}", address)
+}
+
+async fn days_of(pool: &PgPool, title: &str) -> Vec<i32> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT td.day_of_week FROM task_days td JOIN tasks t ON t.id = td.task_id
+        WHERE t.title = $1 ORDER BY td.day_of_week
+        "#,
+        title
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn invalid_arguments_are_refused_before_any_request() {
+    // Aucun serveur n'écoute : chaque refus vient de l'analyse des arguments
+    let cli = Cli::new("http://127.0.0.1:9");
+
+    for args in [
+        vec!["add", "Sport", "--type", "count"],
+        vec!["done", "Sport", "--undo", "--value", "3"],
+        vec!["stats", "--days", "0"],
+        vec!["stats", "--days", "367"],
+        vec!["archive"],
+        vec!["frobnicate"],
+    ] {
+        let output = cli.run(&args).await;
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+    }
+
+    for days in ["mon,xyz", "8", "lu", ""] {
+        let output = cli.run(&["add", "Sport", "--days", days]).await;
+        assert_eq!(output.status.code(), Some(1), "{}", days);
+        assert!(stderr(&output).contains("jour inconnu"), "{}", days);
+    }
+
+    let output = cli.run(&["--help"]).await;
+    assert!(output.status.success());
+    for command in [
+        "login", "today", "done", "add", "edit", "archive", "stats", "export", "import",
+    ] {
+        assert!(stdout(&output).contains(command), "{}", command);
+    }
+}
+
+#[sqlx::test]
+async fn commands_drive_the_api(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = admin::create_user(&pool, "alice", "motdepasse-solide")
+        .await
+        .unwrap()
+        .unwrap();
+    // Même secret de signature que les autres tests
+    token(user_id);
+    let server = start_server(&pool).await;
+    let cli = Cli::new(&server);
+
+    let output = cli.run(&["today"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("non connecté"));
+
+    let output = cli.run(&["login", "-u", "alice"]).await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    let config: Value =
+        serde_json::from_str(&std::fs::read_to_string(&cli.config).unwrap()).unwrap();
+    assert_eq!(config["server"], server);
+    assert!(config["token"].is_string());
+
+    // Jours en toutes lettres, en numéros, en doublon ou par groupe
+    for (title, days) in [
+        ("Lecture", "daily"),
+        ("Running", "lun, wed,7,WED"),
+        ("Bureau", "weekdays"),
+        ("Repos", "weekend"),
+    ] {
+        let output = cli
+            .run(&["add", title, "--days", days, "-s", "Étirements"])
+            .await;
+        assert!(output.status.success(), "{}", stderr(&output));
+    }
+    assert_eq!(days_of(&pool, "Running").await, vec![1, 3, 7]);
+    assert_eq!(days_of(&pool, "Bureau").await, vec![1, 2, 3, 4, 5]);
+    assert_eq!(days_of(&pool, "Repos").await, vec![6, 7]);
+
+    // Tâche désignée par une partie de son titre, si elle est unique
+    let output = cli.run(&["done", "LECT"]).await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    let completed = sqlx::query_scalar!(
+        r#"
+        SELECT c.completed FROM task_completions c JOIN tasks t ON t.id = c.task_id
+        WHERE t.title = 'Lecture' AND c.date = current_date
+        "#
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(completed);
+    let output = cli.run(&["archive", "r"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("ambigu"));
+    let output = cli.run(&["archive", "rep"]).await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    let active = sqlx::query_scalar!("SELECT active FROM tasks WHERE title = 'Repos'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(!active);
+
+    let output = cli
+        .run(&["edit", "Running", "--title", "Course", "--days", "tue"])
+        .await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(days_of(&pool, "Course").await, vec![2]);
+
+    // L'export se réimporte tel quel
+    let output = cli.run(&["export"]).await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    let export: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(export["tasks"].as_array().unwrap().len(), 4);
+    assert_eq!(export["tasks"][0]["subtasks"][0], "Étirements");
+    let file = cli.config.with_file_name("export.json");
+    std::fs::write(&file, &output.stdout).unwrap();
+    let output = cli.run(&["import", file.to_str().unwrap()]).await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("4 tâche(s) importée(s)"));
+    assert_eq!(days_of(&pool, "Course").await, vec![2, 2]);
+
+    let output = cli.run(&["logout"]).await;
+    assert!(output.status.success());
+    let output = cli.run(&["today"]).await;
+    assert!(stderr(&output).contains("non connecté"));
+}
@@ -0,0 +1,227 @@
+use axum::http::StatusCode;
+use sqlx::PgPool;
+use task_manager::jobs::purge_trash;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, create_user, send};
+
+async fn trash(pool: &PgPool, user_id: Uuid, task_id: i32) {
+    let reply = send(
+        pool,
+        user_id,
+        "DELETE",
+        &format!("/tasks/{}", task_id),
+        &[("If-Match", "*")],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+}
+
+async fn trashed_ids(pool: &PgPool, user_id: Uuid) -> Vec<i64> {
+    let reply = send(pool, user_id, "GET", "/tasks/trash", &[], None).await;
+    assert_eq!(reply.status, StatusCode::OK);
+    reply
+        .body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_i64().unwrap())
+        .collect()
+}
+
+async fn task_exists(pool: &PgPool, task_id: i32) -> bool {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM tasks WHERE id = $1) AS "exists!""#,
+        task_id
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn trash_lists_deleted_tasks_until_restored(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let first = create_task(&pool, user_id, "Read", &[1]).await;
+    let second = create_task(&pool, user_id, "Write", &[2]).await;
+    create_task(&pool, user_id, "Run", &[3]).await;
+    sqlx::query!(
+        "INSERT INTO task_completions (task_id, date) VALUES ($1, current_date)",
+        first
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    trash(&pool, user_id, first).await;
+    trash(&pool, user_id, second).await;
+    // Déjà dans la corbeille : rien ne change
+    let deleted_at = || async {
+        sqlx::query_scalar!("SELECT deleted_at FROM tasks WHERE id = $1", first)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    let before = deleted_at().await;
+    trash(&pool, user_id, first).await;
+    assert_eq!(deleted_at().await, before);
+
+    // Dernière supprimée en premier, purge prévue après la durée de rétention
+    let reply = send(&pool, user_id, "GET", "/tasks/trash", &[], None).await;
+    let items = reply.body.as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["id"], second);
+    assert_eq!(items[1]["title"], "Read");
+    let purge_at = sqlx::query_scalar!(
+        r#"SELECT deleted_at + interval '30 days' AS "purge_at!" FROM tasks WHERE id = $1"#,
+        first
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(items[1]["purge_at"], serde_json::json!(purge_at));
+
+    // La corbeille d'un autre utilisateur est vide
+    let other = create_user(&pool).await;
+    assert!(trashed_ids(&pool, other).await.is_empty());
+    let reply = send(
+        &pool,
+        other,
+        "POST",
+        &format!("/tasks/{}/restore", first),
+        &[("If-Match", "*")],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::NOT_FOUND);
+
+    // Restaurée avec ses complétions
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("/tasks/{}/restore", first),
+        &[("If-Match", "*")],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert!(reply.body["undo_token"].is_string());
+    assert_eq!(trashed_ids(&pool, user_id).await, vec![second as i64]);
+    let completions = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM task_completions WHERE task_id = $1"#,
+        first
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(completions, 1);
+    assert_eq!(deleted_at().await, None);
+}
+
+#[sqlx::test]
+async fn emptying_the_trash_keeps_other_tasks_and_the_history(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let other = create_user(&pool).await;
+    let read = create_task(&pool, user_id, "Read", &[1]).await;
+    let write = create_task(&pool, user_id, "Write", &[2]).await;
+    let kept = create_task(&pool, user_id, "Run", &[3]).await;
+    let others = create_task(&pool, other, "Swim", &[4]).await;
+    trash(&pool, user_id, read).await;
+    trash(&pool, user_id, write).await;
+    trash(&pool, other, others).await;
+
+    let reply = send(&pool, user_id, "DELETE", "/tasks/trash", &[], None).await;
+    assert_eq!(reply.status, StatusCode::OK);
+    assert_eq!(reply.body["purged"], 2);
+    assert!(!task_exists(&pool, read).await);
+    assert!(!task_exists(&pool, write).await);
+    assert!(task_exists(&pool, kept).await);
+    assert!(task_exists(&pool, others).await);
+    assert!(trashed_ids(&pool, user_id).await.is_empty());
+    assert_eq!(trashed_ids(&pool, other).await, vec![others as i64]);
+
+    // Le journal garde la trace des tâches purgées, avec leur dernier état
+    let purged = sqlx::query!(
+        r#"
+        SELECT task_id, actor_id, before->>'title' AS title
+        FROM audit_log
+        WHERE action = 'purge'
+        ORDER BY task_id
+        "#
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(purged.len(), 2);
+    assert_eq!(purged[0].task_id, read);
+    assert_eq!(purged[0].actor_id, Some(user_id));
+    assert_eq!(purged[0].title.as_deref(), Some("Read"));
+    let history = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM audit_log WHERE task_id = $1"#,
+        write
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(history >= 2);
+
+    // Corbeille vide : rien à purger
+    let reply = send(&pool, user_id, "DELETE", "/tasks/trash", &[], None).await;
+    assert_eq!(reply.body["purged"], 0);
+}
+
+#[sqlx::test]
+async fn expired_tasks_are_purged_by_the_job(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let old = create_task(&pool, user_id, "Read", &[1]).await;
+    let recent = create_task(&pool, user_id, "Write", &[2]).await;
+    let active = create_task(&pool, user_id, "Run", &[3]).await;
+    trash(&pool, user_id, old).await;
+    trash(&pool, user_id, recent).await;
+    sqlx::query!(
+        "INSERT INTO subtasks (task_id, title) VALUES ($1, 'Chapitre')",
+        old
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "UPDATE tasks SET deleted_at = now() - interval '31 days' WHERE id = $1",
+        old
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(purge_trash(&pool, 30).await.unwrap(), 1);
+    assert!(!task_exists(&pool, old).await);
+    assert!(task_exists(&pool, recent).await);
+    assert!(task_exists(&pool, active).await);
+    // Sous-tâches parties avec la tâche, purge tracée sans auteur
+    let subtasks = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM subtasks WHERE task_id = $1"#,
+        old
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(subtasks, 0);
+    let actor = sqlx::query_scalar!(
+        "SELECT actor_id FROM audit_log WHERE task_id = $1 AND action = 'purge'",
+        old
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(actor, None);
+
+    // Une rétention nulle vide toute la corbeille
+    assert_eq!(purge_trash(&pool, 0).await.unwrap(), 1);
+    assert!(task_exists(&pool, active).await);
+}
@@ -0,0 +1,154 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use sqlx::PgPool;
+use task_manager::models::CreateTriggerRequest;
+use task_manager::routes_triggers::{create_trigger, delete_trigger, run_trigger};
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, create_user};
+
+/// Crée une URL de déclenchement et renvoie son id et son jeton
+async fn trigger(pool: &PgPool, user_id: Uuid, task_id: i32, action: &str) -> (i32, String) {
+    let (_, Json(created)) = create_trigger(
+        Path(task_id),
+        State(pool.clone()),
+        Extension(user_id),
+        Json(CreateTriggerRequest {
+            action: action.to_string(),
+            amount: None,
+            name: None,
+        }),
+    )
+    .await
+    .unwrap();
+    (
+        created["id"].as_i64().unwrap() as i32,
+        created["token"].as_str().unwrap().to_string(),
+    )
+}
+
+/// Appelle l'URL et renvoie le code HTTP et l'état de l'occurrence
+async fn call(pool: &PgPool, token: &str) -> (StatusCode, Option<serde_json::Value>) {
+    match run_trigger(Path(token.to_string()), State(pool.clone())).await {
+        Ok(Json(body)) => (StatusCode::OK, Some(body)),
+        Err(e) => (e.into_response().status(), None),
+    }
+}
+
+#[sqlx::test]
+async fn trigger_urls_set_todays_completion(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+    let (_, complete) = trigger(&pool, user_id, task_id, "complete").await;
+    let (uncomplete_id, uncomplete) = trigger(&pool, user_id, task_id, "uncomplete").await;
+
+    // Rappeler l'URL ne décoche pas la tâche
+    for _ in 0..2 {
+        let (status, body) = call(&pool, &complete).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.unwrap()["completed"], true);
+    }
+    let (_, body) = call(&pool, &uncomplete).await;
+    assert_eq!(body.unwrap()["completed"], false);
+
+    // Chaque changement est tracé au nom du propriétaire
+    let actions = sqlx::query_scalar!(
+        "SELECT action FROM audit_log WHERE task_id = $1 AND entity = 'completion' AND actor_id = $2",
+        task_id,
+        user_id
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(actions, vec!["trigger", "trigger"]);
+
+    // URL révoquée ou inconnue : 404
+    delete_trigger(
+        Path((task_id, uncomplete_id)),
+        State(pool.clone()),
+        Extension(user_id),
+    )
+    .await
+    .unwrap();
+    assert_eq!(call(&pool, &uncomplete).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(call(&pool, "unknown").await.0, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn trigger_urls_are_rate_limited(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+    let (_, toggle) = trigger(&pool, user_id, task_id, "toggle").await;
+
+    for _ in 0..10 {
+        assert_eq!(call(&pool, &toggle).await.0, StatusCode::OK);
+    }
+    assert_eq!(call(&pool, &toggle).await.0, StatusCode::TOO_MANY_REQUESTS);
+
+    // Les appels refusés ne changent rien : 10 bascules, la tâche reste décochée
+    let completed = sqlx::query_scalar!(
+        "SELECT completed FROM task_completions WHERE task_id = $1 AND date = current_date",
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(!completed);
+
+    // Nouvelle fenêtre : l'URL répond de nouveau
+    sqlx::query!("UPDATE task_triggers SET window_start = window_start - interval '1 minute'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(call(&pool, &toggle).await.0, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn trigger_urls_skip_noops_and_archived_tasks(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+    let (_, complete) = trigger(&pool, user_id, task_id, "complete").await;
+    let (_, uncomplete) = trigger(&pool, user_id, task_id, "uncomplete").await;
+
+    // Décocher une tâche non cochée ne crée pas d'occurrence
+    let (status, body) = call(&pool, &uncomplete).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.unwrap()["completed"], false);
+    let (_, body) = call(&pool, &complete).await;
+    assert_eq!(body.unwrap()["completed"], true);
+    let (_, body) = call(&pool, &complete).await;
+    assert_eq!(body.unwrap()["completed"], true);
+
+    // Seul le changement d'état est tracé
+    let changes = sqlx::query_scalar!(
+        r#"SELECT count(*) AS "count!" FROM audit_log WHERE task_id = $1 AND entity = 'completion'"#,
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(changes, 1);
+
+    // Tâche archivée : 409, l'occurrence reste telle quelle
+    sqlx::query!("UPDATE tasks SET active = false WHERE id = $1", task_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(call(&pool, &uncomplete).await.0, StatusCode::CONFLICT);
+    let completed = sqlx::query_scalar!(
+        "SELECT completed FROM task_completions WHERE task_id = $1 AND date = current_date",
+        task_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(completed);
+}
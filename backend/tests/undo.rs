@@ -0,0 +1,282 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+use common::{Reply, apply_migrations, create_task, create_user, send};
+
+/// Tâche complète, telle que `GET /tasks/:id` la renvoie
+async fn task(pool: &PgPool, user_id: Uuid, task_id: i32) -> serde_json::Value {
+    let reply = send(
+        pool,
+        user_id,
+        "GET",
+        &format!("/tasks/{}", task_id),
+        &[],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    reply.body
+}
+
+async fn undo(pool: &PgPool, user_id: Uuid, reply: &Reply) -> StatusCode {
+    let token = reply.body["undo_token"]
+        .as_str()
+        .expect("jeton d'annulation");
+    send(
+        pool,
+        user_id,
+        "POST",
+        &format!("/undo/{}", token),
+        &[],
+        None,
+    )
+    .await
+    .status
+}
+
+#[sqlx::test]
+async fn undo_delete_and_archive(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3]).await;
+    let uri = format!("/tasks/{}", task_id);
+
+    let deleted = send(&pool, user_id, "DELETE", &uri, &[("If-Match", "*")], None).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    assert_eq!(task(&pool, user_id, task_id).await["deleted"], true);
+    assert_eq!(undo(&pool, user_id, &deleted).await, StatusCode::OK);
+    assert_eq!(task(&pool, user_id, task_id).await["deleted"], false);
+    // Un jeton ne sert qu'une fois
+    assert_eq!(undo(&pool, user_id, &deleted).await, StatusCode::GONE);
+
+    let archived = send(&pool, user_id, "PATCH", &uri, &[("If-Match", "*")], None).await;
+    assert_eq!(archived.body["active"], false);
+    assert_eq!(undo(&pool, user_id, &archived).await, StatusCode::OK);
+    assert_eq!(task(&pool, user_id, task_id).await["active"], true);
+
+    // Le jeton d'un autre utilisateur est inconnu
+    let archived = send(&pool, user_id, "PATCH", &uri, &[("If-Match", "*")], None).await;
+    let other = create_user(&pool).await;
+    assert_eq!(undo(&pool, other, &archived).await, StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test]
+async fn undo_edit_brings_subtasks_back_without_reusing_versions(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3]).await;
+    let uri = format!("/tasks/{}", task_id);
+
+    for title in ["Échauffement", "Course", "Étirements"] {
+        let reply = send(
+            &pool,
+            user_id,
+            "POST",
+            &format!("{}/subtasks", uri),
+            &[("If-Match", "*")],
+            Some(json!({ "title": title })),
+        )
+        .await;
+        assert_eq!(reply.status, StatusCode::OK);
+    }
+    let original = task(&pool, user_id, task_id).await;
+    let subtasks = original["subtasks"].as_array().unwrap().clone();
+
+    // Édition : titre, une sous-tâche renommée, une gardée, une supprimée
+    let edited = send(
+        &pool,
+        user_id,
+        "POST",
+        &uri,
+        &[("If-Match", "*")],
+        Some(json!({
+            "title": "Course à pied",
+            "days": [2, 4],
+            "subtasks": [
+                { "id": subtasks[0]["id"], "title": "Échauffement", "version": subtasks[0]["version"] },
+                { "id": subtasks[1]["id"], "title": "Fractionné", "version": subtasks[1]["version"] },
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(edited.status, StatusCode::OK);
+    let after_edit = task(&pool, user_id, task_id).await;
+
+    assert_eq!(undo(&pool, user_id, &edited).await, StatusCode::OK);
+    let restored = task(&pool, user_id, task_id).await;
+    assert_eq!(restored["title"], "Sport");
+    assert_eq!(restored["days"], json!([1, 2, 3]));
+    let restored_subtasks = restored["subtasks"].as_array().unwrap();
+    let ids: Vec<_> = restored_subtasks.iter().map(|s| s["id"].clone()).collect();
+    let original_ids: Vec<_> = subtasks.iter().map(|s| s["id"].clone()).collect();
+    assert_eq!(ids, original_ids);
+    assert_eq!(restored_subtasks[1]["title"], "Course");
+
+    // Aucune version n'est réutilisée : la sous-tâche renommée avance au lieu de repartir
+    // à 1, la sous-tâche recréée dépasse toute version qu'elle a eue
+    let renamed_during_edit = &after_edit["subtasks"][1];
+    assert!(restored_subtasks[1]["version"].as_i64() > renamed_during_edit["version"].as_i64());
+    assert!(restored_subtasks[2]["version"].as_i64() > subtasks[2]["version"].as_i64());
+    // La sous-tâche inchangée garde sa version
+    assert_eq!(
+        restored_subtasks[0]["version"],
+        after_edit["subtasks"][0]["version"]
+    );
+
+    // Une écriture sur une version vue avant l'annulation est refusée
+    let stale = format!("\"{}\"", subtasks[1]["version"]);
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("{}/subtasks/{}", uri, subtasks[1]["id"]),
+        &[("If-Match", &stale)],
+        Some(json!({ "title": "Écrasé" })),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::PRECONDITION_FAILED);
+}
+
+#[sqlx::test]
+async fn undo_reorder_restores_priorities(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let every_day = [1, 2, 3, 4, 5, 6, 7];
+    let first = create_task(&pool, user_id, "Lecture", &every_day).await;
+    let second = create_task(&pool, user_id, "Sport", &every_day).await;
+
+    let order = |ids: [i32; 2]| Some(json!({ "ordered_task_ids": ids }));
+    let reply = send(
+        &pool,
+        user_id,
+        "POST",
+        "/tasks/priorities",
+        &[],
+        order([first, second]),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+    let reordered = send(
+        &pool,
+        user_id,
+        "POST",
+        "/tasks/priorities",
+        &[],
+        order([second, first]),
+    )
+    .await;
+    let titles = |tasks: serde_json::Value| -> Vec<String> {
+        tasks
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["title"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let today = send(&pool, user_id, "GET", "/tasks", &[], None).await.body;
+    assert_eq!(titles(today), ["Sport", "Lecture"]);
+
+    assert_eq!(undo(&pool, user_id, &reordered).await, StatusCode::OK);
+    let today = send(&pool, user_id, "GET", "/tasks", &[], None).await.body;
+    assert_eq!(titles(today), ["Lecture", "Sport"]);
+}
+
+#[sqlx::test]
+async fn undo_is_refused_after_a_later_change_or_once_expired(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3]).await;
+    let uri = format!("/tasks/{}", task_id);
+
+    let renamed = send(
+        &pool,
+        user_id,
+        "POST",
+        &uri,
+        &[("If-Match", "*")],
+        Some(json!({ "title": "Course" })),
+    )
+    .await;
+    send(
+        &pool,
+        user_id,
+        "POST",
+        &uri,
+        &[("If-Match", "*")],
+        Some(json!({ "title": "Natation" })),
+    )
+    .await;
+    // Modifiée depuis : l'annulation écraserait le changement suivant
+    assert_eq!(undo(&pool, user_id, &renamed).await, StatusCode::CONFLICT);
+    assert_eq!(task(&pool, user_id, task_id).await["title"], "Natation");
+
+    let archived = send(&pool, user_id, "PATCH", &uri, &[("If-Match", "*")], None).await;
+    sqlx::query!("UPDATE undo_tokens SET created_at = created_at - interval '11 minutes'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(undo(&pool, user_id, &archived).await, StatusCode::GONE);
+    assert_eq!(task(&pool, user_id, task_id).await["active"], false);
+}
+
+#[sqlx::test]
+async fn undo_batch_toggle_restores_subtasks(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Sport", &[1, 2, 3, 4, 5, 6, 7]).await;
+    for title in ["Échauffement", "Course"] {
+        send(
+            &pool,
+            user_id,
+            "POST",
+            &format!("/tasks/{}/subtasks", task_id),
+            &[("If-Match", "*")],
+            Some(json!({ "title": title })),
+        )
+        .await;
+    }
+    sqlx::query!(
+        "UPDATE subtasks SET completed = true WHERE task_id = $1 AND title = 'Course'",
+        task_id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let subtasks = || async {
+        sqlx::query_scalar!(
+            "SELECT completed FROM subtasks WHERE task_id = $1 ORDER BY id",
+            task_id
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+    };
+
+    // La bascule coche toutes les sous-tâches avec la tâche
+    let batch = send(
+        &pool,
+        user_id,
+        "POST",
+        "/batch",
+        &[],
+        Some(json!({ "operations": [{ "op": "toggle", "task_id": task_id }] })),
+    )
+    .await;
+    assert_eq!(batch.body["results"][0]["status"], 200);
+    assert_eq!(subtasks().await, [true, true]);
+
+    // L'annulation rend à chaque sous-tâche son état d'avant
+    assert_eq!(undo(&pool, user_id, &batch).await, StatusCode::OK);
+    assert_eq!(subtasks().await, [false, true]);
+    let completed = sqlx::query_scalar!(
+        "SELECT completed FROM task_completions WHERE task_id = $1 AND date = current_date",
+        task_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+    assert_ne!(completed, Some(true));
+}
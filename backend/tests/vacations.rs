@@ -0,0 +1,113 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, create_user, send};
+
+const EVERY_DAY: [i32; 7] = [1, 2, 3, 4, 5, 6, 7];
+
+/// Pause d'une semaine autour d'aujourd'hui, limitée à `task_ids` si fourni
+async fn create_vacation(pool: &PgPool, user_id: Uuid, task_ids: Option<&[i32]>) -> StatusCode {
+    let today = sqlx::query_scalar!(r#"SELECT current_date AS "date!""#)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    let mut body = json!({
+        "start_date": today - chrono::Duration::days(3),
+        "end_date": today + chrono::Duration::days(3),
+    });
+    if let Some(task_ids) = task_ids {
+        body["task_ids"] = json!(task_ids);
+    }
+    send(pool, user_id, "POST", "/vacations", &[], Some(body))
+        .await
+        .status
+}
+
+async fn excused_today(pool: &PgPool, task_id: i32) -> bool {
+    sqlx::query_scalar!(
+        r#"SELECT is_excused($1, current_date) AS "excused!""#,
+        task_id
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn vacation_without_selection_pauses_every_task(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let read = create_task(&pool, user_id, "Read", &EVERY_DAY).await;
+    let run = create_task(&pool, user_id, "Run", &EVERY_DAY).await;
+
+    assert_eq!(
+        create_vacation(&pool, user_id, None).await,
+        StatusCode::CREATED
+    );
+    assert!(excused_today(&pool, read).await);
+    assert!(excused_today(&pool, run).await);
+
+    let vacations = send(&pool, user_id, "GET", "/vacations", &[], None)
+        .await
+        .body;
+    assert_eq!(vacations[0]["all_tasks"], true);
+    assert_eq!(vacations[0]["task_ids"], json!([]));
+}
+
+#[sqlx::test]
+async fn purging_the_selected_task_does_not_pause_the_others(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let read = create_task(&pool, user_id, "Read", &EVERY_DAY).await;
+    let run = create_task(&pool, user_id, "Run", &EVERY_DAY).await;
+    let other = create_user(&pool).await;
+    let foreign = create_task(&pool, other, "Swim", &EVERY_DAY).await;
+
+    // Une sélection sans tâche de l'utilisateur est refusée
+    assert_eq!(
+        create_vacation(&pool, user_id, Some(&[foreign])).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    assert_eq!(
+        create_vacation(&pool, user_id, Some(&[read])).await,
+        StatusCode::CREATED
+    );
+    assert!(excused_today(&pool, read).await);
+    assert!(!excused_today(&pool, run).await);
+
+    // La purge supprime la ligne de vacation_tasks : la pause ne couvre plus rien
+    let uri = format!("/tasks/{}", read);
+    let reply = send(&pool, user_id, "DELETE", &uri, &[("If-Match", "*")], None).await;
+    assert_eq!(reply.status, StatusCode::OK);
+    let reply = send(
+        &pool,
+        user_id,
+        "DELETE",
+        &format!("{}/permanent", uri),
+        &[("If-Match", "*")],
+        None,
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::OK);
+
+    assert!(!excused_today(&pool, run).await);
+    let vacations = send(&pool, user_id, "GET", "/vacations", &[], None)
+        .await
+        .body;
+    assert_eq!(vacations[0]["all_tasks"], false);
+    assert_eq!(vacations[0]["task_ids"], json!([]));
+
+    // Les statistiques du jour gardent la tâche restante comme prévue
+    let scheduled = sqlx::query_scalar!(
+        "SELECT scheduled FROM task_daily_stats WHERE task_id = $1 AND date = current_date",
+        run
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(scheduled);
+}
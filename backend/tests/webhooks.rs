@@ -0,0 +1,342 @@
+use axum::http::StatusCode;
+use axum::{Router, body::Bytes, extract::State, http::HeaderMap, routing::post};
+use sqlx::PgPool;
+use task_manager::audit::{self, AuditEntry};
+use task_manager::webhooks::{self, MAX_ATTEMPTS};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+mod common;
+use common::{apply_migrations, create_task, create_user, send};
+
+/// Serveur HTTP local qui transmet les en-têtes et le corps de chaque POST reçu
+async fn stub_endpoint() -> (String, mpsc::UnboundedReceiver<(HeaderMap, Bytes)>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let app = Router::new()
+        .route(
+            "/*path",
+            post(
+                |State(sender): State<mpsc::UnboundedSender<(HeaderMap, Bytes)>>,
+                 headers: HeaderMap,
+                 body: Bytes| async move {
+                    sender.send((headers, body)).unwrap();
+                    "ok"
+                },
+            ),
+        )
+        .with_state(sender);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (url, receiver)
+}
+
+async fn create_webhook(pool: &PgPool, user_id: Uuid, url: &str, events: &[&str]) -> i32 {
+    let events: Vec<String> = events.iter().map(|e| e.to_string()).collect();
+    sqlx::query_scalar!(
+        "INSERT INTO webhooks (user_id, url, secret, events) VALUES ($1, $2, 'secret', $3) RETURNING id",
+        user_id,
+        url,
+        &events
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn task_events_are_delivered_signed(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let (url, mut received) = stub_endpoint().await;
+    let user_id = create_user(&pool).await;
+    let webhook_id = create_webhook(&pool, user_id, &format!("{}/hook", url), &[]).await;
+    let filtered_id = create_webhook(
+        &pool,
+        user_id,
+        &format!("{}/deleted", url),
+        &["task.deleted"],
+    )
+    .await;
+    let task_id = create_task(&pool, user_id, "Read", &[1, 2, 3, 4, 5, 6, 7]).await;
+
+    let mut conn = pool.acquire().await.unwrap();
+    let after = serde_json::json!({ "title": "Read", "deleted": false });
+    audit::record(
+        &mut conn,
+        AuditEntry::task(user_id, task_id, "create").after(Some(after)),
+    )
+    .await
+    .unwrap();
+    let today = chrono::Local::now().date_naive();
+    audit::record(
+        &mut conn,
+        AuditEntry::completion(user_id, task_id, today, "complete")
+            .before(Some(serde_json::json!({ "completed": false })))
+            .after(Some(serde_json::json!({ "completed": true }))),
+    )
+    .await
+    .unwrap();
+    drop(conn);
+
+    // Seul le webhook sans filtre est concerné
+    let queued = sqlx::query_scalar!("SELECT webhook_id FROM webhook_deliveries ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(queued, vec![webhook_id, webhook_id]);
+    assert!(!queued.contains(&filtered_id));
+
+    let sender = webhooks::Sender::local();
+    assert_eq!(webhooks::send_due(&pool, &sender).await.unwrap(), 2);
+
+    let mut events = Vec::new();
+    for _ in 0..2 {
+        let (headers, body) = received.recv().await.unwrap();
+        let timestamp: i64 = headers["x-webhook-timestamp"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            headers["x-webhook-signature"],
+            webhooks::sign("secret", timestamp, &body)
+        );
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["task_id"], task_id);
+        assert_eq!(
+            headers["x-webhook-event"],
+            payload["event"].as_str().unwrap()
+        );
+        events.push(payload["event"].as_str().unwrap().to_string());
+    }
+    events.sort();
+    assert_eq!(events, vec!["completion.toggled", "task.created"]);
+
+    let delivered = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM webhook_deliveries WHERE status = 'delivered'"#
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(delivered, 2);
+}
+
+#[sqlx::test]
+async fn failed_deliveries_back_off_then_give_up(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1]).await;
+
+    // Port fermé : chaque essai échoue
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    drop(listener);
+    create_webhook(&pool, user_id, &url, &[]).await;
+
+    let mut conn = pool.acquire().await.unwrap();
+    audit::record(
+        &mut conn,
+        AuditEntry::task(user_id, task_id, "create").after(Some(serde_json::json!({}))),
+    )
+    .await
+    .unwrap();
+    drop(conn);
+
+    let sender = webhooks::Sender::local();
+    assert_eq!(webhooks::send_due(&pool, &sender).await.unwrap(), 1);
+
+    // Nouvel essai dans 30 s, rien d'autre à envoyer d'ici là
+    let delivery = sqlx::query!(
+        r#"
+        SELECT id, status, attempts,
+               EXTRACT(EPOCH FROM next_attempt_at - now())::float8 AS "wait!"
+        FROM webhook_deliveries
+        "#
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(delivery.status, "pending");
+    assert_eq!(delivery.attempts, 1);
+    assert!((25.0..=30.0).contains(&delivery.wait));
+    assert_eq!(webhooks::send_due(&pool, &sender).await.unwrap(), 0);
+
+    // Dernier essai échoué : l'envoi est abandonné, chaque essai reste au journal
+    sqlx::query!(
+        "UPDATE webhook_deliveries SET attempts = $1 - 1, next_attempt_at = now() WHERE id = $2",
+        MAX_ATTEMPTS,
+        delivery.id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert_eq!(webhooks::send_due(&pool, &sender).await.unwrap(), 1);
+
+    let status = sqlx::query_scalar!(
+        "SELECT status FROM webhook_deliveries WHERE id = $1",
+        delivery.id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(status, "failed");
+    let attempts = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM webhook_delivery_attempts WHERE delivery_id = $1 AND error IS NOT NULL"#,
+        delivery.id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(attempts, 2);
+}
+
+#[test]
+fn only_public_addresses_are_allowed() {
+    for ip in ["8.8.8.8", "2606:4700:4700::1111"] {
+        assert!(webhooks::is_public_ip(ip.parse().unwrap()), "{}", ip);
+    }
+    for ip in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.16.0.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "::1",
+        "::",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:127.0.0.1",
+    ] {
+        assert!(!webhooks::is_public_ip(ip.parse().unwrap()), "{}", ip);
+    }
+}
+
+#[sqlx::test]
+async fn webhook_urls_must_be_public(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let user_id = create_user(&pool).await;
+    let create = |url: &str| {
+        send(
+            &pool,
+            user_id,
+            "POST",
+            "/webhooks",
+            &[],
+            Some(serde_json::json!({ "url": url })),
+        )
+    };
+
+    for url in [
+        "http://localhost:8080/hook",
+        "http://api.localhost/hook",
+        "http://127.0.0.1/hook",
+        "http://2130706433/hook",
+        "http://10.0.0.5/hook",
+        "http://192.168.1.10/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://[::1]/hook",
+        "http://[fd12::1]/hook",
+        "ftp://example.com/hook",
+    ] {
+        assert_eq!(create(url).await.status, StatusCode::BAD_REQUEST, "{}", url);
+    }
+
+    let created = create("https://example.com/hook").await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    let updated = send(
+        &pool,
+        user_id,
+        "POST",
+        &format!("/webhooks/{}", created.body["id"]),
+        &[],
+        Some(serde_json::json!({ "url": "http://127.0.0.1:5432/" })),
+    )
+    .await;
+    assert_eq!(updated.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn deliveries_never_reach_internal_addresses(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let (url, mut received) = stub_endpoint().await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1]).await;
+
+    // Enregistrés avant la vérification, ou visant un nom résolu en adresse locale
+    let port = url.rsplit(':').next().unwrap();
+    create_webhook(&pool, user_id, &format!("{}/ip", url), &[]).await;
+    create_webhook(
+        &pool,
+        user_id,
+        &format!("http://localhost:{}/nom", port),
+        &[],
+    )
+    .await;
+
+    let mut conn = pool.acquire().await.unwrap();
+    audit::record(
+        &mut conn,
+        AuditEntry::task(user_id, task_id, "create").after(Some(serde_json::json!({}))),
+    )
+    .await
+    .unwrap();
+    drop(conn);
+
+    let sender = webhooks::Sender::public();
+    assert_eq!(webhooks::send_due(&pool, &sender).await.unwrap(), 2);
+    assert!(received.try_recv().is_err());
+
+    let attempts = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM webhook_delivery_attempts WHERE status_code IS NULL AND error IS NOT NULL"#
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(attempts, 2);
+}
+
+#[sqlx::test]
+async fn redirects_are_not_followed(pool: PgPool) {
+    apply_migrations(&pool).await;
+    let (target, mut received) = stub_endpoint().await;
+    let user_id = create_user(&pool).await;
+    let task_id = create_task(&pool, user_id, "Read", &[1]).await;
+
+    // Serveur qui renvoie vers un autre
+    let location = format!("{}/cible", target);
+    let app = Router::new().route(
+        "/hook",
+        post(move || async move {
+            (
+                StatusCode::TEMPORARY_REDIRECT,
+                [("location", location.clone())],
+            )
+        }),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    create_webhook(&pool, user_id, &url, &[]).await;
+
+    let mut conn = pool.acquire().await.unwrap();
+    audit::record(
+        &mut conn,
+        AuditEntry::task(user_id, task_id, "create").after(Some(serde_json::json!({}))),
+    )
+    .await
+    .unwrap();
+    drop(conn);
+
+    let sender = webhooks::Sender::local();
+    assert_eq!(webhooks::send_due(&pool, &sender).await.unwrap(), 1);
+    assert!(received.try_recv().is_err());
+    let status_code = sqlx::query_scalar!("SELECT status_code FROM webhook_delivery_attempts")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status_code, Some(307));
+}
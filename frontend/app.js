@@ -1,7 +1,7 @@
 // =========================================================
 // CONFIGURATION
 // =========================================================
-const API_URL = '/api';
+const API_URL = '/api/v1';
 let currentChart = null;
 
 // =========================================================
@@ -39,6 +39,12 @@ async function apiFetch(path, options = {}) {
             return null;
         }
 
+        if (response.status === 412) {
+            // Version périmée : la ressource a été modifiée ailleurs (autre onglet ou appareil)
+            showNotification('Modifié entre-temps sur un autre appareil : rechargez pour voir la dernière version', 'error');
+            return null;
+        }
+
         if (!response.ok) {
             const errorText = await response.text();
             console.error(`API error ${response.status}:`, errorText);
@@ -73,6 +79,22 @@ async function apiFetch(path, options = {}) {
 // =========================================================
 // GESTION DES TÂCHES
 // =========================================================
+
+// Toutes les tâches non supprimées, en suivant les pages de GET /tasks/all (pour les exports)
+async function fetchAllTaskPages() {
+    const tasks = [];
+    let cursor = null;
+    do {
+        const params = new URLSearchParams({ limit: 200 });
+        if (cursor) params.set('cursor', cursor);
+        const page = await apiFetch(`/tasks/all?${params}`);
+        if (!page) return null;
+        tasks.push(...page.tasks);
+        cursor = page.next_cursor;
+    } while (cursor);
+    return tasks;
+}
+
 async function fetchTasks() {
     const loading = document.getElementById('tasks-loading');
     const empty = document.getElementById('tasks-empty');
@@ -129,10 +151,10 @@ function renderTasks(tasks) {
                         <input type="checkbox" 
                                id="subtask-${subtask.id}" 
                                ${subtask.completed ? 'checked' : ''}
-                               onchange="toggleSubtask(${task.id}, ${subtask.id})">
+                               onchange="toggleSubtask(${task.id}, ${subtask.id}, ${subtask.version})">
                         <label for="subtask-${subtask.id}" class="checkbox-custom small"></label>
                     </div>
-                    <span class="subtask-title" onclick="toggleSubtask(${task.id}, ${subtask.id})">
+                    <span class="subtask-title" onclick="toggleSubtask(${task.id}, ${subtask.id}, ${subtask.version})">
                         ${escapeHtml(subtask.title)}
                     </span>
                     <div class="subtask-priority">#${subtask.priority + 1}</div>
@@ -148,11 +170,11 @@ function renderTasks(tasks) {
                         <input type="checkbox" 
                                id="task-${task.id}" 
                                ${task.completed ? 'checked' : ''}
-                               onchange="toggleTask(${task.id})"
+                               onchange="toggleTask(${task.id}, this.checked)"
                                ${task.has_subtasks ? 'data-has-subtasks="true"' : ''}>
                         <label for="task-${task.id}" class="checkbox-custom"></label>
                     </div>
-                    <div class="task-title" onclick="toggleTask(${task.id})">
+                    <div class="task-title" onclick="toggleTask(${task.id}, ${!task.completed})">
                         ${escapeHtml(task.title)}
                         ${task.has_subtasks ? 
                             `<span class="subtask-indicator">
@@ -181,6 +203,7 @@ function renderTasks(tasks) {
                            class="subtask-input" 
                            placeholder="Ajouter une sous-tâche..." 
                            data-task-id="${task.id}"
+                           data-version="${task.version}"
                            onkeypress="if(event.key === 'Enter') addSubtask(${task.id}, this)">
                     <button class="btn-icon" onclick="addSubtask(${task.id}, this.previousElementSibling)">
                         <i class="fas fa-plus"></i>
@@ -261,7 +284,7 @@ function initTaskSorting() {
 
 async function updateTaskPriorities(orderedIds) {
     try {
-        await apiFetch('/tasks/priorities', {
+        const result = await apiFetch('/tasks/priorities', {
             method: 'POST',
             body: JSON.stringify({ ordered_task_ids: orderedIds })
         });
@@ -274,15 +297,19 @@ async function updateTaskPriorities(orderedIds) {
             }
         });
         
-        showNotification('Priorités mises à jour', 'success');
+        showUndoNotification('Priorités mises à jour', result?.undo_token, fetchTasks);
     } catch (error) {
         showNotification('Erreur lors de la mise à jour des priorités', 'error');
     }
 }
 
-async function toggleTask(taskId) {
+// Envoie l'état voulu plutôt qu'une bascule : un double clic ou un renvoi ne l'inverse pas
+async function toggleTask(taskId, completed) {
     try {
-        await apiFetch(`/tasks/${taskId}/toggle`, { method: 'POST' });
+        await apiFetch(`/tasks/${taskId}/completion`, {
+            method: 'PUT',
+            body: JSON.stringify({ completed })
+        });
         await fetchTasks();
         await fetchStats(); // Rafraîchir les statistiques
     } catch (error) {
@@ -290,10 +317,99 @@ async function toggleTask(taskId) {
     }
 }
 
-async function toggleSubtask(taskId, subtaskId) {
+// En-tête de concurrence optimiste pour les écritures sur une version connue
+function ifMatch(version) {
+    return { 'If-Match': `"${version}"` };
+}
+
+// Flux temps réel des modifications (Server-Sent Events).
+// EventSource ne permet pas d'envoyer l'en-tête Authorization : le flux est lu avec fetch.
+// Les rafales d'événements sont regroupées en un seul appel à onChange.
+function subscribeToEvents(onChange) {
+    let timer = null;
+    const notify = (type) => {
+        clearTimeout(timer);
+        timer = setTimeout(() => onChange(type), 300);
+    };
+
+    async function connect(reconnecting) {
+        const token = localStorage.getItem('token');
+        if (!token) return;
+
+        try {
+            const response = await fetch(`${API_URL}/events`, {
+                headers: { 'Authorization': `Bearer ${token}`, 'Accept': 'text/event-stream' }
+            });
+            if (response.status === 401) return;
+            if (!response.ok || !response.body) throw new Error(`HTTP ${response.status}`);
+
+            // Après une coupure, tout recharger pour rattraper les événements manqués
+            if (reconnecting) notify('resync');
+
+            const reader = response.body.pipeThrough(new TextDecoderStream()).getReader();
+            let buffer = '';
+            while (true) {
+                const { value, done } = await reader.read();
+                if (done) break;
+                buffer += value;
+
+                // Les événements sont séparés par une ligne vide
+                const blocks = buffer.split('\n\n');
+                buffer = blocks.pop();
+                for (const block of blocks) {
+                    const type = block.split('\n')
+                        .find(line => line.startsWith('event:'))
+                        ?.slice(6).trim();
+                    if (type) notify(type);
+                }
+            }
+        } catch (err) {
+            console.error('Flux temps réel interrompu :', err);
+        }
+
+        setTimeout(() => connect(true), 5000);
+    }
+
+    connect(false);
+}
+
+// Notifications Web Push (rappels et récapitulatif) : enregistre le service worker,
+// abonne le navigateur avec la clé VAPID du serveur et transmet l'abonnement
+async function enablePushNotifications() {
+    if (!('serviceWorker' in navigator) || !('PushManager' in window)) {
+        showNotification('Notifications non prises en charge par ce navigateur', 'error');
+        return false;
+    }
+    if (await Notification.requestPermission() !== 'granted') return false;
+
+    const key = await apiFetch('/notifications/push/key');
+    if (!key) return false;
+
+    const registration = await navigator.serviceWorker.register('sw.js');
+    const subscription = await registration.pushManager.getSubscription()
+        || await registration.pushManager.subscribe({
+            userVisibleOnly: true,
+            applicationServerKey: base64UrlToBytes(key.public_key)
+        });
+
+    const saved = await apiFetch('/notifications/push/subscriptions', {
+        method: 'POST',
+        body: JSON.stringify(subscription.toJSON())
+    });
+    if (saved) showNotification('Notifications activées', 'success');
+    return Boolean(saved);
+}
+
+function base64UrlToBytes(value) {
+    const base64 = (value + '='.repeat((4 - value.length % 4) % 4)).replace(/-/g, '+').replace(/_/g, '/');
+    return Uint8Array.from(atob(base64), c => c.charCodeAt(0));
+}
+
+async function toggleSubtask(taskId, subtaskId, version) {
     try {
         await apiFetch('/subtasks/toggle', {
             method: 'POST',
+            headers: ifMatch(version),
             body: JSON.stringify({ task_id: taskId, subtask_id: subtaskId })
         });
         await fetchTasks();
@@ -311,6 +427,7 @@ async function addSubtask(taskId, inputElement) {
     try {
         const result = await apiFetch(`/tasks/${taskId}/subtasks`, {
             method: 'POST',
+            headers: ifMatch(inputElement.dataset.version),
             body: JSON.stringify({ title })
         });
         
@@ -691,6 +808,28 @@ function showNotification(message, type = 'info') {
     }, 5000);
 }
 
+// Notification avec un bouton « Annuler » tant que le jeton d'annulation est valide
+function showUndoNotification(message, undoToken, onUndo) {
+    showNotification(message, 'success');
+    if (!undoToken) return;
+
+    const container = document.getElementById('notification-container');
+    const notification = container.lastElementChild;
+
+    const undoButton = document.createElement('button');
+    undoButton.className = 'notification-undo';
+    undoButton.textContent = 'Annuler';
+    undoButton.addEventListener('click', async () => {
+        notification.remove();
+        const result = await apiFetch(`/undo/${undoToken}`, { method: 'POST' });
+        if (result) {
+            showNotification('Modification annulée', 'info');
+            if (onUndo) onUndo();
+        }
+    });
+    notification.insertBefore(undoButton, notification.querySelector('.notification-close'));
+}
+
 function createNotificationContainer() {
     const container = document.createElement('div');
     container.id = 'notification-container';
@@ -738,6 +877,10 @@ document.addEventListener('DOMContentLoaded', function() {
     if (document.getElementById('tasks-list')) {
         fetchTasks();
         fetchStats();
+        subscribeToEvents(() => {
+            fetchTasks();
+            fetchStats();
+        });
     }
     
     // Initialiser les tooltips de la heatmap
@@ -755,7 +898,12 @@ document.addEventListener('DOMContentLoaded', function() {
 // =========================================================
 window.appShowNotification = showNotification;
 window.showNotification = showNotification;
+window.showUndoNotification = showUndoNotification;
 window.apiFetch = apiFetch;
+window.ifMatch = ifMatch;
+window.subscribeToEvents = subscribeToEvents;
+window.enablePushNotifications = enablePushNotifications;
+window.fetchAllTaskPages = fetchAllTaskPages;
 window.logout = logout;
 window.fetchTasks = fetchTasks;
 window.fetchStats = fetchStats;
@@ -764,7 +912,11 @@ window.fetchStats = fetchStats;
 if (typeof module !== 'undefined' && module.exports) {
     module.exports = {
         apiFetch,
+        ifMatch,
+        subscribeToEvents,
+        fetchAllTaskPages,
         showNotification,
+        showUndoNotification,
         logout,
         fetchTasks,
         fetchStats
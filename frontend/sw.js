@@ -0,0 +1,18 @@
+// Service worker des notifications Web Push : le serveur envoie la notification
+// (rappel ou récapitulatif) en JSON chiffré, le navigateur la déchiffre avant cet événement
+self.addEventListener('push', (event) => {
+    const notification = event.data ? event.data.json() : { title: 'Task Manager', body: '' };
+    event.waitUntil(self.registration.showNotification(notification.title, {
+        body: notification.body,
+        tag: `${notification.kind}-${notification.task_id ?? 'all'}-${notification.date}`,
+        data: { url: '/index.html' }
+    }));
+});
+
+self.addEventListener('notificationclick', (event) => {
+    event.notification.close();
+    event.waitUntil(self.clients.matchAll({ type: 'window' }).then((windows) => {
+        const open = windows.find((w) => w.url.includes(event.notification.data.url));
+        return open ? open.focus() : self.clients.openWindow(event.notification.data.url);
+    }));
+});